@@ -7,6 +7,13 @@ macro_rules! conversion {
                 $out(self.0 / rhs.0)
             }
         }
+
+        impl CheckedDiv<$b> for $a {
+            type Output = $out;
+            fn checked_div(self, rhs: $b) -> Result<$out, UnitError> {
+                $out::try_new(self.0.raw() / rhs.0.raw())
+            }
+        }
     };
     ($a:tt * $b:tt => $out:tt) => {
         impl std::ops::Mul<$b> for $a {
@@ -15,12 +22,48 @@ macro_rules! conversion {
                 $out(self.0 * rhs.0)
             }
         }
+
+        impl CheckedMul<$b> for $a {
+            type Output = $out;
+            fn checked_mul(self, rhs: $b) -> Result<$out, UnitError> {
+                $out::try_new(self.0.raw() * rhs.0.raw())
+            }
+        }
     };
 }
 
 #[macro_export]
 macro_rules! unit {
     ($type:ty: $unit:expr) => {
+        impl $type {
+            /// Creates a new value, returning an error if `value` is NaN or infinite.
+            pub fn try_new(value: RawScalar) -> Result<Self, UnitError> {
+                match Scalar::try_new(value) {
+                    Some(value) => Ok(Self(value)),
+                    None => Err(UnitError::new($unit, value)),
+                }
+            }
+
+            /// Creates a new value in a const context. Fails to compile if `value` is NaN or infinite.
+            pub const fn new_const(value: RawScalar) -> Self {
+                assert!(value.is_finite(), "unit constants must be finite");
+                // This is safe because we just asserted that the value is finite
+                Self(unsafe { Scalar::const_unchecked_new(value) })
+            }
+
+            pub fn raw(self) -> RawScalar {
+                self.0.raw()
+            }
+
+            pub fn checked_add(self, other: Self) -> Result<Self, UnitError> {
+                Self::try_new(self.0.raw() + other.0.raw())
+            }
+
+            pub fn checked_sub(self, other: Self) -> Result<Self, UnitError> {
+                Self::try_new(self.0.raw() - other.0.raw())
+            }
+        }
+
         impl std::fmt::Debug for $type {
             fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
                 use std::io::Write;
@@ -28,6 +71,20 @@ macro_rules! unit {
             }
         }
 
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $type {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                crate::units::serialize::serialize(self.0, $unit, serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $type {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                crate::units::serialize::deserialize(deserializer, $unit).map(Self)
+            }
+        }
+
         impl std::ops::AddAssign<$type> for $type {
             fn add_assign(&mut self, other: $type) {
                 self.0 += other.0;
@@ -79,14 +136,21 @@ macro_rules! unit {
         }
 
         impl std::ops::Div<Self> for $type {
-            type Output = R32;
+            type Output = Scalar;
 
-            fn div(self, other: Self) -> R32 {
+            fn div(self, other: Self) -> Scalar {
                 self.0 / other.0
             }
         }
-        impl std::ops::MulAssign<f32> for $type {
-            fn mul_assign(&mut self, other: f32) {
+        impl std::ops::Mul<Scalar> for $type {
+            type Output = Self;
+
+            fn mul(self, other: Scalar) -> Self {
+                Self(self.0 * other)
+            }
+        }
+        impl std::ops::MulAssign<RawScalar> for $type {
+            fn mul_assign(&mut self, other: RawScalar) {
                 self.0 *= other;
             }
         }
@@ -0,0 +1,283 @@
+use super::{MaterialColor, Phase};
+use crate::material::{MaterialDefinition, MaterialId, MaterialProperties, MaterialRegistry};
+use crate::units::{
+    scalar, Density, OhmMeter, RawScalar, SpecificEnergy, SpecificHeatCapacity, ThermalConductivity,
+};
+use specs::{Component, DenseVecStorage};
+use std::fmt;
+
+/// How the thermal conductivity of a mixture is derived from the conductivity of its parts.
+///
+/// All models weigh the parts by their volume fraction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MixingModel {
+    /// The parts conduct side by side, e.g. layers along the direction of the heat flow. This is the upper bound.
+    Parallel,
+    /// The parts conduct one after another, e.g. layers across the direction of the heat flow. This is the lower bound.
+    Series,
+    /// Weighted geometric mean, which lies between [Parallel](MixingModel::Parallel) and [Series](MixingModel::Series). This matches how [HeatSystem](crate::system::HeatSystem) combines neighbouring tiles.
+    #[default]
+    Geometric,
+}
+
+/// The materials that a tile is made of, for tiles that are a mixture or an alloy (mud, slag, brine, etc).
+///
+/// The [Heat](super::Heat) of these tiles uses the effective properties of the mixture, see [Mixture](crate::material::Mixture).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "CompositionDefinition")
+)]
+pub struct Composition {
+    /// Mass fractions of each material, these always add up to 1
+    parts: Vec<(MaterialId, RawScalar)>,
+    pub mixing_model: MixingModel,
+}
+
+/// A [Composition] as it is deserialized, before it is normalized by [Composition::new].
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct CompositionDefinition {
+    parts: Vec<(MaterialId, RawScalar)>,
+    #[serde(default)]
+    mixing_model: MixingModel,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<CompositionDefinition> for Composition {
+    type Error = CompositionError;
+
+    fn try_from(definition: CompositionDefinition) -> Result<Self, Self::Error> {
+        Composition::new(definition.parts, definition.mixing_model)
+    }
+}
+
+impl Component for Composition {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Composition {
+    /// Create a new composition from the relative mass of each material. The masses are normalized, so `[(a, 3.0), (b, 1.0)]` is 75% `a` and 25% `b`.
+    pub fn new(
+        parts: impl IntoIterator<Item = (MaterialId, RawScalar)>,
+        mixing_model: MixingModel,
+    ) -> Result<Self, CompositionError> {
+        let mut merged: Vec<(MaterialId, RawScalar)> = Vec::new();
+        for (material, amount) in parts {
+            if !amount.is_finite() || amount < 0.0 {
+                return Err(CompositionError::InvalidFraction(material, amount));
+            }
+            match merged.iter_mut().find(|(id, _)| *id == material) {
+                Some((_, existing)) => *existing += amount,
+                None => merged.push((material, amount)),
+            }
+        }
+        merged.retain(|(_, amount)| *amount > 0.0);
+        let total: RawScalar = merged.iter().map(|(_, amount)| amount).sum();
+        if merged.is_empty() || !total.is_finite() {
+            return Err(CompositionError::Empty);
+        }
+        for (_, amount) in &mut merged {
+            *amount /= total;
+        }
+        merged.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(Self {
+            parts: merged,
+            mixing_model,
+        })
+    }
+
+    pub fn pure(material: MaterialId) -> Self {
+        Self {
+            parts: vec![(material, 1.0)],
+            mixing_model: MixingModel::default(),
+        }
+    }
+
+    /// The materials and their mass fractions, sorted from the largest to the smallest fraction.
+    pub fn parts(&self) -> &[(MaterialId, RawScalar)] {
+        &self.parts
+    }
+
+    /// The material that makes up most of the mass of this composition.
+    pub fn dominant(&self) -> MaterialId {
+        self.parts[0].0
+    }
+
+    /// The properties of each part in `phase`, with their mass fraction. Returns `None` if a part is not in `registry`.
+    fn properties(
+        &self,
+        registry: &MaterialRegistry,
+        phase: Phase,
+    ) -> Option<Vec<(MaterialProperties, RawScalar)>> {
+        self.parts
+            .iter()
+            .map(|(id, fraction)| Some((registry.get(*id)?.properties(phase), *fraction)))
+            .collect()
+    }
+
+    /// Mass-weighted average of the specific heat capacity of each part.
+    pub fn specific_heat_capacity(
+        &self,
+        registry: &MaterialRegistry,
+        phase: Phase,
+    ) -> Option<SpecificHeatCapacity> {
+        let capacity = self
+            .properties(registry, phase)?
+            .iter()
+            .map(|(properties, fraction)| fraction * properties.specific_heat_capacity.raw())
+            .sum();
+        Some(SpecificHeatCapacity(scalar(capacity)))
+    }
+
+    /// Mass-weighted average of the heat of fusion of each part. Parts without one count as 0.
+    pub fn heat_of_fusion(&self, registry: &MaterialRegistry) -> Option<SpecificEnergy> {
+        self.latent_heat(registry, |definition| definition.heat_of_fusion)
+    }
+
+    /// Mass-weighted average of the heat of vaporization of each part. Parts without one count as 0.
+    pub fn heat_of_vaporization(&self, registry: &MaterialRegistry) -> Option<SpecificEnergy> {
+        self.latent_heat(registry, |definition| definition.heat_of_vaporization)
+    }
+
+    fn latent_heat(
+        &self,
+        registry: &MaterialRegistry,
+        heat: impl Fn(&MaterialDefinition) -> Option<SpecificEnergy>,
+    ) -> Option<SpecificEnergy> {
+        let mut latent = SpecificEnergy::new_const(0.0);
+        for (id, fraction) in &self.parts {
+            if let Some(part) = heat(registry.get(*id)?) {
+                latent += part * scalar(*fraction);
+            }
+        }
+        Some(latent)
+    }
+
+    /// The density of the mixture, assuming the parts don't take up less space when mixed.
+    pub fn density(&self, registry: &MaterialRegistry, phase: Phase) -> Option<Density> {
+        Some(density(&self.properties(registry, phase)?))
+    }
+
+    pub fn thermal_conductivity(
+        &self,
+        registry: &MaterialRegistry,
+        phase: Phase,
+    ) -> Option<ThermalConductivity> {
+        let parts = self.properties(registry, phase)?;
+        let fractions = volume_fractions(&parts)
+            .map(|(properties, phi)| (properties.thermal_conductivity.raw(), phi));
+        let conductivity = match self.mixing_model {
+            MixingModel::Parallel => fractions.map(|(k, phi)| phi * k).sum(),
+            MixingModel::Series => 1.0 / fractions.map(|(k, phi)| phi / k).sum::<RawScalar>(),
+            MixingModel::Geometric => fractions.map(|(k, phi)| k.powf(phi)).product(),
+        };
+        Some(ThermalConductivity(scalar(conductivity)))
+    }
+
+    /// The electrical resistivity of the mixture, mixed with the same [MixingModel] as the thermal conductivity. Insulating parts have no conductivity, so a mixture with an insulating part only conducts if it is mixed in [parallel](MixingModel::Parallel).
+    ///
+    /// The outer `Option` is `None` if a part is not in `registry`, the inner one if the mixture is an insulator.
+    pub fn electrical_resistivity(
+        &self,
+        registry: &MaterialRegistry,
+        phase: Phase,
+    ) -> Option<Option<OhmMeter>> {
+        let parts = self.properties(registry, phase)?;
+        let fractions = volume_fractions(&parts).map(|(properties, phi)| {
+            let conductivity = properties
+                .electrical_resistivity
+                .map_or(0.0, |resistivity| 1.0 / resistivity.raw());
+            (conductivity, phi)
+        });
+        let conductivity: RawScalar = match self.mixing_model {
+            MixingModel::Parallel => fractions.map(|(sigma, phi)| phi * sigma).sum(),
+            MixingModel::Series => {
+                let resistivity: RawScalar = fractions.map(|(sigma, phi)| phi / sigma).sum();
+                1.0 / resistivity
+            }
+            MixingModel::Geometric => fractions.map(|(sigma, phi)| sigma.powf(phi)).product(),
+        };
+        if conductivity > 0.0 {
+            Some(OhmMeter::try_new(1.0 / conductivity).ok())
+        } else {
+            Some(None)
+        }
+    }
+
+    /// Mass-weighted blend of the colors of each part.
+    pub fn color(&self, registry: &MaterialRegistry, phase: Phase) -> Option<MaterialColor> {
+        let parts = self.properties(registry, phase)?;
+        Some(MaterialColor(crate::sys::Color::weighted_average(
+            parts
+                .iter()
+                .map(|(properties, fraction)| (properties.color.0, *fraction)),
+        )))
+    }
+}
+
+/// The density of a mixture of `parts`, given their mass fractions.
+fn density(parts: &[(MaterialProperties, RawScalar)]) -> Density {
+    let specific_volume: RawScalar = parts
+        .iter()
+        .map(|(properties, fraction)| fraction / properties.density.raw())
+        .sum();
+    Density(scalar(1.0 / specific_volume))
+}
+
+/// The volume fraction of each part, in the same order as `parts`.
+fn volume_fractions(
+    parts: &[(MaterialProperties, RawScalar)],
+) -> impl Iterator<Item = (&MaterialProperties, RawScalar)> {
+    let density = density(parts).raw();
+    parts.iter().map(move |(properties, fraction)| {
+        (properties, fraction * density / properties.density.raw())
+    })
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CompositionError {
+    /// The composition does not contain any material
+    Empty,
+    /// A part has a negative or non-finite amount
+    InvalidFraction(MaterialId, RawScalar),
+}
+
+impl fmt::Display for CompositionError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompositionError::Empty => write!(fmt, "A composition needs at least one material"),
+            CompositionError::InvalidFraction(id, amount) => {
+                write!(fmt, "Invalid amount {} for material {:?}", amount, id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CompositionError {}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_normalizes() {
+        let composition: Composition =
+            ron::de::from_str("(parts: [((0), 3.0), ((1), 1.0), ((0), 0.0)])").unwrap();
+        let fractions: Vec<RawScalar> = composition
+            .parts()
+            .iter()
+            .map(|(_, fraction)| *fraction)
+            .collect();
+        assert_eq!(fractions, vec![0.75, 0.25]);
+        assert_eq!(composition.mixing_model, MixingModel::Geometric);
+    }
+
+    #[test]
+    fn deserialize_rejects_empty() {
+        assert!(ron::de::from_str::<Composition>("(parts: [])").is_err());
+        assert!(ron::de::from_str::<Composition>("(parts: [((0), -1.0)])").is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use crate::material::MaterialId;
+use crate::units::{Kelvin, KilogramPerSecond, MeterSquared, SpecificEnergy, Watt};
+use specs::{Component, DenseVecStorage};
+
+/// Gets rid of heat through its large surface, by convection into the gas around it and by thermal radiation.
+///
+/// How much a radiator can reject depends on how hot it is and on its surroundings: it works best in cold, dense air, and only radiates in a vacuum. The radiation depends on the emissivity of its material.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Radiator {
+    /// The area of the fins, on top of the faces of its tile
+    pub surface_area: MeterSquared,
+    /// The temperature that the [capacity](Radiator::capacity) is given for, usually the hottest the radiator is meant to run at
+    pub rated_temperature: Kelvin,
+    capacity: Watt,
+    rejected: Watt,
+}
+
+impl Radiator {
+    pub fn new(surface_area: MeterSquared, rated_temperature: Kelvin) -> Self {
+        Radiator {
+            surface_area,
+            rated_temperature,
+            capacity: Watt::new_const(0.0),
+            rejected: Watt::new_const(0.0),
+        }
+    }
+
+    /// The heat that the radiator would reject at its rated temperature, in the surroundings that it had during the last tick
+    pub fn capacity(self) -> Watt {
+        self.capacity
+    }
+
+    /// The heat that the radiator rejected during the last tick
+    pub fn rejected(self) -> Watt {
+        self.rejected
+    }
+
+    pub(crate) fn set_throughput(&mut self, capacity: Watt, rejected: Watt) {
+        self.capacity = capacity;
+        self.rejected = rejected;
+    }
+}
+
+impl Component for Radiator {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Gets rid of heat by evaporating the liquid next to it.
+///
+/// The tower cools itself with the heat of vaporization of the liquid, and the vapour is carried off into the sky, so it does not add to the air on the map. It can only evaporate as much as the air around it takes up: it does nothing without air, and less when the air already holds vapour of the fluid.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoolingTower {
+    /// The liquid that is evaporated, usually water
+    pub fluid: MaterialId,
+    /// The heat that it takes to evaporate the fluid, about 2.26e6 J/kg for water
+    pub heat_of_vaporization: SpecificEnergy,
+    /// How fast the tower evaporates the fluid when it is surrounded by completely dry air
+    pub max_evaporation: KilogramPerSecond,
+    capacity: Watt,
+    rejected: Watt,
+}
+
+impl CoolingTower {
+    pub fn new(
+        fluid: MaterialId,
+        heat_of_vaporization: SpecificEnergy,
+        max_evaporation: KilogramPerSecond,
+    ) -> Self {
+        CoolingTower {
+            fluid,
+            heat_of_vaporization,
+            max_evaporation,
+            capacity: Watt::new_const(0.0),
+            rejected: Watt::new_const(0.0),
+        }
+    }
+
+    /// The heat that the tower could reject in the air that was around it during the last tick
+    pub fn capacity(self) -> Watt {
+        self.capacity
+    }
+
+    /// The heat that the tower rejected during the last tick
+    pub fn rejected(self) -> Watt {
+        self.rejected
+    }
+
+    pub(crate) fn set_throughput(&mut self, capacity: Watt, rejected: Watt) {
+        self.capacity = capacity;
+        self.rejected = rejected;
+    }
+}
+
+impl Component for CoolingTower {
+    type Storage = DenseVecStorage<Self>;
+}
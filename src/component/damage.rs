@@ -0,0 +1,46 @@
+use crate::units::{scalar, RawScalar, Scalar};
+use specs::{Component, DenseVecStorage};
+
+/// How badly a solid is damaged, from 0 (intact) to 1 (broken).
+///
+/// A damaged solid is weaker, and a solid that is broken collapses. Solids without this component are intact.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Damage(RawScalar);
+
+impl Damage {
+    /// From this much damage on, a solid has visible cracks
+    pub const CRACKED: RawScalar = 0.5;
+
+    pub fn amount(self) -> Scalar {
+        scalar(self.0)
+    }
+
+    /// Add to the damage, up to 1
+    pub fn add(&mut self, amount: Scalar) {
+        self.0 = (self.0 + amount.raw()).clamp(0.0, 1.0);
+    }
+
+    /// The fraction of its original strength that the solid has left
+    pub fn remaining_strength(self) -> Scalar {
+        scalar(1.0 - self.0)
+    }
+
+    pub fn is_cracked(self) -> bool {
+        self.0 >= Self::CRACKED
+    }
+
+    pub fn is_broken(self) -> bool {
+        self.0 >= 1.0
+    }
+}
+
+impl Component for Damage {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Default for Damage {
+    fn default() -> Self {
+        Damage(0.0)
+    }
+}
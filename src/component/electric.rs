@@ -0,0 +1,32 @@
+use crate::units::{Ampere, Volt, Watt};
+use specs::{Component, DenseVecStorage};
+
+/// Holds a conductive tile at a fixed voltage, like the terminal of a battery or a connection to the ground.
+///
+/// Current only flows through a circuit that is connected to electrodes at different voltages.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Electrode {
+    pub voltage: Volt,
+}
+
+impl Component for Electrode {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The state of a conductive tile in a circuit, as solved by the [ElectricalSystem](crate::system::ElectricalSystem).
+///
+/// Tiles that are not connected to an [Electrode] don't have this component.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Electricity {
+    pub voltage: Volt,
+    /// The current that flows through the tile. For an electrode, this is the current that it drives into the circuit
+    pub current: Ampere,
+    /// The electrical power that is turned into heat in the tile
+    pub power: Watt,
+}
+
+impl Component for Electricity {
+    type Storage = DenseVecStorage<Self>;
+}
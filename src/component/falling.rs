@@ -0,0 +1,26 @@
+use crate::units::{Meter, MeterPerSecond};
+use specs::{Component, DenseVecStorage};
+
+/// An entity that is falling, or flowing sideways, because of gravity.
+///
+/// This is added and removed by the [GravitySystem](crate::system::GravitySystem). The entity moves to the next tile every time it has travelled a full tile.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Falling {
+    pub speed: MeterPerSecond,
+    /// How far the entity has travelled since it last moved to a new tile
+    pub distance: Meter,
+}
+
+impl Component for Falling {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Default for Falling {
+    fn default() -> Self {
+        Falling {
+            speed: MeterPerSecond::new_const(0.0),
+            distance: Meter::new_const(0.0),
+        }
+    }
+}
@@ -1,12 +1,24 @@
 use crate::material::Material;
-use crate::units::{Joule, Kelvin, Kilogram, SpecificHeatCapacity, ThermalConductivity};
+use crate::units::{
+    CheckedDiv, CheckedMul, Joule, Kelvin, Kilogram, SpecificEnergy, SpecificHeatCapacity,
+    ThermalConductivity, UnitError,
+};
 use specs::{Component, VecStorage};
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Heat {
     pub capacity: SpecificHeatCapacity,
     pub conductivity: ThermalConductivity,
     pub joules: Joule,
+    /// The heat per kilogram that a tile at its melting or boiling point has taken up to change into the next phase, or given off to change back. The [PhaseSystem](crate::system::PhaseSystem) keeps the temperature at that point until the phase change is done
+    #[cfg_attr(feature = "serde", serde(default = "no_latent_heat"))]
+    pub latent: SpecificEnergy,
+}
+
+#[cfg(feature = "serde")]
+fn no_latent_heat() -> SpecificEnergy {
+    SpecificEnergy::new_const(0.0)
 }
 
 impl Component for Heat {
@@ -25,6 +37,7 @@ impl Heat {
             capacity: specific_heat_capacity,
             conductivity: thermal_conductivity,
             joules,
+            latent: SpecificEnergy::new_const(0.0),
         }
     }
 
@@ -34,7 +47,30 @@ impl Heat {
         Self::from_material_specs(temp, mass, capacity, conductivity)
     }
 
+    /// Change the mass of this tile from `old_mass` to `new_mass`, keeping its temperature the same. The heat is left alone if its temperature is not finite.
+    pub fn try_set_mass(
+        &mut self,
+        old_mass: Kilogram,
+        new_mass: Kilogram,
+    ) -> Result<(), UnitError> {
+        let temperature = self.try_temperature(old_mass)?;
+        self.joules = (self.capacity * new_mass).checked_mul(temperature)?;
+        Ok(())
+    }
+
+    /// Mix `added` mass at `temperature` into this tile. Call this before updating the [Mass](crate::component::Mass) of the tile.
+    pub fn try_add_mass(&mut self, added: Kilogram, temperature: Kelvin) -> Result<(), UnitError> {
+        let joules = (self.capacity * added).checked_mul(temperature)?;
+        self.joules = self.joules.checked_add(joules)?;
+        Ok(())
+    }
+
     pub fn temperature(self, mass: Kilogram) -> Kelvin {
         self.joules / (self.capacity * mass)
     }
+
+    /// Same as [temperature](Heat::temperature), but returns an error instead of panicking when the temperature is not finite, e.g. when `mass` is zero.
+    pub fn try_temperature(self, mass: Kilogram) -> Result<Kelvin, UnitError> {
+        self.joules.checked_div(self.capacity * mass)
+    }
 }
@@ -0,0 +1,25 @@
+use crate::units::Kelvin;
+use specs::{Component, DenseVecStorage, NullStorage};
+
+/// Carries heat across the map far faster than any material conducts it.
+///
+/// Heat pipes connect to the heat pipes and heat-exchanging machines next to them: [HeatExchanger]s, [Boiler](super::Boiler)s, [SteamEngine](super::SteamEngine)s and [Reactor](super::Reactor)s. All heat pipes that are connected share their heat, so they always have the same temperature.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeatPipe {
+    /// A heat pipe that gets hotter than this bursts. It stops carrying heat, and its tile breaks
+    pub max_temperature: Kelvin,
+}
+
+impl Component for HeatPipe {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Marks any other machine that heat pipes connect to. They already connect to every [Boiler](super::Boiler), [SteamEngine](super::SteamEngine) and [Reactor](super::Reactor).
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeatExchanger;
+
+impl Component for HeatExchanger {
+    type Storage = NullStorage<Self>;
+}
@@ -0,0 +1,58 @@
+use crate::grid_storage::Position;
+use crate::units::{scalar, Kelvin, RawScalar, Scalar};
+use noisy_float::types::r32;
+use specs::{Component, DenseVecStorage};
+
+/// One of the four tiles next to a building.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    Left,
+    Up,
+    Right,
+    Down,
+}
+
+impl Side {
+    /// The position of the tile on this side of `position`
+    pub fn of(self, position: Position) -> Position {
+        let (x, y) = match self {
+            Side::Left => (-1.0, 0.0),
+            Side::Up => (0.0, -1.0),
+            Side::Right => (1.0, 0.0),
+            Side::Down => (0.0, 1.0),
+        };
+        Position {
+            x: position.x + r32(x),
+            y: position.y + r32(y),
+        }
+    }
+}
+
+/// Moves heat from the tile on its cold side to the tile on its hot side, against the temperature difference, using the power of the [Consumer](super::Consumer) on the same entity.
+///
+/// For every joule of power, the pump takes [coefficient_of_performance](HeatPump::coefficient_of_performance) joules of heat from the cold side. The hot side gets that heat and the power.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeatPump {
+    pub cold_side: Side,
+    pub hot_side: Side,
+    /// The heat taken from the cold side per joule of power. A fridge reaches about 3. The pump never does better than a perfect (Carnot) heat pump between the two sides
+    pub coefficient_of_performance: RawScalar,
+}
+
+impl HeatPump {
+    /// The heat taken from the cold side per joule of power, when the sides are at `cold` and `hot`
+    pub fn effective_coefficient(self, cold: Kelvin, hot: Kelvin) -> Scalar {
+        let configured = scalar(self.coefficient_of_performance);
+        if hot <= cold {
+            // Heat flows this way by itself, the pump only has to help it along
+            return configured;
+        }
+        configured.min(cold / (hot - cold))
+    }
+}
+
+impl Component for HeatPump {
+    type Storage = DenseVecStorage<Self>;
+}
@@ -0,0 +1,359 @@
+use crate::material::{ItemId, ItemRegistry, Material, MaterialRegistry};
+use crate::units::{scalar, HeatCapacity, Kelvin, Kilogram, RawScalar};
+use specs::{Component, DenseVecStorage};
+
+/// A number of items of the same kind, at the same temperature.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stack {
+    pub item: ItemId,
+    pub count: u32,
+    pub temperature: Kelvin,
+}
+
+impl Stack {
+    /// The mass of all items in the stack. Returns `None` if the item is not in `items`
+    pub fn mass(self, items: &ItemRegistry) -> Option<Kilogram> {
+        Some(items.get(self.item)?.mass * scalar(self.count as RawScalar))
+    }
+
+    /// The heat capacity of all items in the stack, in the phase that their material has at the temperature of the stack. Returns `None` if the item is not in `items`, or its material is not in `materials`
+    pub fn heat_capacity(
+        self,
+        items: &ItemRegistry,
+        materials: &MaterialRegistry,
+    ) -> Option<HeatCapacity> {
+        let material = items.get(self.item)?.material;
+        let phase = materials.get(material)?.phase_at(self.temperature);
+        Some(
+            materials
+                .material(material, phase)?
+                .specific_heat_capacity()
+                * self.mass(items)?,
+        )
+    }
+
+    /// Add `other` to this stack. Both stacks hold the same items, so the new temperature is the mean weighted by their counts
+    fn merge(&mut self, other: Stack) {
+        let total = self.count + other.count;
+        if total == 0 {
+            return;
+        }
+        let weight = scalar(other.count as RawScalar / total as RawScalar);
+        self.temperature += (other.temperature - self.temperature) * weight;
+        self.count = total;
+    }
+}
+
+/// A single place in an [Inventory], which holds at most one stack.
+#[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Slot {
+    /// Never holds a stack without items, the slot is empty instead
+    stack: Option<Stack>,
+    /// Only this item can be put into the slot, if it is set
+    pub filter: Option<ItemId>,
+}
+
+impl Slot {
+    pub fn stack(self) -> Option<Stack> {
+        self.stack
+    }
+
+    fn accepts(self, item: ItemId) -> bool {
+        match self.filter {
+            Some(filter) => filter == item,
+            None => true,
+        }
+    }
+}
+
+/// Holds items in a fixed number of slots, like a chest.
+///
+/// The items keep their temperature, and the [InventorySystem](crate::system::InventorySystem) exchanges heat between them and the [Heat] of the entity. Their mass is not added to the [Mass](crate::component::Mass) of the entity.
+///
+/// [Heat]: crate::component::Heat
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Inventory {
+    slots: Vec<Slot>,
+}
+
+impl Inventory {
+    /// An empty inventory without filters
+    pub fn new(slots: usize) -> Self {
+        Inventory {
+            slots: vec![Slot::default(); slots],
+        }
+    }
+
+    pub fn slots(&self) -> &[Slot] {
+        &self.slots
+    }
+
+    /// Only allow `item` in the slot at `index`, or allow every item if it is `None`. Items that are already in the slot stay there. Returns `false` if there is no such slot
+    pub fn set_filter(&mut self, index: usize, filter: Option<ItemId>) -> bool {
+        match self.slots.get_mut(index) {
+            Some(slot) => {
+                slot.filter = filter;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn stacks(&self) -> impl Iterator<Item = &Stack> {
+        self.slots.iter().filter_map(|slot| slot.stack.as_ref())
+    }
+
+    pub(crate) fn stacks_mut(&mut self) -> impl Iterator<Item = &mut Stack> {
+        self.slots.iter_mut().filter_map(|slot| slot.stack.as_mut())
+    }
+
+    /// The number of `item`s in all slots
+    pub fn count(&self, item: ItemId) -> u32 {
+        self.stacks()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    /// The mass of all items in the inventory. Returns `None` if one of the items is not in `items`
+    pub fn mass(&self, items: &ItemRegistry) -> Option<Kilogram> {
+        let mut mass = Kilogram::new_const(0.0);
+        for stack in self.stacks() {
+            mass += stack.mass(items)?;
+        }
+        Some(mass)
+    }
+
+    /// Put as many items of `stack` into the inventory as fit, and return the ones that did not fit.
+    ///
+    /// The items are added to the stacks of the same item first, and then to empty slots that accept them. Empty slots with a filter for the item are used before the slots without a filter. Items that are not in `items` never fit.
+    pub fn insert(&mut self, items: &ItemRegistry, mut stack: Stack) -> Option<Stack> {
+        let stack_size = match items.get(stack.item) {
+            Some(item) => item.stack_size,
+            None => return Some(stack),
+        };
+        for slot in &mut self.slots {
+            if stack.count == 0 {
+                return None;
+            }
+            if let Some(existing) = slot.stack.as_mut() {
+                if existing.item == stack.item && existing.count < stack_size {
+                    let moved = stack.count.min(stack_size - existing.count);
+                    existing.merge(Stack {
+                        count: moved,
+                        ..stack
+                    });
+                    stack.count -= moved;
+                }
+            }
+        }
+        for filtered in &[true, false] {
+            for slot in &mut self.slots {
+                if stack.count == 0 {
+                    return None;
+                }
+                if slot.stack.is_some()
+                    || slot.filter.is_some() != *filtered
+                    || !slot.accepts(stack.item)
+                {
+                    continue;
+                }
+                let moved = stack.count.min(stack_size);
+                slot.stack = Some(Stack {
+                    count: moved,
+                    ..stack
+                });
+                stack.count -= moved;
+            }
+        }
+        if stack.count == 0 {
+            None
+        } else {
+            Some(stack)
+        }
+    }
+
+    /// Take up to `count` of `item` out of the inventory, starting with the last slot. The stack that is returned has the mean temperature of the items that were taken, and is `None` if there were none
+    pub fn take(&mut self, item: ItemId, count: u32) -> Option<Stack> {
+        let mut taken: Option<Stack> = None;
+        let mut left = count;
+        for slot in self.slots.iter_mut().rev() {
+            if left == 0 {
+                break;
+            }
+            let existing = match slot.stack.as_mut() {
+                Some(existing) if existing.item == item => existing,
+                _ => continue,
+            };
+            let moved = left.min(existing.count);
+            let part = Stack {
+                count: moved,
+                ..*existing
+            };
+            match taken.as_mut() {
+                Some(taken) => taken.merge(part),
+                None => taken = Some(part),
+            }
+            existing.count -= moved;
+            left -= moved;
+            if existing.count == 0 {
+                slot.stack = None;
+            }
+        }
+        taken
+    }
+
+    /// How many more of `item` fit in the inventory, which is 0 if it is not in `items`
+    pub fn space_for(&self, items: &ItemRegistry, item: ItemId) -> u32 {
+        let stack_size = match items.get(item) {
+            Some(item) => item.stack_size,
+            None => return 0,
+        };
+        self.slots
+            .iter()
+            .map(|slot| match slot.stack {
+                Some(stack) if stack.item == item => stack_size.saturating_sub(stack.count),
+                Some(_) => 0,
+                None if slot.accepts(item) => stack_size,
+                None => 0,
+            })
+            .sum()
+    }
+
+    /// Move up to `count` of `item` into `to`, as far as they fit. Returns the number of items that were moved
+    pub fn transfer(
+        &mut self,
+        to: &mut Inventory,
+        items: &ItemRegistry,
+        item: ItemId,
+        count: u32,
+    ) -> u32 {
+        let count = count.min(to.space_for(items, item));
+        match self.take(item, count) {
+            Some(stack) => {
+                // `to` has room for all of them, so nothing is left over
+                let _ = to.insert(items, stack);
+                stack.count
+            }
+            None => 0,
+        }
+    }
+}
+
+impl Component for Inventory {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    fn registries() -> (ItemRegistry, ItemId, ItemId) {
+        let materials =
+            MaterialRegistry::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/materials.ron"))
+                .unwrap();
+        let items = ItemRegistry::load(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/assets/items.ron"),
+            &materials,
+        )
+        .unwrap();
+        let ore = items.by_name("iron ore").unwrap();
+        let coal = items.by_name("coal").unwrap();
+        (items, ore, coal)
+    }
+
+    fn stack(item: ItemId, count: u32, temperature: RawScalar) -> Stack {
+        Stack {
+            item,
+            count,
+            temperature: Kelvin::new_const(temperature),
+        }
+    }
+
+    fn counts(inventory: &Inventory) -> Vec<Option<u32>> {
+        inventory
+            .slots()
+            .iter()
+            .map(|slot| slot.stack.map(|stack| stack.count))
+            .collect()
+    }
+
+    #[test]
+    fn stacks_up_to_the_stack_size() {
+        let (items, ore, _) = registries();
+        assert_eq!(items.get(ore).unwrap().stack_size, 50);
+        let mut inventory = Inventory::new(3);
+
+        assert!(inventory.insert(&items, stack(ore, 120, 300.0)).is_none());
+        assert_eq!(counts(&inventory), vec![Some(50), Some(50), Some(20)]);
+        assert_eq!(inventory.space_for(&items, ore), 30);
+
+        let left = inventory.insert(&items, stack(ore, 40, 300.0)).unwrap();
+        assert_eq!(left.count, 10);
+        assert_eq!(inventory.count(ore), 150);
+    }
+
+    #[test]
+    fn filtered_slots_fill_first() {
+        let (items, ore, coal) = registries();
+        let mut inventory = Inventory::new(3);
+        assert!(inventory.set_filter(2, Some(coal)));
+        assert!(!inventory.set_filter(3, Some(coal)));
+
+        assert!(inventory.insert(&items, stack(coal, 30, 300.0)).is_none());
+        assert_eq!(counts(&inventory), vec![None, None, Some(30)]);
+        assert!(inventory.insert(&items, stack(ore, 10, 300.0)).is_none());
+        assert_eq!(counts(&inventory), vec![Some(10), None, Some(30)]);
+        // The filtered stack is topped up before the coal takes an unfiltered slot
+        assert!(inventory.insert(&items, stack(coal, 60, 300.0)).is_none());
+        assert_eq!(counts(&inventory), vec![Some(10), Some(40), Some(50)]);
+        // Ore never goes into the slot for coal
+        assert_eq!(inventory.space_for(&items, ore), 40);
+    }
+
+    #[test]
+    fn transfer_keeps_the_count() {
+        let (items, ore, _) = registries();
+        let mut from = Inventory::new(2);
+        let mut to = Inventory::new(1);
+        assert!(from.insert(&items, stack(ore, 80, 300.0)).is_none());
+        assert!(to.insert(&items, stack(ore, 20, 300.0)).is_none());
+
+        assert_eq!(from.transfer(&mut to, &items, ore, 100), 30);
+        assert_eq!(from.count(ore), 50);
+        assert_eq!(to.count(ore), 50);
+        assert_eq!(from.transfer(&mut to, &items, ore, 10), 0);
+        assert_eq!(from.count(ore) + to.count(ore), 100);
+    }
+
+    #[test]
+    fn merged_stacks_take_the_mean_temperature() {
+        let (items, ore, _) = registries();
+        let mut inventory = Inventory::new(1);
+        assert!(inventory.insert(&items, stack(ore, 10, 300.0)).is_none());
+        assert!(inventory.insert(&items, stack(ore, 30, 400.0)).is_none());
+        let merged = inventory.stacks().next().unwrap();
+        assert_eq!(merged.count, 40);
+        assert!((merged.temperature.raw() - 375.0).abs() < 1e-3);
+
+        let taken = inventory.take(ore, 15).unwrap();
+        assert_eq!(taken.count, 15);
+        assert!((taken.temperature.raw() - 375.0).abs() < 1e-3);
+        assert_eq!(inventory.count(ore), 25);
+    }
+
+    #[test]
+    fn unknown_items_do_not_fit() {
+        let (_, ore, _) = registries();
+        let empty = ItemRegistry::default();
+        assert!(empty.get(ore).is_none());
+        let mut inventory = Inventory::new(1);
+        assert_eq!(inventory.space_for(&empty, ore), 0);
+        let left = inventory.insert(&empty, stack(ore, 5, 300.0)).unwrap();
+        assert_eq!(left.count, 5);
+        assert!(stack(ore, 5, 300.0).mass(&empty).is_none());
+    }
+}
@@ -0,0 +1,62 @@
+use crate::material::{MaterialId, MaterialRegistry};
+use specs::prelude::*;
+use specs::storage::MaskedStorage;
+use std::ops::Deref;
+
+/// What an entity is made of.
+///
+/// This is written by [Material::build_entity](crate::material::Material::build_entity), so systems can dispatch on the material of a tile.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaterialKind(pub MaterialId);
+
+impl Component for MaterialKind {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl MaterialKind {
+    pub fn id(self) -> MaterialId {
+        self.0
+    }
+
+    /// Returns `None` if the material is not in `registry`
+    pub fn name(self, registry: &MaterialRegistry) -> Option<&str> {
+        registry
+            .get(self.0)
+            .map(|definition| definition.name.as_str())
+    }
+}
+
+pub trait MaterialKindExt {
+    /// Returns `true` if the given entity is made of `kind`.
+    fn is_kind(&self, entity: Entity, kind: MaterialKind) -> bool;
+
+    /// Iterate over all entities that are made of `kind`.
+    fn entities_of_kind<'a>(
+        &'a self,
+        entities: &'a Entities,
+        kind: MaterialKind,
+    ) -> Box<dyn Iterator<Item = Entity> + 'a>;
+}
+
+impl<'b, D> MaterialKindExt for Storage<'b, MaterialKind, D>
+where
+    D: Deref<Target = MaskedStorage<MaterialKind>>,
+{
+    fn is_kind(&self, entity: Entity, kind: MaterialKind) -> bool {
+        self.get(entity) == Some(&kind)
+    }
+
+    fn entities_of_kind<'a>(
+        &'a self,
+        entities: &'a Entities,
+        kind: MaterialKind,
+    ) -> Box<dyn Iterator<Item = Entity> + 'a> {
+        Box::new(
+            (entities, self)
+                .join()
+                .filter(move |(_, other)| **other == kind)
+                .map(|(entity, _)| entity),
+        )
+    }
+}
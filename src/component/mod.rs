@@ -1,9 +1,39 @@
+mod composition;
+mod cooling;
+mod damage;
+mod electric;
+mod falling;
 mod heat;
+mod heat_pipe;
+mod heat_pump;
+mod inventory;
+mod material_kind;
+mod motion;
+mod phase;
+mod power;
+mod reactor;
+mod steam;
+mod volume;
 
 use crate::sys::Color;
-use noisy_float::types::{r32, R32};
+use crate::units::{scalar, RawScalar, Scalar};
 
+pub use self::composition::*;
+pub use self::cooling::*;
+pub use self::damage::*;
+pub use self::electric::*;
+pub use self::falling::*;
 pub use self::heat::*;
+pub use self::heat_pipe::*;
+pub use self::heat_pump::*;
+pub use self::inventory::*;
+pub use self::material_kind::*;
+pub use self::motion::*;
+pub use self::phase::*;
+pub use self::power::*;
+pub use self::reactor::*;
+pub use self::steam::*;
+pub use self::volume::*;
 pub use crate::units::Kilogram as Mass;
 
 impl specs::Component for Mass {
@@ -11,28 +41,43 @@ impl specs::Component for Mass {
 }
 
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StateChangeRequired;
 
 impl specs::Component for StateChangeRequired {
     type Storage = specs::NullStorage<Self>;
 }
 
+/// Marks a tile that is on fire, e.g. because it is fuel in an exothermic reaction that ran this tick.
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Burning;
+
+impl specs::Component for Burning {
+    type Storage = specs::NullStorage<Self>;
+}
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "crate::units::Seconds", into = "crate::units::Seconds")
+)]
 // Delta time, in seconds
-pub struct DeltaTime(R32);
+pub struct DeltaTime(Scalar);
 
 impl DeltaTime {
     pub fn from_elapsed(instant: &mut std::time::Instant) -> DeltaTime {
-        let result = Self::seconds(R32::new(instant.elapsed().as_secs_f32()));
+        let result = Self::seconds(Scalar::new(instant.elapsed().as_secs_f64() as RawScalar));
         *instant = std::time::Instant::now();
         result
     }
-    pub fn seconds(s: R32) -> Self {
+    pub fn seconds(s: Scalar) -> Self {
         Self(s)
     }
 
     pub fn tick() -> Self {
-        Self(r32(1.0 / 10.0))
+        Self(scalar(1.0 / 10.0))
     }
 
     pub fn as_si(self) -> crate::units::Seconds {
@@ -40,7 +85,24 @@ impl DeltaTime {
     }
 }
 
+impl From<crate::units::Seconds> for DeltaTime {
+    fn from(seconds: crate::units::Seconds) -> Self {
+        Self(seconds.0)
+    }
+}
+
+impl From<DeltaTime> for crate::units::Seconds {
+    fn from(delta_time: DeltaTime) -> Self {
+        delta_time.as_si()
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct MaterialColor(pub Color);
 
 impl MaterialColor {
@@ -0,0 +1,91 @@
+use crate::units::{Meter, MeterPerSecond, MeterPerSecondSquared, Newton};
+use specs::{Component, DenseVecStorage};
+
+/// The velocity of a rigid body. Positive `y` points down.
+///
+/// Entities with a velocity are moved by the [MotionSystem](crate::system::MotionSystem). They move to the next tile every time they have travelled a full tile.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Velocity {
+    pub x: MeterPerSecond,
+    pub y: MeterPerSecond,
+    /// How far the entity has travelled horizontally since it last moved to a new tile
+    pub offset_x: Meter,
+    /// How far the entity has travelled vertically since it last moved to a new tile
+    pub offset_y: Meter,
+}
+
+impl Velocity {
+    pub fn new(x: MeterPerSecond, y: MeterPerSecond) -> Self {
+        Velocity {
+            x,
+            y,
+            offset_x: Meter::new_const(0.0),
+            offset_y: Meter::new_const(0.0),
+        }
+    }
+}
+
+impl Component for Velocity {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Default for Velocity {
+    fn default() -> Self {
+        Velocity::new(
+            MeterPerSecond::new_const(0.0),
+            MeterPerSecond::new_const(0.0),
+        )
+    }
+}
+
+/// A constant acceleration of a rigid body, on top of the acceleration caused by its [Force].
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Acceleration {
+    pub x: MeterPerSecondSquared,
+    pub y: MeterPerSecondSquared,
+}
+
+impl Component for Acceleration {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Default for Acceleration {
+    fn default() -> Self {
+        Acceleration {
+            x: MeterPerSecondSquared::new_const(0.0),
+            y: MeterPerSecondSquared::new_const(0.0),
+        }
+    }
+}
+
+/// The sum of the forces on a rigid body during this tick.
+///
+/// Systems add to this with [Force::add], and the [MotionSystem](crate::system::MotionSystem) resets it after applying it.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Force {
+    pub x: Newton,
+    pub y: Newton,
+}
+
+impl Force {
+    pub fn add(&mut self, x: Newton, y: Newton) {
+        self.x += x;
+        self.y += y;
+    }
+}
+
+impl Component for Force {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Default for Force {
+    fn default() -> Self {
+        Force {
+            x: Newton::new_const(0.0),
+            y: Newton::new_const(0.0),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use crate::units::Kelvin;
+use specs::{Component, VecStorage};
+
+/// The state of matter of a tile, ordered from solid to gas.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Phase {
+    Solid,
+    Liquid,
+    Gas,
+}
+
+impl Component for Phase {
+    type Storage = VecStorage<Self>;
+}
+
+impl Phase {
+    pub const ALL: [Phase; 3] = [Phase::Solid, Phase::Liquid, Phase::Gas];
+
+    /// The phase of a material at the given temperature.
+    ///
+    /// A material without a melting point stays solid until it boils (e.g. carbon dioxide sublimates), and a material without either stays solid (e.g. wood and coal, which decompose instead).
+    pub fn at(
+        temperature: Kelvin,
+        melting_point: Option<Kelvin>,
+        boiling_point: Option<Kelvin>,
+    ) -> Self {
+        match (melting_point, boiling_point) {
+            (_, Some(boiling_point)) if temperature >= boiling_point => Phase::Gas,
+            (Some(melting_point), _) if temperature >= melting_point => Phase::Liquid,
+            _ => Phase::Solid,
+        }
+    }
+
+    /// Liquids and gases can flow to neighbouring tiles.
+    pub fn is_fluid(self) -> bool {
+        self != Phase::Solid
+    }
+
+    /// Only solids can carry the weight of the tiles above them.
+    pub fn bears_load(self) -> bool {
+        self == Phase::Solid
+    }
+}
@@ -0,0 +1,125 @@
+use crate::units::{scalar, Joule, RawScalar, Scalar, Seconds, Watt};
+use specs::{Component, DenseVecStorage};
+
+/// Connects buildings to a power network.
+///
+/// Two poles are connected when they are within the wire reach of both. A pole powers every [Generator], [Consumer] and [Accumulator] within its supply range, measured in tiles in each direction.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PowerPole {
+    /// How far away another pole can be connected, in tiles
+    pub wire_reach: u8,
+    pub supply_range: u8,
+}
+
+impl Component for PowerPole {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Turns some other form of energy into electrical power for its network.
+///
+/// A generator only produces as much as its network uses. The energy it loses while doing so is added to its [Heat](crate::component::Heat).
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Generator {
+    /// The most electrical power that the generator can produce
+    pub capacity: Watt,
+    /// The fraction of its input that becomes electrical power, between 0 and 1
+    pub efficiency: RawScalar,
+    output: Watt,
+}
+
+impl Generator {
+    pub fn new(capacity: Watt, efficiency: RawScalar) -> Self {
+        Generator {
+            capacity,
+            efficiency,
+            output: Watt::new_const(0.0),
+        }
+    }
+
+    /// The electrical power that the generator produced during the last tick
+    pub fn output(self) -> Watt {
+        self.output
+    }
+
+    pub(crate) fn set_output(&mut self, output: Watt) {
+        self.output = output;
+    }
+}
+
+impl Component for Generator {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// A building that runs on electrical power.
+///
+/// When its network can not supply enough power, every consumer on it gets the same fraction of its demand. The system that runs the building decides what the power is used for.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Consumer {
+    pub demand: Watt,
+    satisfaction: RawScalar,
+}
+
+impl Consumer {
+    pub fn new(demand: Watt) -> Self {
+        Consumer {
+            demand,
+            satisfaction: 0.0,
+        }
+    }
+
+    /// The fraction of its demand that the consumer received during the last tick, between 0 and 1. Consumers that are not connected to a network get nothing
+    pub fn satisfaction(self) -> Scalar {
+        scalar(self.satisfaction)
+    }
+
+    /// The electrical power that the consumer received during the last tick
+    pub fn received(self) -> Watt {
+        self.demand * self.satisfaction()
+    }
+
+    pub(crate) fn set_satisfaction(&mut self, satisfaction: Scalar) {
+        self.satisfaction = satisfaction.raw();
+    }
+}
+
+impl Component for Consumer {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Stores electrical energy when its network has power to spare, and gives it back when the network is short.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Accumulator {
+    pub capacity: Joule,
+    /// How fast the accumulator can be charged or discharged
+    pub max_power: Watt,
+    pub charge: Joule,
+}
+
+impl Accumulator {
+    /// An empty accumulator
+    pub fn new(capacity: Joule, max_power: Watt) -> Self {
+        Accumulator {
+            capacity,
+            max_power,
+            charge: Joule::new_const(0.0),
+        }
+    }
+
+    /// How much power the accumulator can take in, and how much it can give back, during a tick that takes `delta_time`
+    pub fn limits(self, delta_time: Seconds) -> (Watt, Watt) {
+        let mut room = self.capacity;
+        room -= self.charge;
+        (
+            (room / delta_time).min(self.max_power),
+            (self.charge / delta_time).min(self.max_power),
+        )
+    }
+}
+
+impl Component for Accumulator {
+    type Storage = DenseVecStorage<Self>;
+}
@@ -0,0 +1,88 @@
+use crate::units::{scalar, Joule, Kelvin, RawScalar, Watt};
+use specs::{Component, DenseVecStorage};
+
+/// Heats itself by burning fuel cells, one after the other.
+///
+/// A reactor gives off more heat for every active reactor next to it, without burning its fuel any faster. It needs to be cooled: when it gets hotter than its meltdown temperature, it is destroyed and heats up everything around it.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reactor {
+    /// The heat that the reactor gives off while it burns a fuel cell, before the bonus of its neighbours
+    pub power: Watt,
+    /// The energy in a single fuel cell
+    pub fuel_cell_energy: Joule,
+    /// The fuel cells that are waiting to be burned
+    pub fuel_cells: u32,
+    pub meltdown_temperature: Kelvin,
+    /// How far a meltdown heats up its surroundings, in tiles
+    pub meltdown_radius: u8,
+    /// The energy that is left in the fuel cell that is burning
+    burning: Joule,
+    output: Watt,
+}
+
+impl Reactor {
+    /// A reactor without any fuel cells
+    pub fn new(
+        power: Watt,
+        fuel_cell_energy: Joule,
+        meltdown_temperature: Kelvin,
+        meltdown_radius: u8,
+    ) -> Self {
+        Reactor {
+            power,
+            fuel_cell_energy,
+            fuel_cells: 0,
+            meltdown_temperature,
+            meltdown_radius,
+            burning: Joule::new_const(0.0),
+            output: Watt::new_const(0.0),
+        }
+    }
+
+    /// A reactor is active while it has fuel to burn
+    pub fn is_active(self) -> bool {
+        self.fuel_cells > 0 || self.burning > Joule::new_const(0.0)
+    }
+
+    /// The heat that the reactor gave off during the last tick, including the bonus of its neighbours
+    pub fn output(self) -> Watt {
+        self.output
+    }
+
+    /// The energy in the fuel that is left, including the fuel cell that is burning
+    pub fn remaining_fuel(self) -> Joule {
+        let mut remaining = self.fuel_cell_energy * scalar(self.fuel_cells as RawScalar);
+        remaining += self.burning;
+        remaining
+    }
+
+    /// Burn the fuel for `energy`, starting a new fuel cell when needed, and return the energy that was actually burned
+    pub(crate) fn burn(&mut self, energy: Joule) -> Joule {
+        let zero = Joule::new_const(0.0);
+        let mut burned = zero;
+        let mut left = energy;
+        while left > zero {
+            if self.burning <= zero {
+                if self.fuel_cells == 0 {
+                    break;
+                }
+                self.fuel_cells -= 1;
+                self.burning = self.fuel_cell_energy;
+            }
+            let step = left.min(self.burning);
+            self.burning -= step;
+            left -= step;
+            burned += step;
+        }
+        burned
+    }
+
+    pub(crate) fn set_output(&mut self, output: Watt) {
+        self.output = output;
+    }
+}
+
+impl Component for Reactor {
+    type Storage = DenseVecStorage<Self>;
+}
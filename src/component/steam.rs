@@ -0,0 +1,63 @@
+use crate::material::MaterialId;
+use crate::units::{scalar, Joule, Kelvin, RawScalar, Scalar, Watt};
+use specs::{Component, DenseVecStorage};
+
+/// Boils a liquid with the heat of the tiles around it.
+///
+/// Every tick, the boiler moves heat from its neighbours that are hotter than the boiling point of its fluid into the neighbouring tiles of that fluid, which then boil into steam.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Boiler {
+    /// The material that is boiled, usually water
+    pub fluid: MaterialId,
+    /// How fast heat can be moved into the fluid
+    pub max_power: Watt,
+}
+
+impl Component for Boiler {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Turns the heat of the hot gas around it into electrical power, through the [Generator](crate::component::Generator) on the same entity.
+///
+/// The engine is the cold side of the heat engine: its efficiency depends on the temperature of the steam and its own temperature, and it takes up the heat that is not turned into power.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SteamEngine {
+    pub max_power: Watt,
+    /// The fraction of the Carnot efficiency that the engine reaches, between 0 and 1. Real steam engines reach about half
+    pub fraction_of_carnot: RawScalar,
+    /// The heat that the engine took out of the steam this tick, for the power that it offered to the power grid
+    reserved: Joule,
+}
+
+impl SteamEngine {
+    pub fn new(max_power: Watt, fraction_of_carnot: RawScalar) -> Self {
+        SteamEngine {
+            max_power,
+            fraction_of_carnot,
+            reserved: Joule::new_const(0.0),
+        }
+    }
+
+    /// The heat that the engine took out of the steam this tick, for the power that it offered to the power grid. This is settled in the same tick, and is zero in between ticks
+    pub fn reserved(self) -> Joule {
+        self.reserved
+    }
+
+    pub(crate) fn set_reserved(&mut self, reserved: Joule) {
+        self.reserved = reserved;
+    }
+
+    /// The fraction of the heat from steam at `steam` that the engine turns into power, when it is at `ambient` itself
+    pub fn efficiency(self, steam: Kelvin, ambient: Kelvin) -> Scalar {
+        if steam <= ambient {
+            return scalar(0.0);
+        }
+        (scalar(1.0) - ambient / steam) * scalar(self.fraction_of_carnot)
+    }
+}
+
+impl Component for SteamEngine {
+    type Storage = DenseVecStorage<Self>;
+}
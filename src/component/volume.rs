@@ -0,0 +1,158 @@
+use super::Phase;
+use crate::grid_storage::{GridStorageExt, Position};
+use crate::units::{Density, Kilogram, MeterCubed, MeterSquared, Scalar};
+use specs::prelude::*;
+use specs::storage::MaskedStorage;
+use std::fmt;
+use std::ops::Deref;
+
+/// Every tile is a 1m x 1m x 1m cube
+pub const TILE_VOLUME: MeterCubed = MeterCubed::new_const(1.0);
+/// The area of a single face of a tile
+pub const TILE_FACE_AREA: MeterSquared = MeterSquared::new_const(1.0);
+/// Gases are never squeezed into less than this, so a tile that is filled up with solids does not give its gas an infinite pressure
+pub const MIN_GAS_VOLUME: MeterCubed = MeterCubed::new_const(0.01);
+
+/// The space that an entity takes up in its tile, derived from its mass and density.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Volume(pub MeterCubed);
+
+impl Component for Volume {
+    type Storage = VecStorage<Self>;
+}
+
+impl Volume {
+    /// Calculate the volume of `mass`. Returns an error if this does not fit in a single tile.
+    pub fn from_mass(mass: Kilogram, density: Density) -> Result<Self, OverfullTile> {
+        let volume = mass / density;
+        if volume > TILE_VOLUME {
+            Err(OverfullTile { volume })
+        } else {
+            Ok(Volume(volume))
+        }
+    }
+
+    /// Calculate the volume of `mass` in the given phase. A gas always expands to fill the entire tile.
+    pub fn new(mass: Kilogram, density: Density, phase: Phase) -> Result<Self, OverfullTile> {
+        match phase {
+            Phase::Gas => Ok(Self::full()),
+            Phase::Solid | Phase::Liquid => Self::from_mass(mass, density),
+        }
+    }
+
+    /// A volume that fills the entire tile. This is used for entities that do not have a [Volume].
+    pub fn full() -> Self {
+        Volume(TILE_VOLUME)
+    }
+
+    /// Check that the solids and liquids on `position` fit in the tile together. Entities without a [Phase] count as solids, and entities without a [Volume] fill the entire tile.
+    pub fn check_tile<P, V>(
+        position: Position,
+        positions: &ReadStorage<Position>,
+        phases: &Storage<Phase, P>,
+        volumes: &Storage<Volume, V>,
+    ) -> Result<(), OverfullTile>
+    where
+        P: Deref<Target = MaskedStorage<Phase>>,
+        V: Deref<Target = MaskedStorage<Volume>>,
+    {
+        let mut volume = MeterCubed::new_const(0.0);
+        for (entity, _) in positions.on_tile(position) {
+            if phases.get(entity) != Some(&Phase::Gas) {
+                volume += volumes.get(entity).copied().unwrap_or_else(Self::full).0;
+            }
+        }
+        if volume > TILE_VOLUME {
+            Err(OverfullTile { volume })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The space that is left for gases in a tile, when `occupied` is taken up by solids and liquids.
+    pub fn free(occupied: MeterCubed) -> MeterCubed {
+        let free = TILE_VOLUME - occupied;
+        if free < MIN_GAS_VOLUME {
+            MIN_GAS_VOLUME
+        } else {
+            free
+        }
+    }
+
+    /// How much of the tile is filled, between 0 and 1.
+    pub fn fill_fraction(self) -> Scalar {
+        self.0 / TILE_VOLUME
+    }
+
+    /// The area through which this tile touches a neighbouring tile.
+    ///
+    /// Material settles at the bottom of its tile, so two neighbours only touch for the height of the emptiest tile.
+    pub fn contact_area(self, other: Volume) -> MeterSquared {
+        TILE_FACE_AREA * self.fill_fraction().min(other.fill_fraction())
+    }
+}
+
+/// Returned when a tile would have to hold more than [TILE_VOLUME].
+#[derive(Debug, Copy, Clone)]
+pub struct OverfullTile {
+    pub volume: MeterCubed,
+}
+
+impl fmt::Display for OverfullTile {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{:?} does not fit in a tile of {:?}",
+            self.volume, TILE_VOLUME
+        )
+    }
+}
+
+impl std::error::Error for OverfullTile {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid_storage::GridStorage;
+    use crate::units::RawScalar;
+    use noisy_float::types::r32;
+
+    #[test]
+    fn check_tile() {
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(1, 1));
+        world.register::<Phase>();
+        world.register::<Volume>();
+        let position = Position {
+            x: r32(0.0),
+            y: r32(0.0),
+        };
+        let mut add = |phase: Phase, volume: RawScalar| {
+            world
+                .create_entity()
+                .with(position)
+                .with(phase)
+                .with(Volume(MeterCubed::new_const(volume)))
+                .build();
+            world.exec(
+                |(positions, phases, volumes): (
+                    ReadStorage<Position>,
+                    ReadStorage<Phase>,
+                    ReadStorage<Volume>,
+                )| {
+                    Volume::check_tile(position, &positions, &phases, &volumes).is_ok()
+                },
+            )
+        };
+
+        assert!(add(Phase::Liquid, 0.6));
+        // Gases fill the space that is left, so they never overfill a tile
+        assert!(add(Phase::Gas, 1.0));
+        assert!(!add(Phase::Solid, 0.6));
+    }
+}
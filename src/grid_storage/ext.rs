@@ -1,9 +1,24 @@
 use super::{EntityPositionIterator, Position};
 use specs::storage::{MaskedStorage, Storage};
-use std::ops::Deref;
+use specs::world::Entity;
+use std::ops::{Deref, DerefMut};
+
+/// The offsets of the tiles that [GridStorageExt::neighbours] looks at
+pub const NEIGHBOUR_OFFSETS: [(isize, isize); 4] = [(-1, 0), (0, -1), (1, 0), (0, 1)];
 
 pub trait GridStorageExt {
     fn neighbours(&self, position: Position) -> EntityPositionIterator;
+    /// All entities on the same tile as `position`
+    fn on_tile(&self, position: Position) -> EntityPositionIterator;
+    /// Check if `position` lies within the grid
+    fn is_in_bounds(&self, position: Position) -> bool;
+}
+
+pub trait GridStorageMutExt {
+    /// Move `entity` to `position`, updating the tile lookup of the grid.
+    ///
+    /// Always use this to move an entity. Changing the [Position] through `get_mut` or `insert` leaves the entity in the lookup of its old tile.
+    fn move_to(&mut self, entity: Entity, position: Position);
 }
 
 impl<'b, D> GridStorageExt for Storage<'b, Position, D>
@@ -13,8 +28,31 @@ where
     fn neighbours(&self, position: Position) -> EntityPositionIterator {
         let grid = self.unprotected_storage();
         let entities = self.fetched_entities();
-        let offsets = &[(-1, 0), (0, -1), (1, 0), (0, 1)];
 
-        EntityPositionIterator::new(position, grid, entities, offsets)
+        EntityPositionIterator::new(position, grid, entities, &NEIGHBOUR_OFFSETS)
+    }
+
+    fn on_tile(&self, position: Position) -> EntityPositionIterator {
+        let grid = self.unprotected_storage();
+        let entities = self.fetched_entities();
+
+        EntityPositionIterator::new(position, grid, entities, &[(0, 0)])
+    }
+
+    fn is_in_bounds(&self, position: Position) -> bool {
+        self.unprotected_storage().is_in_bounds(position)
+    }
+}
+
+impl<'b, D> GridStorageMutExt for Storage<'b, Position, D>
+where
+    D: DerefMut<Target = MaskedStorage<Position>>,
+{
+    fn move_to(&mut self, entity: Entity, position: Position) {
+        // Removing and inserting goes through GridStorage, which keeps the tile lookup up to date
+        if self.remove(entity).is_some() {
+            // This can only fail if the entity is dead, in which case `remove` returned None
+            let _ = self.insert(entity, position);
+        }
     }
 }
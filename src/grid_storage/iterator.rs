@@ -59,7 +59,7 @@ impl<'a> Iterator for EntityPositionIterator<'a> {
 
                 let position = Position {
                     x: r32(x as f32) + self.starting_position.x,
-                    y: r32(y as f32) + self.starting_position.x,
+                    y: r32(y as f32) + self.starting_position.y,
                 };
 
                 if !self.grid.is_in_bounds(position) {
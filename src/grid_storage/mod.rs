@@ -2,7 +2,7 @@ mod ext;
 mod iterator;
 mod position;
 
-pub use self::ext::GridStorageExt;
+pub use self::ext::{GridStorageExt, GridStorageMutExt, NEIGHBOUR_OFFSETS};
 pub use self::iterator::EntityPositionIterator;
 pub use self::position::Position;
 
@@ -106,6 +106,68 @@ impl UnprotectedStorage<Position> for GridStorage {
     }
 
     unsafe fn remove(&mut self, id: Index) -> Position {
-        self.inner.remove(id)
+        let position = self.inner.remove(id);
+
+        let index = self.position_to_index(position);
+        self.entities[index].retain(|other| *other != id);
+        position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noisy_float::types::r32;
+    use specs::prelude::*;
+
+    fn at(x: f32, y: f32) -> Position {
+        Position {
+            x: r32(x),
+            y: r32(y),
+        }
+    }
+
+    /// A 3x3 grid with one entity on every tile
+    fn grid() -> World {
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(3, 3));
+        for y in 0..3 {
+            for x in 0..3 {
+                world.create_entity().with(at(x as f32, y as f32)).build();
+            }
+        }
+        world
+    }
+
+    #[test]
+    fn neighbours_off_the_diagonal() {
+        let world = grid();
+        let positions = world.read_storage::<Position>();
+        let mut neighbours: Vec<(f32, f32)> = positions
+            .neighbours(at(0.0, 1.0))
+            .map(|(_, position)| (position.x.raw(), position.y.raw()))
+            .collect();
+        neighbours.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(neighbours, vec![(0.0, 0.0), (0.0, 2.0), (1.0, 1.0)]);
+    }
+
+    #[test]
+    fn removed_entities_leave_their_tile() {
+        let mut world = grid();
+        let entity = world
+            .read_storage::<Position>()
+            .on_tile(at(1.0, 2.0))
+            .map(|(entity, _)| entity)
+            .next()
+            .unwrap();
+        world.delete_entity(entity).unwrap();
+        world.maintain();
+        // The new entity reuses the index of the deleted one, so a stale index would find it on the old tile
+        let new = world.create_entity().with(at(0.0, 0.0)).build();
+        assert_eq!(new.id(), entity.id());
+
+        let positions = world.read_storage::<Position>();
+        assert_eq!(positions.on_tile(at(1.0, 2.0)).count(), 0);
+        assert_eq!(positions.neighbours(at(1.0, 1.0)).count(), 3);
     }
 }
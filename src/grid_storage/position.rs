@@ -1,12 +1,54 @@
 use super::GridStorage;
 use noisy_float::prelude::*;
+use std::fmt;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RawPosition", into = "RawPosition")
+)]
 pub struct Position {
     pub x: R32,
     pub y: R32,
 }
 
+/// Plain float representation of a [Position], so we don't rely on `noisy_float` implementing serde.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RawPosition {
+    x: f32,
+    y: f32,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<RawPosition> for Position {
+    type Error = String;
+
+    fn try_from(raw: RawPosition) -> Result<Self, String> {
+        match (R32::try_new(raw.x), R32::try_new(raw.y)) {
+            (Some(x), Some(y)) => Ok(Position { x, y }),
+            _ => Err(format!("Position ({}, {}) is not finite", raw.x, raw.y)),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Position> for RawPosition {
+    fn from(position: Position) -> Self {
+        RawPosition {
+            x: position.x.raw(),
+            y: position.y.raw(),
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "({}, {})", self.x, self.y)
+    }
+}
+
 impl specs::Component for Position {
     type Storage = GridStorage;
 }
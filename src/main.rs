@@ -10,17 +10,32 @@ pub mod utils;
 
 mod grid_storage;
 
-use crate::component::{DeltaTime, Heat, Mass, MaterialColor, StateChangeRequired};
+use crate::component::{
+    Acceleration, Accumulator, Boiler, Burning, Composition, Consumer, CoolingTower, Damage,
+    DeltaTime, Electricity, Electrode, Falling, Force, Generator, Heat, HeatExchanger, HeatPipe,
+    HeatPump, Inventory, Mass, MaterialColor, MaterialKind, Phase, PowerPole, Radiator, Reactor,
+    StateChangeRequired, SteamEngine, Velocity, Volume,
+};
 use crate::grid_storage::*;
-use crate::material::{Material, Steel, Water};
+use crate::material::{ItemRegistry, MaterialRegistry, ReactionRegistry};
 use crate::sys::{Event, WindowStateView};
-use crate::units::Kelvin;
+use crate::system::{fill_atmosphere, Atmosphere, FireEvents, MeltdownEvents, SimulationErrors};
+use crate::units::{scalar, Kelvin, MeterCubed, RawScalar, Seconds, ATMOSPHERIC_PRESSURE};
 use noisy_float::types::r32;
+use rand::seq::IteratorRandom;
 use specs::prelude::*;
 
+/// The most simulation steps that are run between two frames
+const MAX_TICKS_PER_FRAME: RawScalar = 5.0;
+/// Pass this flag to print the events of the simulation, e.g. fires starting and reactors melting down
+const LOG_EVENTS_FLAG: &str = "--log-events";
+
 fn main() {
+    let log_events = std::env::args().any(|arg| arg == LOG_EVENTS_FLAG);
+
     let mut world = World::empty();
     world.insert(specs::world::EntitiesRes::default());
+    world.insert(LazyUpdate::default());
 
     let world_dimensions = (10, 10);
 
@@ -29,36 +44,220 @@ fn main() {
     });
     world.register::<MaterialColor>();
     world.register::<Mass>();
+    world.register::<Volume>();
     world.register::<Heat>();
     world.register::<StateChangeRequired>();
+    world.register::<MaterialKind>();
+    world.register::<Composition>();
+    world.register::<Phase>();
+    world.register::<Burning>();
+    world.register::<Falling>();
+    world.register::<Velocity>();
+    world.register::<Acceleration>();
+    world.register::<Force>();
+    world.register::<Damage>();
+    world.register::<Electrode>();
+    world.register::<Electricity>();
+    world.register::<PowerPole>();
+    world.register::<Generator>();
+    world.register::<Consumer>();
+    world.register::<Accumulator>();
+    world.register::<Boiler>();
+    world.register::<SteamEngine>();
+    world.register::<HeatPipe>();
+    world.register::<HeatExchanger>();
+    world.register::<HeatPump>();
+    world.register::<Radiator>();
+    world.register::<CoolingTower>();
+    world.register::<Reactor>();
+    world.register::<Inventory>();
+    world.insert(SimulationErrors::default());
+    world.insert(FireEvents::default());
+    world.insert(MeltdownEvents::default());
+    world.insert(Atmosphere::new(world_dimensions.0, world_dimensions.1));
+
+    let materials = match MaterialRegistry::load("assets/materials.ron") {
+        Ok(materials) => materials,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
 
     for x in 0..world_dimensions.0 {
         for y in 0..world_dimensions.1 {
-            let material: Box<dyn Material> = if rand::random::<bool>() {
-                Box::new(Steel)
-            } else {
-                Box::new(Water)
+            let material = materials
+                .ids()
+                .choose(&mut rand::thread_rng())
+                .expect("assets/materials.ron does not contain any materials");
+            let mass = some_or_continue!(materials.get(material)).density * MeterCubed::random();
+            let position = Position {
+                x: r32(x as f32),
+                y: r32(y as f32),
             };
-            material.build_entity(
+            if let Err(e) = materials.build_entity(
+                material,
                 world.create_entity(),
                 Kelvin::random(),
-                Mass::random(),
-                Position {
-                    x: r32(x as f32),
-                    y: r32(y as f32),
-                },
-            );
+                mass,
+                position,
+            ) {
+                eprintln!("Could not place a material at {}: {}", position, e);
+            }
         }
     }
 
+    if let Err(e) = fill_atmosphere(
+        &mut world,
+        &materials,
+        Kelvin::new_const(293.15),
+        ATMOSPHERIC_PRESSURE,
+    ) {
+        eprintln!("{}", e);
+        return;
+    }
+
+    let reactions = match ReactionRegistry::load("assets/reactions.ron", &materials) {
+        Ok(reactions) => reactions,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    let items = match ItemRegistry::load("assets/items.ron", &materials) {
+        Ok(items) => items,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    world.insert(materials);
+    world.insert(reactions);
+    world.insert(items);
+
     let mut dispatcher = DispatcherBuilder::new()
         .with(crate::system::HeatSystem, "heat system", &[])
+        .with(
+            crate::system::HeatPipeSystem,
+            "heat pipe system",
+            &["heat system"],
+        )
+        .with(
+            crate::system::ElectricalSystem,
+            "electrical system",
+            &["heat system"],
+        )
+        .with(
+            crate::system::SteamIntakeSystem,
+            "steam intake system",
+            &["heat system"],
+        )
+        .with(
+            crate::system::PowerGridSystem,
+            "power grid system",
+            &["heat system", "steam intake system"],
+        )
+        .with(
+            crate::system::SteamEngineSystem,
+            "steam engine system",
+            &["power grid system"],
+        )
+        .with(
+            crate::system::BoilerSystem,
+            "boiler system",
+            &["heat system"],
+        )
+        .with(
+            crate::system::HeatPumpSystem,
+            "heat pump system",
+            &["power grid system"],
+        )
+        .with(
+            crate::system::RadiatorSystem,
+            "radiator system",
+            &["heat system"],
+        )
+        .with(
+            crate::system::CoolingTowerSystem,
+            "cooling tower system",
+            &["heat system"],
+        )
+        .with(
+            crate::system::ReactorSystem,
+            "reactor system",
+            &["heat system"],
+        )
+        .with(
+            crate::system::InventorySystem,
+            "inventory system",
+            &["heat system"],
+        )
+        .with(
+            crate::system::ReactionSystem,
+            "reaction system",
+            &["heat system"],
+        )
+        .with(
+            crate::system::PhaseSystem,
+            "phase system",
+            &[
+                "heat system",
+                "electrical system",
+                "power grid system",
+                "boiler system",
+                "steam intake system",
+                "steam engine system",
+                "heat pipe system",
+                "heat pump system",
+                "radiator system",
+                "cooling tower system",
+                "reactor system",
+                "inventory system",
+                "reaction system",
+            ],
+        )
+        .with(
+            crate::system::GravitySystem::default(),
+            "gravity system",
+            &["phase system"],
+        )
+        .with(
+            crate::system::ThermalStressSystem,
+            "thermal stress system",
+            &["phase system"],
+        )
+        .with(
+            crate::system::StructuralSystem,
+            "structural system",
+            &["phase system", "thermal stress system"],
+        )
+        .with(
+            crate::system::MotionSystem,
+            "motion system",
+            &["gravity system", "structural system"],
+        )
+        .with(
+            crate::system::FluidSystem,
+            "fluid system",
+            &["gravity system", "motion system"],
+        )
+        .with(
+            crate::system::AtmosphereSystem,
+            "atmosphere system",
+            &["fluid system"],
+        )
         .build();
 
     let mut window = crate::sys::Window::default();
     let mut running = true;
 
+    // The simulation always advances in steps of the same length, so that the same input gives the same result.
+    world.insert(DeltaTime::tick());
+    let tick = DeltaTime::tick().as_si();
     let mut last_update_time = std::time::Instant::now();
+    let mut unsimulated = Seconds::new_const(0.0);
 
     while running {
         window.update(|window, e| match e {
@@ -66,8 +265,28 @@ fn main() {
             Event::Input(user_input) => user_input.apply(window, &mut world),
         });
 
-        world.insert(DeltaTime::from_elapsed(&mut last_update_time));
-        dispatcher.dispatch(&world);
+        unsimulated += DeltaTime::from_elapsed(&mut last_update_time).as_si();
+        // When the simulation falls behind, it slows down instead of trying to catch up
+        unsimulated = unsimulated.min(tick * scalar(MAX_TICKS_PER_FRAME));
+        while unsimulated >= tick {
+            unsimulated -= tick;
+            dispatcher.dispatch(&world);
+            world.maintain();
+        }
+
+        for error in world.fetch_mut::<SimulationErrors>().drain() {
+            eprintln!("{}", error);
+        }
+        for event in world.fetch_mut::<FireEvents>().drain() {
+            if log_events {
+                eprintln!("{}", event);
+            }
+        }
+        for event in world.fetch_mut::<MeltdownEvents>().drain() {
+            if log_events {
+                eprintln!("{}", event);
+            }
+        }
 
         window.clear();
 
@@ -81,7 +300,7 @@ fn main() {
                     let mut min = Kelvin::max_value();
                     let mut max = Kelvin::min_value();
                     for (heat, mass) in (&heat, &mass).join() {
-                        let temp = heat.temperature(*mass);
+                        let temp = some_or_continue!(heat.try_temperature(*mass).ok());
                         if temp > max {
                             max = temp;
                         }
@@ -92,24 +311,44 @@ fn main() {
                     min *= 0.9;
                     max *= 1.1;
                     for (heat, mass, position) in (&heat, &mass, &position).join() {
-                        let temp = heat.temperature(*mass);
+                        let temp = some_or_continue!(heat.try_temperature(*mass).ok());
                         window.draw_temperature(temp, *position, min, max);
                     }
                 },
             ),
             WindowStateView::MaterialMap => {
                 world.exec(
-                    |(material_color, position): (
+                    |(material_color, position, phase): (
                         ReadStorage<MaterialColor>,
                         ReadStorage<Position>,
+                        ReadStorage<Phase>,
                     )| {
-                        for (color, position) in (&material_color, &position).join() {
-                            window.draw_material(*color, *position);
+                        for (color, position, phase) in
+                            (&material_color, &position, phase.maybe()).join()
+                        {
+                            let phase = phase.copied().unwrap_or(Phase::Solid);
+                            window.draw_material(*color, *position, phase);
                         }
                     },
                 );
             }
         }
+        world.exec(
+            |(burning, position): (ReadStorage<Burning>, ReadStorage<Position>)| {
+                for (_, position) in (&burning, &position).join() {
+                    window.draw_fire(*position);
+                }
+            },
+        );
+        world.exec(
+            |(damage, position): (ReadStorage<Damage>, ReadStorage<Position>)| {
+                for (damage, position) in (&damage, &position).join() {
+                    if damage.is_cracked() {
+                        window.draw_cracks(*position);
+                    }
+                }
+            },
+        );
         window.present();
     }
 }
@@ -0,0 +1,171 @@
+use super::{MaterialId, MaterialRegistry};
+use crate::units::Kilogram;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Identifies an item in the [ItemRegistry].
+///
+/// Inventories store this in their [Stack](crate::component::Stack)s.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ItemId(u16);
+
+/// An item as it is defined in the item files, e.g. an iron plate.
+///
+/// The material is referenced by name.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ItemDefinition {
+    pub name: String,
+    /// The material that the item is made of, which gives it its heat capacity
+    pub material: String,
+    /// The mass of a single item
+    pub mass: Kilogram,
+    /// How many of these items fit in a single inventory slot
+    pub stack_size: u32,
+}
+
+/// An [ItemDefinition] with the material name resolved.
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub name: String,
+    pub material: MaterialId,
+    pub mass: Kilogram,
+    pub stack_size: u32,
+}
+
+impl Item {
+    pub fn new(
+        definition: ItemDefinition,
+        materials: &MaterialRegistry,
+    ) -> Result<Self, ItemRegistryError> {
+        if !definition.mass.raw().is_finite() || definition.mass <= Kilogram::new_const(0.0) {
+            return Err(ItemRegistryError::InvalidMass {
+                item: definition.name,
+                mass: definition.mass,
+            });
+        }
+        if definition.stack_size == 0 {
+            return Err(ItemRegistryError::InvalidStackSize(definition.name));
+        }
+        let material = match materials.by_name(&definition.material) {
+            Some(id) => id,
+            None => {
+                return Err(ItemRegistryError::UnknownMaterial {
+                    item: definition.name,
+                    material: definition.material,
+                })
+            }
+        };
+        Ok(Item {
+            name: definition.name,
+            material,
+            mass: definition.mass,
+            stack_size: definition.stack_size,
+        })
+    }
+}
+
+/// All items that are known to the game.
+///
+/// This is inserted into the world as a resource, so systems can look up the mass, stack size and material of an [ItemId].
+#[derive(Default)]
+pub struct ItemRegistry {
+    items: Vec<Item>,
+    by_name: HashMap<String, ItemId>,
+}
+
+impl ItemRegistry {
+    /// Load the items from a RON file, containing a list of [ItemDefinition].
+    #[cfg(feature = "serde")]
+    pub fn load(
+        path: impl AsRef<std::path::Path>,
+        materials: &MaterialRegistry,
+    ) -> Result<Self, ItemRegistryError> {
+        let contents = std::fs::read_to_string(path).map_err(ItemRegistryError::Io)?;
+        Self::from_ron(&contents, materials)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_ron(
+        contents: &str,
+        materials: &MaterialRegistry,
+    ) -> Result<Self, ItemRegistryError> {
+        let definitions: Vec<ItemDefinition> =
+            ron::de::from_str(contents).map_err(ItemRegistryError::Parse)?;
+        let mut registry = Self::default();
+        for definition in definitions {
+            registry.register(Item::new(definition, materials)?)?;
+        }
+        Ok(registry)
+    }
+
+    pub fn register(&mut self, item: Item) -> Result<ItemId, ItemRegistryError> {
+        if self.by_name.contains_key(&item.name) {
+            return Err(ItemRegistryError::DuplicateName(item.name));
+        }
+        if self.items.len() > u16::MAX as usize {
+            return Err(ItemRegistryError::TooManyItems);
+        }
+        let id = ItemId(self.items.len() as u16);
+        self.by_name.insert(item.name.clone(), id);
+        self.items.push(item);
+        Ok(id)
+    }
+
+    /// The item with `id`, or `None` if it is not in this registry, e.g. because it comes from a saved game with other items
+    pub fn get(&self, id: ItemId) -> Option<&Item> {
+        self.items.get(id.0 as usize)
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<ItemId> {
+        self.by_name.get(name).copied()
+    }
+}
+
+#[derive(Debug)]
+pub enum ItemRegistryError {
+    Io(std::io::Error),
+    #[cfg(feature = "serde")]
+    Parse(ron::de::Error),
+    DuplicateName(String),
+    TooManyItems,
+    UnknownMaterial {
+        item: String,
+        material: String,
+    },
+    InvalidMass {
+        item: String,
+        mass: Kilogram,
+    },
+    InvalidStackSize(String),
+}
+
+impl fmt::Display for ItemRegistryError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ItemRegistryError::Io(e) => write!(fmt, "Could not read the item file: {}", e),
+            #[cfg(feature = "serde")]
+            ItemRegistryError::Parse(e) => write!(fmt, "Could not parse the item file: {}", e),
+            ItemRegistryError::DuplicateName(name) => {
+                write!(fmt, "Item {:?} is defined more than once", name)
+            }
+            ItemRegistryError::TooManyItems => {
+                write!(fmt, "Too many items, at most {} are supported", u16::MAX)
+            }
+            ItemRegistryError::UnknownMaterial { item, material } => write!(
+                fmt,
+                "Item {:?} is made of unknown material {:?}",
+                item, material
+            ),
+            ItemRegistryError::InvalidMass { item, mass } => {
+                write!(fmt, "Item {:?} has an invalid mass {} kg", item, mass.raw())
+            }
+            ItemRegistryError::InvalidStackSize(item) => {
+                write!(fmt, "Item {:?} has a stack size of 0", item)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ItemRegistryError {}
@@ -0,0 +1,60 @@
+use super::{Material, MaterialRegistry};
+use crate::component::{Composition, MaterialColor, MaterialKind, Phase};
+use crate::units::{Density, OhmMeter, SpecificHeatCapacity, ThermalConductivity};
+
+/// A [Composition] of several registered materials, e.g. mud, brine or an alloy.
+///
+/// The [MaterialKind] and [Phase] of a mixture are that of the material that makes up most of its mass.
+#[derive(Copy, Clone)]
+pub struct Mixture {
+    kind: MaterialKind,
+    phase: Phase,
+    specific_heat_capacity: SpecificHeatCapacity,
+    thermal_conductivity: ThermalConductivity,
+    color: MaterialColor,
+    density: Density,
+    electrical_resistivity: Option<OhmMeter>,
+}
+
+impl Mixture {
+    /// The effective properties of `composition` in `phase`. Returns `None` if one of its parts is not in `registry`.
+    pub fn new(
+        composition: &Composition,
+        registry: &MaterialRegistry,
+        phase: Phase,
+    ) -> Option<Self> {
+        Some(Mixture {
+            kind: MaterialKind(composition.dominant()),
+            phase,
+            specific_heat_capacity: composition.specific_heat_capacity(registry, phase)?,
+            thermal_conductivity: composition.thermal_conductivity(registry, phase)?,
+            color: composition.color(registry, phase)?,
+            density: composition.density(registry, phase)?,
+            electrical_resistivity: composition.electrical_resistivity(registry, phase)?,
+        })
+    }
+}
+
+impl Material for Mixture {
+    fn kind(&self) -> MaterialKind {
+        self.kind
+    }
+    fn phase(&self) -> Phase {
+        self.phase
+    }
+    fn specific_heat_capacity(&self) -> SpecificHeatCapacity {
+        self.specific_heat_capacity
+    }
+    fn thermal_conductivity(&self) -> ThermalConductivity {
+        self.thermal_conductivity
+    }
+    fn material_color(&self) -> MaterialColor {
+        self.color
+    }
+    fn density(&self) -> Density {
+        self.density
+    }
+    fn electrical_resistivity(&self) -> Option<OhmMeter> {
+        self.electrical_resistivity
+    }
+}
@@ -1,23 +1,70 @@
-use crate::component::{Heat, Mass, MaterialColor};
+mod item;
+mod mixture;
+mod reaction;
+mod registry;
+
+pub use self::item::*;
+pub use self::mixture::*;
+pub use self::reaction::*;
+pub use self::registry::*;
+
+use crate::component::{Heat, Mass, MaterialColor, MaterialKind, OverfullTile, Phase, Volume};
 use crate::grid_storage::Position;
-use crate::units::{Kelvin, SpecificHeatCapacity, ThermalConductivity};
-use noisy_float::types::r32;
+use crate::units::{Density, Kelvin, OhmMeter, SpecificHeatCapacity, ThermalConductivity};
 use specs::prelude::*;
+use std::fmt;
+
+/// Returned when an entity can not be built from a material in the [MaterialRegistry].
+#[derive(Debug, Copy, Clone)]
+pub enum BuildError {
+    /// The material, or one of the parts of a mixture, is not in the registry
+    UnknownMaterial(MaterialId),
+    Overfull(OverfullTile),
+}
+
+impl From<OverfullTile> for BuildError {
+    fn from(error: OverfullTile) -> Self {
+        BuildError::Overfull(error)
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::UnknownMaterial(id) => write!(fmt, "{:?} is not in the registry", id),
+            BuildError::Overfull(error) => write!(fmt, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
 
 pub trait Material {
+    fn kind(&self) -> MaterialKind;
+    fn phase(&self) -> Phase;
     fn specific_heat_capacity(&self) -> SpecificHeatCapacity;
     fn thermal_conductivity(&self) -> ThermalConductivity;
     fn material_color(&self) -> MaterialColor;
+    fn density(&self) -> Density;
+    /// `None` if the material does not conduct electricity
+    fn electrical_resistivity(&self) -> Option<OhmMeter>;
 
-    fn build_entity(
+    /// Build an entity of this material. Fails if `mass` does not fit in a single tile, in which case the entity is not created.
+    ///
+    /// `builder` can be a [EntityBuilder] or, from inside a system, a [LazyBuilder](specs::world::LazyBuilder).
+    fn build_entity<B: Builder>(
         &self,
-        builder: EntityBuilder,
+        builder: B,
         temperature: Kelvin,
         mass: Mass,
         position: Position,
-    ) {
-        builder
+    ) -> Result<Entity, OverfullTile> {
+        let volume = Volume::new(mass, self.density(), self.phase())?;
+        Ok(builder
+            .with(self.kind())
+            .with(self.phase())
             .with(mass)
+            .with(volume)
             .with(self.material_color())
             .with(Heat::from_material_specs(
                 temperature,
@@ -26,34 +73,6 @@ pub trait Material {
                 self.thermal_conductivity(),
             ))
             .with(position)
-            .build();
-    }
-}
-
-pub struct Steel;
-
-impl Material for Steel {
-    fn specific_heat_capacity(&self) -> SpecificHeatCapacity {
-        SpecificHeatCapacity(r32(490.0))
-    }
-    fn thermal_conductivity(&self) -> ThermalConductivity {
-        ThermalConductivity(r32(54.0))
-    }
-    fn material_color(&self) -> MaterialColor {
-        MaterialColor::rgb(70, 130, 180)
-    }
-}
-
-pub struct Water;
-
-impl Material for Water {
-    fn specific_heat_capacity(&self) -> SpecificHeatCapacity {
-        SpecificHeatCapacity(r32(4179.0))
-    }
-    fn thermal_conductivity(&self) -> ThermalConductivity {
-        ThermalConductivity(r32(0.609))
-    }
-    fn material_color(&self) -> MaterialColor {
-        MaterialColor::rgb(235, 244, 250)
+            .build())
     }
 }
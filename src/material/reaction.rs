@@ -0,0 +1,181 @@
+use super::{MaterialId, MaterialRegistry};
+use crate::units::{Kelvin, KilogramPerSecond, RawScalar, SpecificEnergy};
+use std::fmt;
+
+/// A chemical reaction as it is defined in the reaction files, e.g. `coal + oxygen -> carbon dioxide`.
+///
+/// Materials are referenced by name, and the amounts are relative masses. E.g. burning 12 kg of coal takes 32 kg of oxygen and produces 44 kg of carbon dioxide.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReactionDefinition {
+    pub name: String,
+    /// The first reactant is the fuel: the reaction happens on its tile, and the other reactants are taken from the same or neighbouring tiles
+    pub reactants: Vec<(String, RawScalar)>,
+    pub products: Vec<(String, RawScalar)>,
+    /// The fuel has to be at least this hot for the reaction to happen
+    pub ignition_temperature: Kelvin,
+    /// The heat that is released per kg of fuel. This is negative for reactions that absorb heat
+    pub energy: SpecificEnergy,
+    /// The amount of fuel that a single tile consumes per second at the ignition temperature, if the other reactants touch it on every side
+    pub rate: KilogramPerSecond,
+}
+
+/// A [ReactionDefinition] with the material names resolved, and the amounts relative to 1 kg of fuel.
+#[derive(Debug, Clone)]
+pub struct Reaction {
+    pub name: String,
+    pub fuel: MaterialId,
+    /// The other reactants, and how many kg is needed per kg of fuel
+    pub reactants: Vec<(MaterialId, RawScalar)>,
+    /// The products, and how many kg is produced per kg of fuel
+    pub products: Vec<(MaterialId, RawScalar)>,
+    pub ignition_temperature: Kelvin,
+    pub energy: SpecificEnergy,
+    pub rate: KilogramPerSecond,
+}
+
+impl Reaction {
+    pub fn new(
+        definition: ReactionDefinition,
+        materials: &MaterialRegistry,
+    ) -> Result<Self, ReactionRegistryError> {
+        let resolve = |(name, amount): &(String, RawScalar)| {
+            if !amount.is_finite() || *amount <= 0.0 {
+                return Err(ReactionRegistryError::InvalidAmount {
+                    reaction: definition.name.clone(),
+                    material: name.clone(),
+                    amount: *amount,
+                });
+            }
+            match materials.by_name(name) {
+                Some(id) => Ok((id, *amount)),
+                None => Err(ReactionRegistryError::UnknownMaterial {
+                    reaction: definition.name.clone(),
+                    material: name.clone(),
+                }),
+            }
+        };
+        let mut reactants = definition
+            .reactants
+            .iter()
+            .map(resolve)
+            .collect::<Result<Vec<_>, _>>()?;
+        let products = definition
+            .products
+            .iter()
+            .map(resolve)
+            .collect::<Result<Vec<_>, _>>()?;
+        if reactants.is_empty() {
+            return Err(ReactionRegistryError::NoFuel(definition.name));
+        }
+
+        let (fuel, fuel_amount) = reactants.remove(0);
+        let per_kg_fuel = |(id, amount): (MaterialId, RawScalar)| (id, amount / fuel_amount);
+        Ok(Reaction {
+            name: definition.name,
+            fuel,
+            reactants: reactants.into_iter().map(per_kg_fuel).collect(),
+            products: products.into_iter().map(per_kg_fuel).collect(),
+            ignition_temperature: definition.ignition_temperature,
+            energy: definition.energy,
+            rate: definition.rate,
+        })
+    }
+}
+
+/// All reactions that can happen in the game.
+///
+/// This is inserted into the world as a resource, and used by the [ReactionSystem](crate::system::ReactionSystem).
+#[derive(Default)]
+pub struct ReactionRegistry {
+    reactions: Vec<Reaction>,
+}
+
+impl ReactionRegistry {
+    /// Load the reactions from a RON file, containing a list of [ReactionDefinition].
+    #[cfg(feature = "serde")]
+    pub fn load(
+        path: impl AsRef<std::path::Path>,
+        materials: &MaterialRegistry,
+    ) -> Result<Self, ReactionRegistryError> {
+        let contents = std::fs::read_to_string(path).map_err(ReactionRegistryError::Io)?;
+        Self::from_ron(&contents, materials)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_ron(
+        contents: &str,
+        materials: &MaterialRegistry,
+    ) -> Result<Self, ReactionRegistryError> {
+        let definitions: Vec<ReactionDefinition> =
+            ron::de::from_str(contents).map_err(ReactionRegistryError::Parse)?;
+        let mut registry = Self::default();
+        for definition in definitions {
+            registry.register(Reaction::new(definition, materials)?);
+        }
+        Ok(registry)
+    }
+
+    pub fn register(&mut self, reaction: Reaction) {
+        self.reactions.push(reaction);
+    }
+
+    /// All reactions that use `fuel` as their fuel.
+    pub fn with_fuel(&self, fuel: MaterialId) -> impl Iterator<Item = &Reaction> {
+        self.reactions
+            .iter()
+            .filter(move |reaction| reaction.fuel == fuel)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Reaction> {
+        self.reactions.iter()
+    }
+}
+
+#[derive(Debug)]
+pub enum ReactionRegistryError {
+    Io(std::io::Error),
+    #[cfg(feature = "serde")]
+    Parse(ron::de::Error),
+    UnknownMaterial {
+        reaction: String,
+        material: String,
+    },
+    InvalidAmount {
+        reaction: String,
+        material: String,
+        amount: RawScalar,
+    },
+    NoFuel(String),
+}
+
+impl fmt::Display for ReactionRegistryError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReactionRegistryError::Io(e) => write!(fmt, "Could not read the reaction file: {}", e),
+            #[cfg(feature = "serde")]
+            ReactionRegistryError::Parse(e) => {
+                write!(fmt, "Could not parse the reaction file: {}", e)
+            }
+            ReactionRegistryError::UnknownMaterial { reaction, material } => write!(
+                fmt,
+                "Reaction {:?} uses unknown material {:?}",
+                reaction, material
+            ),
+            ReactionRegistryError::InvalidAmount {
+                reaction,
+                material,
+                amount,
+            } => write!(
+                fmt,
+                "Reaction {:?} has an invalid amount {} of {:?}",
+                reaction, amount, material
+            ),
+            ReactionRegistryError::NoFuel(reaction) => {
+                write!(fmt, "Reaction {:?} does not have any reactants", reaction)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReactionRegistryError {}
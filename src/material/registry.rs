@@ -0,0 +1,699 @@
+use super::{BuildError, Material, Mixture};
+use crate::component::{Composition, Mass, MaterialColor, MaterialKind, Phase, Volume};
+use crate::grid_storage::Position;
+use crate::units::{
+    Density, Kelvin, KilogramPerMole, OhmMeter, Pascal, PerKelvin, RawScalar, SpecificEnergy,
+    SpecificHeatCapacity, ThermalConductivity,
+};
+use specs::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Identifies a material in the [MaterialRegistry].
+///
+/// Entities store this in their [MaterialKind] component.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaterialId(u16);
+
+/// A material as it is defined in the material files.
+///
+/// The top-level properties apply to every phase, unless they are overridden in [solid](MaterialDefinition::solid), [liquid](MaterialDefinition::liquid) or [gas](MaterialDefinition::gas).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaterialDefinition {
+    pub name: String,
+    pub specific_heat_capacity: SpecificHeatCapacity,
+    pub thermal_conductivity: ThermalConductivity,
+    pub density: Density,
+    /// How well the surface radiates heat, between 0 (perfect mirror) and 1 (black body)
+    pub emissivity: RawScalar,
+    pub color: MaterialColor,
+    /// Loose solids like sand and ore that fall down and pile up, instead of holding their shape
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub granular: bool,
+    /// How strongly the material resists an electric current. Materials without a resistivity are insulators, and gases never conduct
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub electrical_resistivity: Option<OhmMeter>,
+    /// The stress at which a solid breaks when it is pressed together. Only solids with a strength, that are not [granular](MaterialDefinition::granular), can carry a structure
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub compressive_strength: Option<Pascal>,
+    /// The stress at which a solid breaks when it is pulled apart or bent. This is required for materials with a [compressive_strength](MaterialDefinition::compressive_strength)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tensile_strength: Option<Pascal>,
+    /// How much a solid grows per kelvin that it is heated, as a fraction of its length
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub thermal_expansion: Option<PerKelvin>,
+    /// How stiff a solid is: the stress it takes to stretch it by its own length. This is required for materials with a [thermal_expansion](MaterialDefinition::thermal_expansion)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub elastic_modulus: Option<Pascal>,
+    /// The temperature at which this material turns from a solid into a liquid, if it has a liquid phase
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub melting_point: Option<Kelvin>,
+    /// The heat that it takes to melt a kilogram at the [melting_point](MaterialDefinition::melting_point). Materials without it melt without taking up any heat
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub heat_of_fusion: Option<SpecificEnergy>,
+    /// The temperature at which this material turns from a liquid into a gas, if it has a gas phase
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub boiling_point: Option<Kelvin>,
+    /// The heat that it takes to boil a kilogram at the [boiling_point](MaterialDefinition::boiling_point), or to sublimate it if it has no melting point
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub heat_of_vaporization: Option<SpecificEnergy>,
+    /// The mass of one mole of this material, used to calculate the pressure of its gas. This is required for materials with a [boiling_point](MaterialDefinition::boiling_point)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub molar_mass: Option<KilogramPerMole>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub solid: PhaseOverrides,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub liquid: PhaseOverrides,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub gas: PhaseOverrides,
+}
+
+/// Properties of a material that are different in a specific [Phase].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhaseOverrides {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub specific_heat_capacity: Option<SpecificHeatCapacity>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub thermal_conductivity: Option<ThermalConductivity>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub density: Option<Density>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub color: Option<MaterialColor>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub electrical_resistivity: Option<OhmMeter>,
+}
+
+/// The properties of a material in a single [Phase].
+#[derive(Debug, Copy, Clone)]
+pub struct MaterialProperties {
+    pub specific_heat_capacity: SpecificHeatCapacity,
+    pub thermal_conductivity: ThermalConductivity,
+    pub density: Density,
+    pub color: MaterialColor,
+    /// `None` for insulators
+    pub electrical_resistivity: Option<OhmMeter>,
+}
+
+impl MaterialDefinition {
+    pub fn phase_at(&self, temperature: Kelvin) -> Phase {
+        Phase::at(temperature, self.melting_point, self.boiling_point)
+    }
+
+    pub fn properties(&self, phase: Phase) -> MaterialProperties {
+        let overrides = match phase {
+            Phase::Solid => &self.solid,
+            Phase::Liquid => &self.liquid,
+            Phase::Gas => &self.gas,
+        };
+        MaterialProperties {
+            specific_heat_capacity: overrides
+                .specific_heat_capacity
+                .unwrap_or(self.specific_heat_capacity),
+            thermal_conductivity: overrides
+                .thermal_conductivity
+                .unwrap_or(self.thermal_conductivity),
+            density: overrides.density.unwrap_or(self.density),
+            color: overrides.color.unwrap_or(self.color),
+            electrical_resistivity: match phase {
+                Phase::Gas => None,
+                _ => overrides
+                    .electrical_resistivity
+                    .or(self.electrical_resistivity),
+            },
+        }
+    }
+
+    /// Check that the properties of this material are physically plausible, to catch typos and unit mistakes in the material files.
+    ///
+    /// The ranges span everything from hydrogen gas to osmium.
+    pub fn validate(&self) -> Result<(), MaterialRegistryError> {
+        let check = |property: &'static str, value: RawScalar, min: RawScalar, max: RawScalar| {
+            if value >= min && value <= max {
+                Ok(())
+            } else {
+                Err(MaterialRegistryError::OutOfRange {
+                    material: self.name.clone(),
+                    property,
+                    value,
+                })
+            }
+        };
+        for phase in &Phase::ALL {
+            let properties = self.properties(*phase);
+            check(
+                "specific_heat_capacity",
+                properties.specific_heat_capacity.raw(),
+                100.0,
+                15_000.0,
+            )?;
+            check(
+                "thermal_conductivity",
+                properties.thermal_conductivity.raw(),
+                0.005,
+                500.0,
+            )?;
+            check("density", properties.density.raw(), 0.05, 23_000.0)?;
+            if let Some(resistivity) = properties.electrical_resistivity {
+                check("electrical_resistivity", resistivity.raw(), 1e-9, 1e10)?;
+            }
+        }
+        check("emissivity", self.emissivity, 0.0, 1.0)?;
+        if let Some(melting_point) = self.melting_point {
+            check("melting_point", melting_point.raw(), 1.0, 4_000.0)?;
+        }
+        let missing = |property: &'static str| MaterialRegistryError::MissingProperty {
+            material: self.name.clone(),
+            property,
+        };
+        if let Some(heat_of_fusion) = self.heat_of_fusion {
+            check("heat_of_fusion", heat_of_fusion.raw(), 1e3, 2e7)?;
+            self.melting_point.ok_or_else(|| missing("melting_point"))?;
+        }
+        if let Some(heat_of_vaporization) = self.heat_of_vaporization {
+            check("heat_of_vaporization", heat_of_vaporization.raw(), 1e3, 2e7)?;
+            self.boiling_point.ok_or_else(|| missing("boiling_point"))?;
+        }
+        if let Some(boiling_point) = self.boiling_point {
+            check("boiling_point", boiling_point.raw(), 1.0, 6_000.0)?;
+            if self.molar_mass.is_none() {
+                return Err(MaterialRegistryError::MissingProperty {
+                    material: self.name.clone(),
+                    property: "molar_mass",
+                });
+            }
+        }
+        match (self.compressive_strength, self.tensile_strength) {
+            (Some(compressive_strength), Some(tensile_strength)) => {
+                check(
+                    "compressive_strength",
+                    compressive_strength.raw(),
+                    1e5,
+                    1e10,
+                )?;
+                check("tensile_strength", tensile_strength.raw(), 1e5, 1e10)?;
+            }
+            (Some(_), None) => {
+                return Err(MaterialRegistryError::MissingProperty {
+                    material: self.name.clone(),
+                    property: "tensile_strength",
+                });
+            }
+            (None, Some(_)) => {
+                return Err(MaterialRegistryError::MissingProperty {
+                    material: self.name.clone(),
+                    property: "compressive_strength",
+                });
+            }
+            (None, None) => {}
+        }
+        match (self.thermal_expansion, self.elastic_modulus) {
+            (Some(thermal_expansion), Some(elastic_modulus)) => {
+                check("thermal_expansion", thermal_expansion.raw(), 1e-7, 1e-3)?;
+                check("elastic_modulus", elastic_modulus.raw(), 1e8, 1e12)?;
+            }
+            (Some(_), None) => {
+                return Err(MaterialRegistryError::MissingProperty {
+                    material: self.name.clone(),
+                    property: "elastic_modulus",
+                });
+            }
+            (None, Some(_)) => {
+                return Err(MaterialRegistryError::MissingProperty {
+                    material: self.name.clone(),
+                    property: "thermal_expansion",
+                });
+            }
+            (None, None) => {}
+        }
+        if let Some(molar_mass) = self.molar_mass {
+            check("molar_mass", molar_mass.raw(), 0.001, 1.0)?;
+        }
+        if let (Some(melting_point), Some(boiling_point)) = (self.melting_point, self.boiling_point)
+        {
+            if melting_point > boiling_point {
+                return Err(MaterialRegistryError::OutOfRange {
+                    material: self.name.clone(),
+                    property: "boiling_point",
+                    value: boiling_point.raw(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A [MaterialDefinition] in a specific [Phase], together with the id it was registered under.
+#[derive(Copy, Clone)]
+pub struct RegisteredMaterial<'a> {
+    pub id: MaterialId,
+    pub definition: &'a MaterialDefinition,
+    pub phase: Phase,
+}
+
+impl<'a> Material for RegisteredMaterial<'a> {
+    fn kind(&self) -> MaterialKind {
+        MaterialKind(self.id)
+    }
+    fn phase(&self) -> Phase {
+        self.phase
+    }
+    fn specific_heat_capacity(&self) -> SpecificHeatCapacity {
+        self.definition
+            .properties(self.phase)
+            .specific_heat_capacity
+    }
+    fn thermal_conductivity(&self) -> ThermalConductivity {
+        self.definition.properties(self.phase).thermal_conductivity
+    }
+    fn material_color(&self) -> MaterialColor {
+        self.definition.properties(self.phase).color
+    }
+    fn density(&self) -> Density {
+        self.definition.properties(self.phase).density
+    }
+    fn electrical_resistivity(&self) -> Option<OhmMeter> {
+        self.definition
+            .properties(self.phase)
+            .electrical_resistivity
+    }
+}
+
+/// All materials that are known to the game.
+///
+/// This is inserted into the world as a resource, so systems can look up the properties of a [MaterialId].
+#[derive(Default)]
+pub struct MaterialRegistry {
+    materials: Vec<MaterialDefinition>,
+    by_name: HashMap<String, MaterialId>,
+}
+
+impl MaterialRegistry {
+    /// Load the materials from a RON file, containing a list of [MaterialDefinition].
+    #[cfg(feature = "serde")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, MaterialRegistryError> {
+        let contents = std::fs::read_to_string(path).map_err(MaterialRegistryError::Io)?;
+        Self::from_ron(&contents)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_ron(contents: &str) -> Result<Self, MaterialRegistryError> {
+        let definitions: Vec<MaterialDefinition> =
+            ron::de::from_str(contents).map_err(MaterialRegistryError::Parse)?;
+        let mut registry = Self::default();
+        for definition in definitions {
+            registry.register(definition)?;
+        }
+        Ok(registry)
+    }
+
+    pub fn register(
+        &mut self,
+        definition: MaterialDefinition,
+    ) -> Result<MaterialId, MaterialRegistryError> {
+        definition.validate()?;
+        if self.by_name.contains_key(&definition.name) {
+            return Err(MaterialRegistryError::DuplicateName(definition.name));
+        }
+        if self.materials.len() >= u16::MAX as usize {
+            return Err(MaterialRegistryError::TooManyMaterials);
+        }
+        let id = MaterialId(self.materials.len() as u16);
+        self.by_name.insert(definition.name.clone(), id);
+        self.materials.push(definition);
+        Ok(id)
+    }
+
+    /// Look up a material. Returns `None` if the id does not belong to this registry, e.g. because it was deserialized from a save with other materials
+    pub fn get(&self, id: MaterialId) -> Option<&MaterialDefinition> {
+        self.materials.get(id.0 as usize)
+    }
+
+    pub fn material(&self, id: MaterialId, phase: Phase) -> Option<RegisteredMaterial> {
+        Some(RegisteredMaterial {
+            id,
+            definition: self.get(id)?,
+            phase,
+        })
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<MaterialId> {
+        self.by_name.get(name).copied()
+    }
+
+    /// Same as [by_name](MaterialRegistry::by_name), but returns an error if the material does not exist.
+    pub fn require(&self, name: &str) -> Result<MaterialId, MaterialRegistryError> {
+        self.by_name(name)
+            .ok_or_else(|| MaterialRegistryError::UnknownMaterial(name.to_owned()))
+    }
+
+    pub fn kind(&self, name: &str) -> Option<MaterialKind> {
+        self.by_name(name).map(MaterialKind)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = MaterialId> {
+        (0..self.materials.len() as u16).map(MaterialId)
+    }
+
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.materials.is_empty()
+    }
+
+    pub fn build_entity<B: Builder>(
+        &self,
+        id: MaterialId,
+        builder: B,
+        temperature: Kelvin,
+        mass: Mass,
+        position: Position,
+    ) -> Result<Entity, BuildError> {
+        let definition = self.get(id).ok_or(BuildError::UnknownMaterial(id))?;
+        let phase = definition.phase_at(temperature);
+        Ok(RegisteredMaterial {
+            id,
+            definition,
+            phase,
+        }
+        .build_entity(builder, temperature, mass, position)?)
+    }
+
+    /// Build an entity that is a mixture of several materials. See [Material::build_entity].
+    pub fn build_mixture<B: Builder>(
+        &self,
+        composition: Composition,
+        builder: B,
+        temperature: Kelvin,
+        mass: Mass,
+        position: Position,
+    ) -> Result<Entity, BuildError> {
+        let dominant = composition.dominant();
+        let phase = self
+            .get(dominant)
+            .ok_or(BuildError::UnknownMaterial(dominant))?
+            .phase_at(temperature);
+        let mixture = Mixture::new(&composition, self, phase).ok_or_else(|| {
+            let missing = composition
+                .parts()
+                .iter()
+                .map(|(id, _)| *id)
+                .find(|id| self.get(*id).is_none());
+            BuildError::UnknownMaterial(missing.unwrap_or(dominant))
+        })?;
+        // Check this before adding the composition, so we don't leave a half-built entity behind
+        Volume::new(mass, mixture.density(), phase)?;
+        Ok(mixture.build_entity(
+            builder.with(composition.clone()),
+            temperature,
+            mass,
+            position,
+        )?)
+    }
+}
+
+#[derive(Debug)]
+pub enum MaterialRegistryError {
+    Io(std::io::Error),
+    #[cfg(feature = "serde")]
+    Parse(ron::de::Error),
+    DuplicateName(String),
+    UnknownMaterial(String),
+    TooManyMaterials,
+    OutOfRange {
+        material: String,
+        property: &'static str,
+        value: RawScalar,
+    },
+    MissingProperty {
+        material: String,
+        property: &'static str,
+    },
+}
+
+impl fmt::Display for MaterialRegistryError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MaterialRegistryError::Io(e) => write!(fmt, "Could not read the material file: {}", e),
+            #[cfg(feature = "serde")]
+            MaterialRegistryError::Parse(e) => {
+                write!(fmt, "Could not parse the material file: {}", e)
+            }
+            MaterialRegistryError::DuplicateName(name) => {
+                write!(fmt, "Material {:?} is defined more than once", name)
+            }
+            MaterialRegistryError::UnknownMaterial(name) => {
+                write!(fmt, "Material {:?} is not defined", name)
+            }
+            MaterialRegistryError::TooManyMaterials => {
+                write!(
+                    fmt,
+                    "Too many materials, at most {} are supported",
+                    u16::MAX
+                )
+            }
+            MaterialRegistryError::OutOfRange {
+                material,
+                property,
+                value,
+            } => write!(
+                fmt,
+                "Material {:?} has an implausible {} of {}",
+                material, property, value
+            ),
+            MaterialRegistryError::MissingProperty { material, property } => {
+                write!(fmt, "Material {:?} is missing its {}", material, property)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MaterialRegistryError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The specific heat capacity (J/(kg K)), thermal conductivity (W/(m K)) and density (kg/m³) of every material, as given in the source cited in assets/materials.ron
+    #[cfg(feature = "serde")]
+    const REFERENCE_PROPERTIES: &[(&str, Phase, RawScalar, RawScalar, RawScalar)] = &[
+        // Incropera, table A.1
+        ("steel", Phase::Solid, 434.0, 60.5, 7854.0),
+        ("iron", Phase::Solid, 447.0, 80.2, 7870.0),
+        ("copper", Phase::Solid, 385.0, 401.0, 8933.0),
+        ("aluminium", Phase::Solid, 903.0, 237.0, 2702.0),
+        ("gold", Phase::Solid, 129.0, 317.0, 19300.0),
+        ("lead", Phase::Solid, 129.0, 35.3, 11340.0),
+        ("uranium", Phase::Solid, 116.0, 27.6, 19070.0),
+        // Engineering ToolBox
+        ("iron ore", Phase::Solid, 650.0, 11.3, 5260.0),
+        ("ash", Phase::Solid, 800.0, 0.1, 700.0),
+        ("salt", Phase::Solid, 880.0, 6.5, 2165.0),
+        // Incropera, table A.3
+        ("coal", Phase::Solid, 1260.0, 0.26, 1350.0),
+        ("wood", Phase::Solid, 2385.0, 0.17, 545.0),
+        ("stone", Phase::Solid, 775.0, 2.79, 2630.0),
+        ("sand", Phase::Solid, 800.0, 0.27, 1515.0),
+        ("soil", Phase::Solid, 1840.0, 0.52, 2050.0),
+        ("concrete", Phase::Solid, 880.0, 1.4, 2300.0),
+        ("brick", Phase::Solid, 835.0, 0.72, 1920.0),
+        ("glass", Phase::Solid, 750.0, 1.4, 2500.0),
+        ("water", Phase::Solid, 2040.0, 1.88, 920.0),
+        // Incropera, table A.6
+        ("water", Phase::Liquid, 4179.0, 0.609, 997.0),
+        ("water", Phase::Gas, 2080.0, 0.0248, 0.596),
+        // Incropera, table A.4
+        ("air", Phase::Gas, 1007.0, 0.0263, 1.1614),
+        ("nitrogen", Phase::Gas, 1041.0, 0.0259, 1.1233),
+        ("oxygen", Phase::Gas, 920.0, 0.0268, 1.284),
+        ("carbon dioxide", Phase::Gas, 851.0, 0.0166, 1.797),
+    ];
+
+    /// The melting point (K), heat of fusion (J/kg), boiling point (K) and heat of vaporization (J/kg) of every material that has them, from the CRC Handbook
+    #[cfg(feature = "serde")]
+    #[allow(clippy::type_complexity)]
+    const REFERENCE_TRANSITIONS: &[(
+        &str,
+        Option<RawScalar>,
+        Option<RawScalar>,
+        Option<RawScalar>,
+        Option<RawScalar>,
+    )] = &[
+        (
+            "steel",
+            Some(1700.0),
+            Some(247e3),
+            Some(3134.0),
+            Some(6.09e6),
+        ),
+        (
+            "iron",
+            Some(1811.0),
+            Some(247e3),
+            Some(3134.0),
+            Some(6.09e6),
+        ),
+        (
+            "copper",
+            Some(1358.0),
+            Some(209e3),
+            Some(2835.0),
+            Some(4.73e6),
+        ),
+        (
+            "aluminium",
+            Some(933.0),
+            Some(397e3),
+            Some(2792.0),
+            Some(10.9e6),
+        ),
+        (
+            "gold",
+            Some(1336.0),
+            Some(63.7e3),
+            Some(3129.0),
+            Some(1.645e6),
+        ),
+        ("lead", Some(601.0), Some(23.0e3), Some(2022.0), Some(866e3)),
+        (
+            "uranium",
+            Some(1405.0),
+            Some(38.4e3),
+            Some(4404.0),
+            Some(1.752e6),
+        ),
+        ("iron ore", Some(1838.0), None, None, None),
+        ("ash", Some(1500.0), None, None, None),
+        ("stone", Some(1500.0), None, None, None),
+        ("sand", Some(1986.0), Some(160e3), Some(2503.0), None),
+        ("glass", Some(1300.0), None, None, None),
+        ("salt", Some(1074.0), Some(482e3), Some(1738.0), None),
+        (
+            "water",
+            Some(273.15),
+            Some(333.6e3),
+            Some(373.15),
+            Some(2.257e6),
+        ),
+        ("air", Some(63.15), Some(25.3e3), Some(77.36), Some(199e3)),
+        (
+            "nitrogen",
+            Some(63.15),
+            Some(25.3e3),
+            Some(77.36),
+            Some(199e3),
+        ),
+        (
+            "oxygen",
+            Some(54.36),
+            Some(13.8e3),
+            Some(90.19),
+            Some(213e3),
+        ),
+        ("carbon dioxide", None, None, Some(194.7), Some(573e3)),
+    ];
+
+    #[cfg(feature = "serde")]
+    fn assert_close(material: &str, property: &str, value: RawScalar, reference: RawScalar) {
+        assert!(
+            (value - reference).abs() <= reference.abs() * 1e-3,
+            "{} has a {} of {}, but its source gives {}",
+            material,
+            property,
+            value,
+            reference
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn materials_match_their_sources() {
+        let registry =
+            MaterialRegistry::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/materials.ron"))
+                .unwrap();
+
+        for (name, phase, capacity, conductivity, density) in REFERENCE_PROPERTIES {
+            let id = registry
+                .by_name(name)
+                .unwrap_or_else(|| panic!("{} is not in the library", name));
+            let properties = registry.get(id).unwrap().properties(*phase);
+            let capacity_name = format!("{:?} specific heat capacity", phase);
+            let conductivity_name = format!("{:?} thermal conductivity", phase);
+            let density_name = format!("{:?} density", phase);
+            assert_close(
+                name,
+                &capacity_name,
+                properties.specific_heat_capacity.raw(),
+                *capacity,
+            );
+            assert_close(
+                name,
+                &conductivity_name,
+                properties.thermal_conductivity.raw(),
+                *conductivity,
+            );
+            assert_close(name, &density_name, properties.density.raw(), *density);
+        }
+
+        for id in registry.ids() {
+            let definition = registry.get(id).unwrap();
+            let (_, melting_point, heat_of_fusion, boiling_point, heat_of_vaporization) =
+                REFERENCE_TRANSITIONS
+                    .iter()
+                    .find(|(name, ..)| *name == definition.name)
+                    .copied()
+                    .unwrap_or((&definition.name, None, None, None, None));
+            for (property, value, reference) in &[
+                (
+                    "melting point",
+                    definition.melting_point.map(Kelvin::raw),
+                    melting_point,
+                ),
+                (
+                    "heat of fusion",
+                    definition.heat_of_fusion.map(SpecificEnergy::raw),
+                    heat_of_fusion,
+                ),
+                (
+                    "boiling point",
+                    definition.boiling_point.map(Kelvin::raw),
+                    boiling_point,
+                ),
+                (
+                    "heat of vaporization",
+                    definition.heat_of_vaporization.map(SpecificEnergy::raw),
+                    heat_of_vaporization,
+                ),
+            ] {
+                match (value, reference) {
+                    (Some(value), Some(reference)) => {
+                        assert_close(&definition.name, property, *value, *reference)
+                    }
+                    (None, None) => {}
+                    _ => panic!(
+                        "{} has a {} of {:?}, but its source gives {:?}",
+                        definition.name, property, value, reference
+                    ),
+                }
+            }
+            assert!(
+                REFERENCE_PROPERTIES
+                    .iter()
+                    .any(|(name, ..)| *name == definition.name),
+                "{} has no reference values",
+                definition.name
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_id() {
+        let registry = MaterialRegistry::default();
+        assert!(registry.get(MaterialId(0)).is_none());
+        assert!(registry.material(MaterialId(0), Phase::Solid).is_none());
+    }
+}
@@ -1,5 +1,12 @@
+use crate::units::RawScalar;
+
 #[repr(transparent)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "(u8, u8, u8, u8)", into = "(u8, u8, u8, u8)")
+)]
 pub struct Color(sdl2::pixels::Color);
 
 impl Color {
@@ -7,7 +14,42 @@ impl Color {
         Self(sdl2::pixels::Color::RGB(r, g, b))
     }
 
+    /// Average a list of colors, each with a weight. The weights don't have to add up to 1.
+    pub fn weighted_average(colors: impl IntoIterator<Item = (Color, RawScalar)>) -> Self {
+        let mut total: [RawScalar; 4] = [0.0; 4];
+        let mut total_weight = 0.0;
+        for (color, weight) in colors {
+            let (r, g, b, a) = color.0.rgba();
+            for (total, channel) in total.iter_mut().zip(&[r, g, b, a]) {
+                *total += RawScalar::from(*channel) * weight;
+            }
+            total_weight += weight;
+        }
+        if total_weight <= 0.0 {
+            return Self::rgb(0, 0, 0);
+        }
+        let [r, g, b, a] = total;
+        Self(sdl2::pixels::Color::RGBA(
+            (r / total_weight) as u8,
+            (g / total_weight) as u8,
+            (b / total_weight) as u8,
+            (a / total_weight) as u8,
+        ))
+    }
+
     pub(super) fn into_sdl(self) -> sdl2::pixels::Color {
         self.0
     }
 }
+
+impl From<(u8, u8, u8, u8)> for Color {
+    fn from((r, g, b, a): (u8, u8, u8, u8)) -> Self {
+        Self(sdl2::pixels::Color::RGBA(r, g, b, a))
+    }
+}
+
+impl From<Color> for (u8, u8, u8, u8) {
+    fn from(color: Color) -> Self {
+        color.0.rgba()
+    }
+}
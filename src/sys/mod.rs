@@ -5,9 +5,9 @@ pub use self::color::Color;
 pub use self::event::Event;
 use crate::units::si::Kelvin;
 
-use crate::component::MaterialColor;
+use crate::component::{MaterialColor, Phase};
 use crate::grid_storage::Position;
-use sdl2::rect::Rect;
+use sdl2::rect::{Point, Rect};
 use std::time::Instant;
 
 const FLASHY_BACKGROUND_ENABLED: bool = cfg!(debug_assertions);
@@ -115,15 +115,71 @@ impl Window {
         self.canvas.clear();
     }
 
-    pub fn draw_material(&mut self, material_color: MaterialColor, position: Position) {
+    /// Draw a tile in the color of its material. Solids fill the entire tile, liquids leave a gap at the top and gases are drawn as a grid of dots.
+    pub fn draw_material(
+        &mut self,
+        material_color: MaterialColor,
+        position: Position,
+        phase: Phase,
+    ) {
+        let (x, y) = (
+            (position.x.raw() * 64.) as i32,
+            (position.y.raw() * 64.) as i32,
+        );
         self.canvas.set_draw_color(material_color.0.into_sdl());
+        match phase {
+            Phase::Solid => self.canvas.fill_rect(Rect::new(x, y, 64, 64)).unwrap(),
+            Phase::Liquid => self.canvas.fill_rect(Rect::new(x, y + 16, 64, 48)).unwrap(),
+            Phase::Gas => {
+                for dx in (0..64).step_by(8) {
+                    for dy in (0..64).step_by(8) {
+                        self.canvas
+                            .fill_rect(Rect::new(x + dx + 2, y + dy + 2, 4, 4))
+                            .unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draw flames over a tile that is [Burning](crate::component::Burning).
+    pub fn draw_fire(&mut self, position: Position) {
+        let (x, y) = (
+            (position.x.raw() * 64.) as i32,
+            (position.y.raw() * 64.) as i32,
+        );
         self.canvas
-            .fill_rect(Rect::new(
-                (position.x.raw() * 64.) as i32,
-                (position.y.raw() * 64.) as i32,
-                64,
-                64,
-            ))
+            .set_draw_color(sdl2::pixels::Color::RGB(255, 96, 0));
+        self.canvas
+            .fill_rect(Rect::new(x + 16, y + 24, 32, 40))
+            .unwrap();
+        self.canvas
+            .set_draw_color(sdl2::pixels::Color::RGB(255, 220, 0));
+        self.canvas
+            .fill_rect(Rect::new(x + 24, y + 40, 16, 24))
+            .unwrap();
+    }
+
+    /// Draw cracks over a tile that is [cracked](crate::component::Damage::is_cracked).
+    pub fn draw_cracks(&mut self, position: Position) {
+        let (x, y) = (
+            (position.x.raw() * 64.) as i32,
+            (position.y.raw() * 64.) as i32,
+        );
+        self.canvas
+            .set_draw_color(sdl2::pixels::Color::RGB(20, 20, 20));
+        self.canvas
+            .draw_lines(
+                &[
+                    Point::new(x + 8, y + 4),
+                    Point::new(x + 28, y + 24),
+                    Point::new(x + 20, y + 40),
+                    Point::new(x + 44, y + 60),
+                ][..],
+            )
+            .unwrap();
+        self.canvas
+            .draw_line(Point::new(x + 28, y + 24), Point::new(x + 56, y + 16))
             .unwrap();
     }
 
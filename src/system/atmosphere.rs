@@ -0,0 +1,637 @@
+use super::{SimulationError, SimulationErrors};
+use crate::component::{
+    Composition, DeltaTime, Heat, Mass, MaterialKind, Phase, Volume, MIN_GAS_VOLUME,
+    TILE_FACE_AREA, TILE_VOLUME,
+};
+use crate::material::{MaterialId, MaterialRegistry, MaterialRegistryError};
+use crate::units::{
+    scalar, HeatCapacity, Joule, Kelvin, Kilogram, Meter, MeterCubed, MeterSquaredPerSecond,
+    Pascal, RawScalar, SpecificHeatCapacity,
+};
+use crate::{GridStorageExt, Position, NEIGHBOUR_OFFSETS};
+use noisy_float::types::r32;
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// The gases that make up fresh air, and their mole fractions. Argon and the trace gases are counted as nitrogen
+pub const AIR: &[(&str, RawScalar)] = &[("nitrogen", 0.79), ("oxygen", 0.21)];
+
+/// Air mixes mostly by convection, which is far faster than molecular diffusion (about 2e-5 m²/s). This is roughly the effective diffusivity of still indoor air
+const DIFFUSIVITY: MeterSquaredPerSecond = MeterSquaredPerSecond::new_const(0.01);
+/// The distance between the centers of two neighbouring tiles
+const TILE_DISTANCE: Meter = Meter::new_const(1.0);
+/// A tile gives at most this fraction of a gas to a single neighbour per tick, so it never gives away more than it has
+const MAX_DIFFUSION_FRACTION: RawScalar = 0.2;
+/// Gas is only spread to a tile that does not have it yet once at least this much would move, so trace amounts don't end up everywhere
+const MIN_SPAWN_MASS: Kilogram = Kilogram::new_const(0.000_01);
+/// The layer of still gas on a surface, through which heat is conducted. For air this gives a heat transfer coefficient of about 10 W/(m² K), which is typical for natural convection
+pub(super) const BOUNDARY_LAYER: Meter = Meter::new_const(0.002_5);
+
+/// The gases in a single tile.
+#[derive(Debug, Clone)]
+pub struct AtmosphereTile {
+    /// The sum of the partial pressures of all gases
+    pub pressure: Pascal,
+    /// The mean temperature of the gases, or `None` if the tile is a vacuum
+    pub temperature: Option<Kelvin>,
+    pub composition: Vec<(MaterialId, Kilogram)>,
+}
+
+impl AtmosphereTile {
+    fn vacuum() -> Self {
+        AtmosphereTile {
+            pressure: Pascal::new_const(0.0),
+            temperature: None,
+            composition: Vec::new(),
+        }
+    }
+
+    /// The mass of a single gas in this tile, e.g. to see how much oxygen is available.
+    pub fn mass_of(&self, id: MaterialId) -> Kilogram {
+        self.composition
+            .iter()
+            .find(|(other, _)| *other == id)
+            .map(|(_, mass)| *mass)
+            .unwrap_or_else(|| Kilogram::new_const(0.0))
+    }
+
+    pub fn is_vacuum(&self) -> bool {
+        self.composition.is_empty()
+    }
+}
+
+/// The pressure, temperature and composition of the gases on every tile.
+///
+/// This is a resource that is updated every tick by the [AtmosphereSystem]. The gases themselves are entities, just like solids and liquids.
+pub struct Atmosphere {
+    width: usize,
+    height: usize,
+    tiles: Vec<AtmosphereTile>,
+}
+
+impl Atmosphere {
+    pub fn new(width: usize, height: usize) -> Self {
+        Atmosphere {
+            width,
+            height,
+            tiles: vec![AtmosphereTile::vacuum(); width * height],
+        }
+    }
+
+    pub fn at(&self, position: Position) -> Option<&AtmosphereTile> {
+        let index = self.index(position)?;
+        self.tiles.get(index)
+    }
+
+    fn index(&self, position: Position) -> Option<usize> {
+        let (x, y) = (position.x.raw(), position.y.raw());
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+            return None;
+        }
+        Some(y as usize * self.width + x as usize)
+    }
+
+    fn positions(&self) -> impl Iterator<Item = Position> {
+        let width = self.width;
+        (0..self.width * self.height).map(move |index| Position {
+            x: r32((index % width) as f32),
+            y: r32((index / width) as f32),
+        })
+    }
+}
+
+/// Fill the space that is left in every tile with fresh [AIR], at the given temperature and pressure.
+///
+/// Tiles that already contain a gas, or that are completely filled, are skipped. This needs the [Atmosphere] resource to know the size of the grid, and reports gas that could not be placed to the [SimulationErrors] resource.
+pub fn fill_atmosphere(
+    world: &mut World,
+    registry: &MaterialRegistry,
+    temperature: Kelvin,
+    pressure: Pascal,
+) -> Result<(), MaterialRegistryError> {
+    let mut air = Vec::with_capacity(AIR.len());
+    for (name, fraction) in AIR {
+        let id = registry.require(name)?;
+        let molar_mass = registry
+            .get(id)
+            .and_then(|definition| definition.molar_mass)
+            .ok_or(MaterialRegistryError::MissingProperty {
+                material: (*name).to_owned(),
+                property: "molar_mass",
+            })?;
+        air.push((id, molar_mass, *fraction));
+    }
+
+    let mut spawns = Vec::new();
+    world.exec(
+        |(atmosphere, position_storage, phase_storage, volume_storage): (
+            ReadExpect<Atmosphere>,
+            ReadStorage<Position>,
+            ReadStorage<Phase>,
+            ReadStorage<Volume>,
+        )| {
+            for position in atmosphere.positions() {
+                let mut occupied = MeterCubed::new_const(0.0);
+                let mut has_gas = false;
+                for (entity, _) in position_storage.on_tile(position) {
+                    if phase_storage.get(entity) == Some(&Phase::Gas) {
+                        has_gas = true;
+                    } else {
+                        occupied += volume_storage
+                            .get(entity)
+                            .copied()
+                            .unwrap_or_else(Volume::full)
+                            .0;
+                    }
+                }
+                if has_gas || TILE_VOLUME - occupied < MIN_GAS_VOLUME {
+                    continue;
+                }
+                let volume = Volume::free(occupied);
+                for (id, molar_mass, fraction) in &air {
+                    // The pressure of an ideal gas is proportional to its mass
+                    let pressure_per_kg = Pascal::ideal_gas(
+                        Kilogram::new_const(1.0),
+                        *molar_mass,
+                        temperature,
+                        volume,
+                    );
+                    let mass =
+                        Kilogram::new_const(1.0) * (pressure * scalar(*fraction) / pressure_per_kg);
+                    spawns.push((*id, mass, position));
+                }
+            }
+        },
+    );
+
+    for (id, mass, position) in spawns {
+        if let Err(error) =
+            registry.build_entity(id, world.create_entity(), temperature, mass, position)
+        {
+            world
+                .fetch_mut::<SimulationErrors>()
+                .push(SimulationError::Atmosphere {
+                    position,
+                    material: MaterialKind(id)
+                        .name(registry)
+                        .unwrap_or("an unknown material")
+                        .to_owned(),
+                    error,
+                });
+        }
+    }
+    Ok(())
+}
+
+type AtmosphereSystemData<'a> = (
+    ReadExpect<'a, MaterialRegistry>,
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, MaterialKind>,
+    ReadStorage<'a, Composition>,
+    ReadStorage<'a, Phase>,
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, Volume>,
+    WriteStorage<'a, Mass>,
+    WriteStorage<'a, Heat>,
+    Entities<'a>,
+    Read<'a, LazyUpdate>,
+    WriteExpect<'a, Atmosphere>,
+    Write<'a, SimulationErrors>,
+);
+
+/// Mixes the gases of neighbouring tiles, and exchanges heat between the gases and the solids and liquids on the same tile.
+///
+/// Every gas spreads from the tile where it is most concentrated to its neighbours, independent of the other gases. The [FluidSystem](super::FluidSystem) takes care of gas that is pushed around by a difference in pressure.
+///
+/// This also updates the [Atmosphere] resource.
+pub struct AtmosphereSystem;
+
+impl<'a> System<'a> for AtmosphereSystem {
+    type SystemData = AtmosphereSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            registry,
+            delta_time,
+            kind_storage,
+            composition_storage,
+            phase_storage,
+            position_storage,
+            volume_storage,
+            mut mass_storage,
+            mut heat_storage,
+            entities,
+            lazy,
+            mut atmosphere,
+            mut errors,
+        ): Self::SystemData,
+    ) {
+        let mut tiles: HashMap<Position, Tile> = HashMap::new();
+        for (entity, position, phase, mass, heat) in (
+            &entities,
+            &position_storage,
+            phase_storage.maybe(),
+            mass_storage.maybe(),
+            heat_storage.maybe(),
+        )
+            .join()
+        {
+            let tile = tiles.entry(*position).or_insert_with(Tile::new);
+            if phase == Some(&Phase::Gas) {
+                continue;
+            }
+            tile.occupied += volume_storage
+                .get(entity)
+                .copied()
+                .unwrap_or_else(Volume::full)
+                .0;
+            if let (Some(mass), Some(heat)) = (mass, heat) {
+                let temperature = some_or_continue!(heat.try_temperature(*mass).ok());
+                tile.condensed.push(Condensed {
+                    entity,
+                    capacity: heat.capacity * *mass,
+                    temperature,
+                });
+            }
+        }
+        for (entity, kind, phase, position, mass, heat, _) in (
+            &entities,
+            &kind_storage,
+            &phase_storage,
+            &position_storage,
+            &mass_storage,
+            &heat_storage,
+            !&composition_storage,
+        )
+            .join()
+        {
+            if *phase != Phase::Gas || *mass <= Kilogram::new_const(0.0) {
+                continue;
+            }
+            let temperature = some_or_continue!(heat.try_temperature(*mass).ok());
+            let tile = tiles.entry(*position).or_insert_with(Tile::new);
+            tile.gases.push(Gas {
+                entity,
+                kind: kind.id(),
+                mass: *mass,
+                heat: *heat,
+                temperature,
+            });
+        }
+
+        for position in atmosphere.positions().collect::<Vec<_>>() {
+            let summary = match tiles.get(&position) {
+                Some(tile) => tile.summarize(&registry),
+                None => AtmosphereTile::vacuum(),
+            };
+            if let Some(index) = atmosphere.index(position) {
+                atmosphere.tiles[index] = summary;
+            }
+        }
+
+        let mut changes: HashMap<Entity, (Kilogram, Joule)> = HashMap::new();
+        let mut spawns: Vec<Spawn> = Vec::new();
+        let mut change = |entity: Entity, mass: Kilogram, joules: Joule| {
+            let change = changes
+                .entry(entity)
+                .or_insert((Kilogram::new_const(0.0), Joule::new_const(0.0)));
+            change.0 += mass;
+            change.1 += joules;
+        };
+
+        // Sort the tiles, so the gases spread the same way every time
+        let mut positions: Vec<Position> = tiles.keys().copied().collect();
+        positions.sort_by(|a, b| a.y.cmp(&b.y).then(a.x.cmp(&b.x)));
+        for position in positions {
+            let tile = &tiles[&position];
+            let volume = Volume::free(tile.occupied);
+
+            let mut neighbours: Vec<Position> = Vec::with_capacity(4);
+            for (_, other) in position_storage.neighbours(position) {
+                if !neighbours.contains(&other) {
+                    neighbours.push(other);
+                }
+            }
+            // The heat that the gases exchange with the solids and liquids changes their temperatures, so each gas sees what the ones before it left behind
+            let mut condensed_temperatures: Vec<Kelvin> = tile
+                .condensed
+                .iter()
+                .map(|condensed| condensed.temperature)
+                .collect();
+            for gas in &tile.gases {
+                let concentration = gas.mass / volume;
+                for neighbour in &neighbours {
+                    let other_tile = some_or_continue!(tiles.get(neighbour));
+                    if other_tile.gases.is_empty() {
+                        continue;
+                    }
+                    let other_volume = Volume::free(other_tile.occupied);
+                    let other_gas = other_tile.gases.iter().find(|other| other.kind == gas.kind);
+                    let other_concentration = match other_gas {
+                        Some(other) => other.mass / other_volume,
+                        None => Kilogram::new_const(0.0) / other_volume,
+                    };
+                    if concentration <= other_concentration {
+                        continue;
+                    }
+
+                    // Fick's law
+                    let difference = concentration - other_concentration;
+                    let mut mass = difference
+                        * ((DIFFUSIVITY / TILE_DISTANCE) * TILE_FACE_AREA)
+                        * delta_time.as_si();
+                    // Never move more than it takes to even out the concentrations. A tile exchanges with all of its neighbours at once, so each one only gets its share
+                    let mut total_volume = volume;
+                    total_volume += other_volume;
+                    let equalizing_mass = difference
+                        * (volume * (other_volume / total_volume))
+                        * scalar(1.0 / NEIGHBOUR_OFFSETS.len() as RawScalar);
+                    if equalizing_mass < mass {
+                        mass = equalizing_mass;
+                    }
+                    if gas.mass * scalar(MAX_DIFFUSION_FRACTION) < mass {
+                        mass = gas.mass * scalar(MAX_DIFFUSION_FRACTION);
+                    }
+
+                    // The gas takes its heat with it
+                    let joules = gas.heat.joules * (mass / gas.mass);
+                    match other_gas {
+                        Some(other) => change(other.entity, mass, joules),
+                        None if mass >= MIN_SPAWN_MASS => {
+                            let existing = spawns.iter_mut().find(|spawn| {
+                                spawn.position == *neighbour && spawn.kind == gas.kind
+                            });
+                            match existing {
+                                Some(spawn) => {
+                                    spawn.mass += mass;
+                                    spawn.joules += joules;
+                                }
+                                None => spawns.push(Spawn {
+                                    position: *neighbour,
+                                    kind: gas.kind,
+                                    capacity: gas.heat.capacity,
+                                    mass,
+                                    joules,
+                                }),
+                            }
+                        }
+                        None => continue,
+                    }
+                    change(gas.entity, -mass, -joules);
+                }
+
+                // Exchange heat with the solids and liquids in the same tile
+                let gas_capacity = gas.heat.capacity * gas.mass;
+                let mut gas_temperature = gas.temperature;
+                for (condensed, condensed_temperature) in
+                    tile.condensed.iter().zip(&mut condensed_temperatures)
+                {
+                    let temperature_difference = *condensed_temperature - gas_temperature;
+                    let mut joules =
+                        gas.heat.conductivity * temperature_difference * TILE_FACE_AREA
+                            / BOUNDARY_LAYER
+                            * delta_time.as_si();
+                    // Never transfer more than it takes to reach the same temperature, otherwise the temperatures oscillate
+                    let mut total_capacity = gas_capacity;
+                    total_capacity += condensed.capacity;
+                    let equalizing_joules = gas_capacity
+                        * (condensed.capacity / total_capacity)
+                        * temperature_difference;
+                    if joules.raw().abs() > equalizing_joules.raw().abs() {
+                        joules = equalizing_joules;
+                    }
+                    change(gas.entity, Kilogram::new_const(0.0), joules);
+                    change(condensed.entity, Kilogram::new_const(0.0), -joules);
+                    gas_temperature += joules / gas_capacity;
+                    *condensed_temperature -= joules / condensed.capacity;
+                }
+            }
+        }
+
+        for (entity, (mass_change, joules_change)) in changes {
+            if let Some(heat) = heat_storage.get_mut(entity) {
+                heat.joules += joules_change;
+            }
+            if let Some(mass) = mass_storage.get_mut(entity) {
+                *mass += mass_change;
+                if *mass <= Kilogram::new_const(0.0) {
+                    // If deleting fails, the entity was already deleted by another system
+                    let _ = entities.delete(entity);
+                }
+            }
+        }
+
+        for spawn in spawns {
+            let temperature: Kelvin = spawn.joules / (spawn.capacity * spawn.mass);
+            if let Err(error) = registry.build_entity(
+                spawn.kind,
+                lazy.create_entity(&entities),
+                temperature,
+                spawn.mass,
+                spawn.position,
+            ) {
+                errors.push(SimulationError::Atmosphere {
+                    position: spawn.position,
+                    material: MaterialKind(spawn.kind)
+                        .name(&registry)
+                        .unwrap_or("an unknown material")
+                        .to_owned(),
+                    error,
+                });
+            }
+        }
+    }
+}
+
+struct Tile {
+    gases: Vec<Gas>,
+    condensed: Vec<Condensed>,
+    /// The space that is taken up by solids and liquids
+    occupied: MeterCubed,
+}
+
+impl Tile {
+    fn new() -> Self {
+        Tile {
+            gases: Vec::new(),
+            condensed: Vec::new(),
+            occupied: MeterCubed::new_const(0.0),
+        }
+    }
+
+    fn summarize(&self, registry: &MaterialRegistry) -> AtmosphereTile {
+        let mut summary = AtmosphereTile::vacuum();
+        if self.gases.is_empty() {
+            return summary;
+        }
+        let volume = Volume::free(self.occupied);
+        let mut joules = Joule::new_const(0.0);
+        let mut capacity = self.gases[0].heat.capacity * Kilogram::new_const(0.0);
+        for gas in &self.gases {
+            if let Some(molar_mass) = registry.get(gas.kind).and_then(|gas| gas.molar_mass) {
+                summary.pressure +=
+                    Pascal::ideal_gas(gas.mass, molar_mass, gas.temperature, volume);
+            }
+            joules += gas.heat.joules;
+            capacity += gas.heat.capacity * gas.mass;
+            match summary
+                .composition
+                .iter_mut()
+                .find(|(kind, _)| *kind == gas.kind)
+            {
+                Some((_, mass)) => *mass += gas.mass,
+                None => summary.composition.push((gas.kind, gas.mass)),
+            }
+        }
+        summary.temperature = Some(joules / capacity);
+        summary
+    }
+}
+
+struct Gas {
+    entity: Entity,
+    kind: MaterialId,
+    mass: Kilogram,
+    heat: Heat,
+    temperature: Kelvin,
+}
+
+/// A solid or liquid that the gases in its tile exchange heat with.
+struct Condensed {
+    entity: Entity,
+    capacity: HeatCapacity,
+    temperature: Kelvin,
+}
+
+/// Gas that spread into a tile that did not have any of it yet.
+struct Spawn {
+    position: Position,
+    kind: MaterialId,
+    capacity: SpecificHeatCapacity,
+    mass: Kilogram,
+    joules: Joule,
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::component::MaterialColor;
+    use crate::grid_storage::GridStorage;
+
+    /// A row of tiles, where every tile holds the given materials
+    fn world(tiles: &[&[(&str, RawScalar, RawScalar)]]) -> (World, Vec<Vec<Entity>>) {
+        let registry =
+            MaterialRegistry::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/materials.ron"))
+                .unwrap();
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(tiles.len(), 1));
+        world.register::<MaterialKind>();
+        world.register::<Composition>();
+        world.register::<Phase>();
+        world.register::<Mass>();
+        world.register::<Volume>();
+        world.register::<Heat>();
+        world.register::<MaterialColor>();
+        world.insert(DeltaTime::tick());
+        world.insert(SimulationErrors::default());
+        world.insert(Atmosphere::new(tiles.len(), 1));
+        let mut entities = Vec::new();
+        for (x, tile) in tiles.iter().enumerate() {
+            let mut on_tile = Vec::new();
+            for (name, temperature, mass) in tile.iter() {
+                let entity = registry
+                    .build_entity(
+                        registry.require(name).unwrap(),
+                        world.create_entity(),
+                        Kelvin::new_const(*temperature),
+                        Kilogram::new_const(*mass),
+                        Position {
+                            x: r32(x as f32),
+                            y: r32(0.0),
+                        },
+                    )
+                    .unwrap();
+                on_tile.push(entity);
+            }
+            entities.push(on_tile);
+        }
+        world.insert(registry);
+        (world, entities)
+    }
+
+    fn temperature(world: &World, entity: Entity) -> RawScalar {
+        let mass = *world.read_storage::<Mass>().get(entity).unwrap();
+        let heat = *world.read_storage::<Heat>().get(entity).unwrap();
+        heat.temperature(mass).raw()
+    }
+
+    fn total_joules(world: &World) -> RawScalar {
+        world
+            .read_storage::<Heat>()
+            .join()
+            .map(|heat| heat.joules.raw())
+            .sum()
+    }
+
+    #[test]
+    fn gases_diffuse_to_where_they_are_less_concentrated() {
+        let (mut world, _) = world(&[
+            &[("nitrogen", 300.0, 1.0), ("oxygen", 300.0, 1.0)],
+            &[("nitrogen", 300.0, 1.0)],
+        ]);
+        let oxygen = world
+            .read_resource::<MaterialRegistry>()
+            .require("oxygen")
+            .unwrap();
+
+        AtmosphereSystem.run_now(&world);
+        world.maintain();
+
+        let oxygen_in = |x: f32| -> RawScalar {
+            let kinds = world.read_storage::<MaterialKind>();
+            let masses = world.read_storage::<Mass>();
+            world
+                .read_storage::<Position>()
+                .on_tile(Position {
+                    x: r32(x),
+                    y: r32(0.0),
+                })
+                .filter(|(entity, _)| kinds.get(*entity).map(|kind| kind.id()) == Some(oxygen))
+                .map(|(entity, _)| masses.get(entity).unwrap().raw())
+                .sum()
+        };
+        let spread = oxygen_in(1.0);
+        assert!(spread > 0.0);
+        assert!((oxygen_in(0.0) + spread - 1.0).abs() < 1e-6);
+        assert_eq!(
+            world.write_resource::<SimulationErrors>().drain().count(),
+            0
+        );
+    }
+
+    #[test]
+    fn convection_does_not_overshoot_with_several_gases() {
+        // A light solid that is heated much faster than its heat capacity allows
+        let (world, entities) = world(&[&[
+            ("stone", 400.0, 0.001),
+            ("nitrogen", 300.0, 1.0),
+            ("oxygen", 300.0, 1.0),
+        ]]);
+        let joules_before = total_joules(&world);
+
+        AtmosphereSystem.run_now(&world);
+
+        // Each gas alone would cool the stone down to its own temperature, together they must not cool it any further
+        let stone = temperature(&world, entities[0][0]);
+        assert!(
+            stone < 400.0 && stone > 300.0 - 1e-3,
+            "the stone ended up at {}",
+            stone
+        );
+        for gas in &entities[0][1..] {
+            assert!(temperature(&world, *gas) > 300.0);
+        }
+        assert!((total_joules(&world) - joules_before).abs() < 1e-5 * joules_before);
+    }
+}
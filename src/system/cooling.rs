@@ -0,0 +1,414 @@
+use super::atmosphere::BOUNDARY_LAYER;
+use super::heat_pipe::exchange;
+use super::{Atmosphere, AtmosphereTile};
+use crate::component::{
+    Composition, CoolingTower, DeltaTime, Heat, Mass, MaterialKind, Phase, Radiator,
+};
+use crate::material::MaterialRegistry;
+use crate::units::{
+    scalar, HeatCapacity, HeatTransferCoefficient, Joule, Kelvin, Kilogram, Mole, RawScalar,
+    Scalar, Watt, WattPerKelvin, ATMOSPHERIC_PRESSURE, GAS_CONSTANT,
+};
+use crate::{GridStorageExt, Position, NEIGHBOUR_OFFSETS};
+use noisy_float::types::r32;
+use specs::prelude::*;
+
+/// What a radiator radiates to when there is no gas around it, roughly the temperature of deep space
+const SKY_TEMPERATURE: Kelvin = Kelvin::new_const(3.0);
+
+type RadiatorSystemData<'a> = (
+    ReadExpect<'a, MaterialRegistry>,
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, MaterialKind>,
+    ReadStorage<'a, Phase>,
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, Mass>,
+    WriteStorage<'a, Radiator>,
+    WriteStorage<'a, Heat>,
+    Entities<'a>,
+);
+
+/// Rejects the heat of [Radiator]s into their surroundings.
+///
+/// The surface of a radiator is shared between the gases on its own tile and on the tiles next to it, and passes heat into them by convection, which is limited by the heat conductivity of each gas. On top of that, the radiator radiates to the mean temperature of these gases, or to the sky if it is in a vacuum. The radiated heat leaves the map.
+pub struct RadiatorSystem;
+
+impl<'a> System<'a> for RadiatorSystem {
+    type SystemData = RadiatorSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            registry,
+            delta_time,
+            kind_storage,
+            phase_storage,
+            position_storage,
+            mass_storage,
+            mut radiator_storage,
+            mut heat_storage,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let delta_time = delta_time.as_si();
+        for (entity, radiator, position) in
+            (&entities, &mut radiator_storage, &position_storage).join()
+        {
+            let mass = *some_or_continue!(mass_storage.get(entity));
+            let heat = *some_or_continue!(heat_storage.get(entity));
+            // A radiator without a finite temperature is left alone, the HeatSystem reports it
+            some_or_continue!(heat.try_temperature(mass).ok());
+            let capacity = heat.capacity * mass;
+            let mut current = heat;
+            let emissivity = kind_storage
+                .get(entity)
+                .and_then(|kind| registry.get(kind.id()))
+                .map_or(0.0, |definition| definition.emissivity);
+
+            let mut gases = Vec::new();
+            for (other, _) in position_storage
+                .on_tile(*position)
+                .chain(position_storage.neighbours(*position))
+            {
+                if phase_storage.get(other) != Some(&Phase::Gas) {
+                    continue;
+                }
+                let gas_heat = *some_or_continue!(heat_storage.get(other));
+                let gas_mass = *some_or_continue!(mass_storage.get(other));
+                let temperature = some_or_continue!(gas_heat.try_temperature(gas_mass).ok());
+                gases.push((other, temperature, gas_heat.capacity * gas_mass, gas_heat));
+            }
+
+            let mut gas_capacity = HeatCapacity::new_const(0.0);
+            let mut gas_joules = Joule::new_const(0.0);
+            for (_, temperature, capacity, _) in &gases {
+                gas_capacity += *capacity;
+                gas_joules += *capacity * *temperature;
+            }
+            let surroundings: Kelvin = if gases.is_empty() {
+                SKY_TEMPERATURE
+            } else {
+                gas_joules / gas_capacity
+            };
+
+            // Convection, with the surface shared evenly between the gases
+            let share = radiator.surface_area * scalar(1.0 / gases.len().max(1) as RawScalar);
+            let mut rated = Watt::new_const(0.0);
+            for (gas, gas_temperature, gas_capacity, gas_heat) in &gases {
+                let conductance: WattPerKelvin = (gas_heat.conductivity / BOUNDARY_LAYER) * share;
+                rated += conductance * (radiator.rated_temperature - *gas_temperature);
+                let temperature = match current.try_temperature(mass) {
+                    Ok(temperature) => temperature,
+                    Err(_) => break,
+                };
+                let transfer = exchange(
+                    (temperature, capacity),
+                    (*gas_temperature, *gas_capacity),
+                    conductance,
+                    delta_time,
+                );
+                current.joules -= transfer;
+                if let Some(heat) = heat_storage.get_mut(*gas) {
+                    heat.joules += transfer;
+                }
+            }
+
+            // Radiation, which never cools the radiator below its surroundings
+            let temperature = current.try_temperature(mass).unwrap_or(surroundings);
+            if temperature > surroundings {
+                let coefficient =
+                    HeatTransferCoefficient::radiation(emissivity, temperature, surroundings);
+                let radiated = (coefficient
+                    * radiator.surface_area
+                    * (temperature - surroundings)
+                    * delta_time)
+                    .min(capacity * (temperature - surroundings));
+                current.joules -= radiated;
+            }
+            if radiator.rated_temperature > surroundings {
+                let coefficient = HeatTransferCoefficient::radiation(
+                    emissivity,
+                    radiator.rated_temperature,
+                    surroundings,
+                );
+                rated += coefficient
+                    * radiator.surface_area
+                    * (radiator.rated_temperature - surroundings);
+            }
+
+            let mut rejected = heat.joules;
+            rejected -= current.joules;
+            radiator.set_throughput(rated.max(Watt::new_const(0.0)), rejected / delta_time);
+            if let Some(heat) = heat_storage.get_mut(entity) {
+                heat.joules = current.joules;
+            }
+        }
+    }
+}
+
+type CoolingTowerSystemData<'a> = (
+    ReadExpect<'a, MaterialRegistry>,
+    ReadExpect<'a, DeltaTime>,
+    ReadExpect<'a, Atmosphere>,
+    ReadStorage<'a, MaterialKind>,
+    ReadStorage<'a, Composition>,
+    ReadStorage<'a, Phase>,
+    ReadStorage<'a, Position>,
+    WriteStorage<'a, CoolingTower>,
+    WriteStorage<'a, Mass>,
+    WriteStorage<'a, Heat>,
+    Entities<'a>,
+);
+
+/// Evaporates the liquid next to [CoolingTower]s, and takes the heat of vaporization out of the towers.
+///
+/// Every side of a tower that has gas on it takes up vapour, as long as the gas is not saturated with the fluid yet. How saturated the gas is follows from its partial pressure of the fluid, against the vapour pressure of the fluid at the temperature of the gas. A tower is never cooled below the temperature of the air around it.
+pub struct CoolingTowerSystem;
+
+impl<'a> System<'a> for CoolingTowerSystem {
+    type SystemData = CoolingTowerSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            registry,
+            delta_time,
+            atmosphere,
+            kind_storage,
+            composition_storage,
+            phase_storage,
+            position_storage,
+            mut tower_storage,
+            mut mass_storage,
+            mut heat_storage,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let delta_time = delta_time.as_si();
+        for (entity, tower, position) in (&entities, &mut tower_storage, &position_storage).join() {
+            // How much vapour the air around the tower takes up, as a fraction of what completely dry air on every side would take
+            let mut dryness = scalar(0.0);
+            let mut air_temperature = Kelvin::new_const(0.0);
+            let mut sides = 0;
+            for (dx, dy) in NEIGHBOUR_OFFSETS.iter() {
+                let side = Position {
+                    x: position.x + r32(*dx as f32),
+                    y: position.y + r32(*dy as f32),
+                };
+                let tile = some_or_continue!(atmosphere.at(side));
+                let temperature = some_or_continue!(tile.temperature);
+                let humidity =
+                    relative_humidity(&registry, tile, tower).unwrap_or_else(|| scalar(0.0));
+                dryness += (scalar(1.0) - humidity).clamp(scalar(0.0), scalar(1.0))
+                    * scalar(1.0 / NEIGHBOUR_OFFSETS.len() as RawScalar);
+                air_temperature += temperature;
+                sides += 1;
+            }
+            let capacity: Watt = tower.max_evaporation * dryness * tower.heat_of_vaporization;
+            tower.set_throughput(capacity, Watt::new_const(0.0));
+            if sides == 0 {
+                continue;
+            }
+            let air_temperature = air_temperature * scalar(1.0 / sides as RawScalar);
+
+            let heat = *some_or_continue!(heat_storage.get(entity));
+            let mass = *some_or_continue!(mass_storage.get(entity));
+            let temperature = some_or_continue!(heat.try_temperature(mass).ok());
+            if temperature <= air_temperature {
+                continue;
+            }
+            let heat_above = heat.capacity * mass * (temperature - air_temperature);
+
+            let mut fluid = Vec::new();
+            let mut available = Kilogram::new_const(0.0);
+            for (neighbour, _) in position_storage.neighbours(*position) {
+                let is_fluid = matches!(kind_storage.get(neighbour), Some(kind) if kind.id() == tower.fluid)
+                    && phase_storage.get(neighbour) == Some(&Phase::Liquid)
+                    && !composition_storage.contains(neighbour);
+                if !is_fluid {
+                    continue;
+                }
+                let mass = *some_or_continue!(mass_storage.get(neighbour));
+                fluid.push((neighbour, mass));
+                available += mass;
+            }
+            if available <= Kilogram::new_const(0.0) {
+                continue;
+            }
+
+            let evaporated: Kilogram = (capacity * delta_time / tower.heat_of_vaporization)
+                .min(available)
+                .min(heat_above / tower.heat_of_vaporization);
+            if evaporated <= Kilogram::new_const(0.0) {
+                continue;
+            }
+            for (tile, mass) in fluid {
+                let remaining = mass - evaporated * (mass / available);
+                if let Some(heat) = heat_storage.get_mut(tile) {
+                    // A tile without a finite temperature keeps its heat, the HeatSystem reports it
+                    let _ = heat.try_set_mass(mass, remaining);
+                }
+                if let Some(stored) = mass_storage.get_mut(tile) {
+                    *stored = remaining;
+                }
+                if remaining <= Kilogram::new_const(0.0) {
+                    // If deleting fails, the entity was already deleted by another system
+                    let _ = entities.delete(tile);
+                }
+            }
+            let rejected: Joule = tower.heat_of_vaporization * evaporated;
+            if let Some(heat) = heat_storage.get_mut(entity) {
+                heat.joules -= rejected;
+            }
+            tower.set_throughput(capacity, rejected / delta_time);
+        }
+    }
+}
+
+/// The partial pressure of the fluid of `tower` in `tile`, as a fraction of its vapour pressure at the temperature of the tile.
+///
+/// The vapour pressure follows from the boiling point with the Clausius–Clapeyron equation. Returns `None` if the fluid or one of the gases has no molar mass, the fluid has no boiling point, or the tile is so cold that the vapour pressure is too small to represent.
+fn relative_humidity(
+    registry: &MaterialRegistry,
+    tile: &AtmosphereTile,
+    tower: &CoolingTower,
+) -> Option<Scalar> {
+    let temperature = tile.temperature?;
+    let definition = registry.get(tower.fluid)?;
+    let molar_mass = definition.molar_mass?;
+    let boiling_point = definition.boiling_point?;
+
+    let mut moles = Mole::new_const(0.0);
+    for (id, mass) in &tile.composition {
+        moles += *mass / registry.get(*id)?.molar_mass?;
+    }
+    if moles <= Mole::new_const(0.0) {
+        return None;
+    }
+    let vapour: Mole = tile.mass_of(tower.fluid) / molar_mass;
+    let partial_pressure = tile.pressure * (vapour / moles);
+
+    let exponent = tower.heat_of_vaporization.0 * molar_mass.0 / GAS_CONSTANT.0
+        * (scalar(1.0) / boiling_point.0 - scalar(1.0) / temperature.0);
+    let factor = exponent.raw().exp();
+    if !factor.is_normal() {
+        return None;
+    }
+    let vapour_pressure = ATMOSPHERIC_PRESSURE * scalar(factor);
+    Some(partial_pressure / vapour_pressure)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid_storage::GridStorage;
+    use crate::units::{
+        KilogramPerSecond, MeterSquared, SpecificEnergy, SpecificHeatCapacity, ThermalConductivity,
+    };
+
+    #[test]
+    fn radiators_reject_less_in_warm_air() {
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(5, 1));
+        world.register::<MaterialKind>();
+        world.register::<Phase>();
+        world.register::<Mass>();
+        world.register::<Radiator>();
+        world.register::<Heat>();
+        world.insert(MaterialRegistry::default());
+        world.insert(DeltaTime::tick());
+        let mass = Kilogram::new_const(1.0);
+        let tile = |world: &mut World, x: f32, phase: Phase, temperature: RawScalar| {
+            world
+                .create_entity()
+                .with(Position {
+                    x: r32(x),
+                    y: r32(0.0),
+                })
+                .with(phase)
+                .with(mass)
+                .with(Heat::from_material_specs(
+                    Kelvin::new_const(temperature),
+                    mass,
+                    SpecificHeatCapacity::new_const(1000.0),
+                    ThermalConductivity::new_const(0.026),
+                ))
+                .build()
+        };
+        let radiator = Radiator::new(MeterSquared::new_const(10.0), Kelvin::new_const(400.0));
+        let in_cold_air = tile(&mut world, 0.0, Phase::Solid, 380.0);
+        let cold_air = tile(&mut world, 1.0, Phase::Gas, 250.0);
+        tile(&mut world, 3.0, Phase::Gas, 350.0);
+        let in_warm_air = tile(&mut world, 4.0, Phase::Solid, 380.0);
+        for entity in &[in_cold_air, in_warm_air] {
+            world
+                .write_storage::<Radiator>()
+                .insert(*entity, radiator)
+                .unwrap();
+        }
+        let joules =
+            |world: &World, entity| world.read_storage::<Heat>().get(entity).unwrap().joules;
+        let before: Vec<Joule> = [in_cold_air, cold_air]
+            .iter()
+            .map(|entity| joules(&world, *entity))
+            .collect();
+
+        RadiatorSystem.run_now(&world);
+        let radiators = world.read_storage::<Radiator>();
+        let cold = radiators.get(in_cold_air).unwrap();
+        let warm = radiators.get(in_warm_air).unwrap();
+        assert!(cold.capacity() > warm.capacity());
+        assert!(warm.capacity() > Watt::new_const(0.0));
+        assert!(cold.rejected() > warm.rejected());
+        assert!(warm.rejected() > Watt::new_const(0.0));
+
+        // Without emissivity, all of the heat goes into the air
+        let lost = before[0] - joules(&world, in_cold_air);
+        let gained = joules(&world, cold_air) - before[1];
+        assert!((lost - gained).raw().abs() < 1e-3 * lost.raw());
+        assert!(
+            (cold.rejected() * DeltaTime::tick().as_si() - lost)
+                .raw()
+                .abs()
+                < 1e-3 * lost.raw()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn relative_humidity_of_the_air() {
+        let registry =
+            MaterialRegistry::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/materials.ron"))
+                .unwrap();
+        let water = registry.require("water").unwrap();
+        let nitrogen = registry.require("nitrogen").unwrap();
+        let tower = CoolingTower::new(
+            water,
+            SpecificEnergy::new_const(2.257e6),
+            KilogramPerSecond::new_const(0.01),
+        );
+        let tile = |temperature: RawScalar, composition: Vec<_>| AtmosphereTile {
+            pressure: ATMOSPHERIC_PRESSURE,
+            temperature: Some(Kelvin::new_const(temperature)),
+            composition,
+        };
+        let air = |vapour: RawScalar| {
+            vec![
+                (nitrogen, Kilogram::new_const(1.0)),
+                (water, Kilogram::new_const(vapour)),
+            ]
+        };
+
+        let dry = relative_humidity(&registry, &tile(293.15, air(0.0)), &tower).unwrap();
+        let humid = relative_humidity(&registry, &tile(293.15, air(0.01)), &tower).unwrap();
+        let warm = relative_humidity(&registry, &tile(313.15, air(0.01)), &tower).unwrap();
+        assert_eq!(dry, scalar(0.0));
+        assert!(humid > scalar(0.0) && humid < scalar(1.0), "{}", humid);
+        // Warm air holds more vapour
+        assert!(warm < humid);
+
+        // No gas, or so cold that the vapour pressure is 0
+        assert!(relative_humidity(&registry, &tile(293.15, Vec::new()), &tower).is_none());
+        assert!(relative_humidity(&registry, &tile(1.0, air(0.01)), &tower).is_none());
+    }
+}
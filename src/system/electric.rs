@@ -0,0 +1,413 @@
+use crate::component::{Composition, DeltaTime, Electricity, Electrode, Heat, MaterialKind, Phase};
+use crate::material::{Material, MaterialRegistry, Mixture};
+use crate::units::{scalar, Ampere, Meter, Ohm, RawScalar, Volt, Watt};
+use crate::{GridStorageExt, Position};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// The width and height of a tile
+const TILE_SIZE: Meter = Meter::new_const(1.0);
+/// The solver stops when the voltage of every tile is this close to its solution, as a fraction of the voltage between the electrodes
+const TOLERANCE: f64 = 1e-9;
+
+type ElectricalSystemData<'a> = (
+    ReadExpect<'a, MaterialRegistry>,
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, MaterialKind>,
+    ReadStorage<'a, Composition>,
+    ReadStorage<'a, Phase>,
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, Electrode>,
+    WriteStorage<'a, Electricity>,
+    WriteStorage<'a, Heat>,
+    Entities<'a>,
+);
+
+/// Solves the voltages and currents in circuits of conductive tiles, and heats up the tiles that the current flows through.
+///
+/// Neighbouring tiles with an [electrical_resistivity](crate::material::MaterialDefinition::electrical_resistivity) form a circuit, where the current between two tiles flows from the centre of one to the centre of the other. Circuits that contain an [Electrode] are solved with nodal analysis: every tile that is not an electrode has as much current flowing in as out. The result is stored in the [Electricity] of each tile.
+///
+/// The resistive losses (I²R) of each tile are added to its [Heat], so wires that carry a large current warm up.
+pub struct ElectricalSystem;
+
+impl<'a> System<'a> for ElectricalSystem {
+    type SystemData = ElectricalSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            registry,
+            delta_time,
+            kind_storage,
+            composition_storage,
+            phase_storage,
+            position_storage,
+            electrode_storage,
+            mut electricity_storage,
+            mut heat_storage,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let mut nodes = Vec::new();
+        let mut indices: HashMap<Entity, usize> = HashMap::new();
+        for (entity, kind, phase, _) in
+            (&entities, &kind_storage, &phase_storage, &position_storage).join()
+        {
+            let resistivity = match composition_storage.get(entity) {
+                Some(composition) => Mixture::new(composition, &registry, *phase)
+                    .and_then(|mixture| mixture.electrical_resistivity()),
+                None => registry
+                    .material(kind.id(), *phase)
+                    .and_then(|material| material.electrical_resistivity()),
+            };
+            let resistivity = some_or_continue!(resistivity);
+            // The current flows from the centre of the tile to its face, through half of the tile. The face of a tile is as wide as the tile is long, so ρ×L/A works out to ρ/L
+            let half_resistance: Ohm = resistivity / TILE_SIZE * scalar(0.5);
+            indices.insert(entity, nodes.len());
+            nodes.push(Node {
+                entity,
+                half_resistance: precise(half_resistance.raw()),
+                fixed: electrode_storage
+                    .get(entity)
+                    .map(|electrode| precise(electrode.voltage.raw())),
+                voltage: electricity_storage
+                    .get(entity)
+                    .map_or(0.0, |electricity| precise(electricity.voltage.raw())),
+            });
+        }
+
+        let mut connections: Vec<Vec<(usize, f64)>> = vec![Vec::new(); nodes.len()];
+        for (index, node) in nodes.iter().enumerate() {
+            // Entities without a position are not in `indices`
+            let position = *some_or_continue!(position_storage.get(node.entity));
+            for (other_entity, _) in position_storage.neighbours(position) {
+                let other = *some_or_continue!(indices.get(&other_entity));
+                let conductance = 1.0 / (node.half_resistance + nodes[other].half_resistance);
+                connections[index].push((other, conductance));
+            }
+        }
+
+        let delta_time = delta_time.as_si();
+        let mut circuit_of = vec![None; nodes.len()];
+        for start in 0..nodes.len() {
+            if circuit_of[start].is_some() {
+                continue;
+            }
+            let circuit = find_circuit(start, &connections, &mut circuit_of);
+            if circuit.iter().all(|index| nodes[*index].fixed.is_none()) {
+                // Without an electrode, no current flows
+                for index in circuit {
+                    electricity_storage.remove(nodes[index].entity);
+                }
+                continue;
+            }
+            solve(&mut nodes, &connections, &circuit);
+
+            for index in circuit {
+                let node = &nodes[index];
+                let mut through = 0.0;
+                let mut net = 0.0;
+                let mut power = 0.0;
+                for (other, conductance) in &connections[index] {
+                    let current = conductance * (node.voltage - nodes[*other].voltage);
+                    through += current.abs();
+                    net += current;
+                    power += current * current * node.half_resistance;
+                }
+                let current = match node.fixed {
+                    Some(_) => net.abs(),
+                    // Every current that flows in also flows out
+                    None => through / 2.0,
+                };
+                let electricity = Electricity {
+                    voltage: Volt(scalar(node.voltage as RawScalar)),
+                    current: Ampere(scalar(current as RawScalar)),
+                    power: Watt(scalar(power as RawScalar)),
+                };
+                if let Some(heat) = heat_storage.get_mut(node.entity) {
+                    heat.joules += electricity.power * delta_time;
+                }
+                // This can only fail if the entity is dead, and dead entities are not joined
+                let _ = electricity_storage.insert(node.entity, electricity);
+            }
+        }
+    }
+}
+
+/// A conductive tile.
+struct Node {
+    entity: Entity,
+    /// The resistance between the centre of the tile and each of its faces, in Ω
+    half_resistance: f64,
+    /// The voltage of an [Electrode], in V
+    fixed: Option<f64>,
+    /// In V. This starts out at the voltage of the last tick, so the solver only has to find the change
+    voltage: f64,
+}
+
+// With the `f64` feature, the simulation already runs in double precision
+#[allow(clippy::useless_conversion)]
+fn precise(value: RawScalar) -> f64 {
+    f64::from(value)
+}
+
+/// Find all nodes that are connected to `start`, and mark them as part of its circuit.
+fn find_circuit(
+    start: usize,
+    connections: &[Vec<(usize, f64)>],
+    circuit_of: &mut [Option<usize>],
+) -> Vec<usize> {
+    let mut circuit = vec![start];
+    circuit_of[start] = Some(start);
+    let mut next = 0;
+    while let Some(index) = circuit.get(next).copied() {
+        next += 1;
+        for (other, _) in &connections[index] {
+            if circuit_of[*other].is_none() {
+                circuit_of[*other] = Some(start);
+                circuit.push(*other);
+            }
+        }
+    }
+    circuit
+}
+
+/// Find the voltages of the nodes in `circuit` that are not electrodes, so that as much current flows into each node as out of it.
+///
+/// This is a system of linear equations G×V = I, where G holds the conductances between the nodes and I the currents from the electrodes. It is solved with the conjugate gradient method, using the diagonal of G as preconditioner. The resistances of tiles span many orders of magnitude, from copper to water, so this is done in double precision.
+fn solve(nodes: &mut [Node], connections: &[Vec<(usize, f64)>], circuit: &[usize]) {
+    for index in circuit {
+        if let Some(voltage) = nodes[*index].fixed {
+            nodes[*index].voltage = voltage;
+        }
+    }
+    let free: Vec<usize> = circuit
+        .iter()
+        .copied()
+        .filter(|index| nodes[*index].fixed.is_none())
+        .collect();
+    if free.is_empty() {
+        return;
+    }
+    let mut local: HashMap<usize, usize> = HashMap::new();
+    for (i, index) in free.iter().enumerate() {
+        local.insert(*index, i);
+    }
+
+    let diagonal: Vec<f64> = free
+        .iter()
+        .map(|index| connections[*index].iter().map(|(_, g)| g).sum())
+        .collect();
+    let driven: Vec<f64> = free
+        .iter()
+        .map(|index| {
+            connections[*index]
+                .iter()
+                .filter_map(|(other, g)| Some(g * nodes[*other].fixed?))
+                .sum()
+        })
+        .collect();
+    // G×x, only counting the conductances between free nodes
+    let multiply = |x: &[f64]| -> Vec<f64> {
+        free.iter()
+            .enumerate()
+            .map(|(i, index)| {
+                let coupled: f64 = connections[*index]
+                    .iter()
+                    .filter_map(|(other, g)| Some(g * x[*local.get(other)?]))
+                    .sum();
+                diagonal[i] * x[i] - coupled
+            })
+            .collect()
+    };
+    let dot = |a: &[f64], b: &[f64]| -> f64 { a.iter().zip(b).map(|(a, b)| a * b).sum() };
+
+    let mut voltages: Vec<f64> = free.iter().map(|index| nodes[*index].voltage).collect();
+    let fixed = circuit.iter().filter_map(|index| nodes[*index].fixed);
+    let span = fixed.clone().fold(f64::MIN, f64::max) - fixed.fold(f64::MAX, f64::min);
+    let limit = TOLERANCE * span.max(1.0);
+    let mut residual: Vec<f64> = multiply(&voltages)
+        .iter()
+        .zip(&driven)
+        .map(|(gv, i)| i - gv)
+        .collect();
+    let mut preconditioned: Vec<f64> = residual.iter().zip(&diagonal).map(|(r, d)| r / d).collect();
+    let mut direction = preconditioned.clone();
+    let mut rz = dot(&residual, &preconditioned);
+    // In exact arithmetic, the method converges after one step per unknown
+    for _ in 0..2 * free.len() + 10 {
+        // The leftover current of a tile, divided by its conductance, is roughly how far off its voltage is
+        let error = preconditioned
+            .iter()
+            .fold(0.0, |max: f64, e| max.max(e.abs()));
+        if error <= limit {
+            break;
+        }
+        let product = multiply(&direction);
+        let step = rz / dot(&direction, &product);
+        if !step.is_finite() {
+            break;
+        }
+        for i in 0..free.len() {
+            voltages[i] += step * direction[i];
+            residual[i] -= step * product[i];
+            preconditioned[i] = residual[i] / diagonal[i];
+        }
+        let next_rz = dot(&residual, &preconditioned);
+        let beta = next_rz / rz;
+        rz = next_rz;
+        for i in 0..free.len() {
+            direction[i] = preconditioned[i] + beta * direction[i];
+        }
+    }
+
+    for (i, index) in free.iter().enumerate() {
+        nodes[*index].voltage = voltages[i];
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::component::{Mass, MaterialColor, Volume};
+    use crate::grid_storage::GridStorage;
+    use crate::units::{Joule, Kelvin, Kilogram};
+    use noisy_float::types::r32;
+
+    /// Steel tiles at the given positions, with the voltage of the ones that are electrodes. Runs the [ElectricalSystem] once.
+    fn circuit(
+        width: usize,
+        height: usize,
+        tiles: &[(f32, f32, Option<RawScalar>)],
+    ) -> (World, Vec<Entity>) {
+        let registry =
+            MaterialRegistry::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/materials.ron"))
+                .unwrap();
+        let steel = registry.require("steel").unwrap();
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(width, height));
+        world.register::<MaterialKind>();
+        world.register::<Composition>();
+        world.register::<Phase>();
+        world.register::<Mass>();
+        world.register::<Volume>();
+        world.register::<Heat>();
+        world.register::<MaterialColor>();
+        world.register::<Electrode>();
+        world.register::<Electricity>();
+        world.insert(DeltaTime::tick());
+        let entities = tiles
+            .iter()
+            .map(|(x, y, voltage)| {
+                let mut builder = world.create_entity();
+                if let Some(voltage) = voltage {
+                    builder = builder.with(Electrode {
+                        voltage: Volt::new_const(*voltage),
+                    });
+                }
+                registry
+                    .build_entity(
+                        steel,
+                        builder,
+                        Kelvin::new_const(300.0),
+                        Kilogram::new_const(100.0),
+                        Position {
+                            x: r32(*x),
+                            y: r32(*y),
+                        },
+                    )
+                    .unwrap()
+            })
+            .collect();
+        world.insert(registry);
+
+        ElectricalSystem.run_now(&world);
+        (world, entities)
+    }
+
+    /// The resistance between the centre of a steel tile and its face
+    fn half_resistance(world: &World) -> RawScalar {
+        let registry = world.read_resource::<MaterialRegistry>();
+        let steel = registry.require("steel").unwrap();
+        let resistivity = registry.get(steel).unwrap().electrical_resistivity.unwrap();
+        0.5 * resistivity.raw()
+    }
+
+    fn electricity(world: &World, entity: Entity) -> Electricity {
+        *world.read_storage::<Electricity>().get(entity).unwrap()
+    }
+
+    fn close(value: RawScalar, expected: RawScalar) -> bool {
+        (value - expected).abs() <= 1e-4 * expected.abs()
+    }
+
+    #[test]
+    fn wire_between_two_electrodes() {
+        let (world, tiles) = circuit(
+            3,
+            1,
+            &[
+                (0.0, 0.0, Some(1.0)),
+                (1.0, 0.0, None),
+                (2.0, 0.0, Some(0.0)),
+            ],
+        );
+        let half = half_resistance(&world);
+
+        // The current flows through half of each electrode and all of the wire
+        let current = 1.0 / (4.0 * half);
+        let wire = electricity(&world, tiles[1]);
+        assert!(close(wire.voltage.raw(), 0.5));
+        assert!(close(wire.current.raw(), current));
+        assert!(close(electricity(&world, tiles[0]).current.raw(), current));
+
+        // The resistive losses of the wire end up in its heat
+        let losses = current * current * 2.0 * half * DeltaTime::tick().as_si().raw();
+        let heat = *world.read_storage::<Heat>().get(tiles[1]).unwrap();
+        let heat_before: Joule =
+            heat.capacity * Kilogram::new_const(100.0) * Kelvin::new_const(300.0);
+        assert!(close((heat.joules - heat_before).raw(), losses));
+        assert!(close(wire.power.raw(), current * current * 2.0 * half));
+    }
+
+    #[test]
+    fn current_splits_over_parallel_paths() {
+        // A short path straight between the electrodes, and a path three times as long around the empty tile below it
+        let (world, tiles) = circuit(
+            3,
+            3,
+            &[
+                (0.0, 0.0, Some(1.0)),
+                (1.0, 0.0, None),
+                (2.0, 0.0, Some(0.0)),
+                (0.0, 1.0, None),
+                (2.0, 1.0, None),
+                (0.0, 2.0, None),
+                (1.0, 2.0, None),
+                (2.0, 2.0, None),
+            ],
+        );
+        let half = half_resistance(&world);
+
+        let short = 1.0 / (4.0 * half);
+        let long = 1.0 / (12.0 * half);
+        assert!(close(electricity(&world, tiles[1]).current.raw(), short));
+        for tile in &tiles[3..] {
+            assert!(close(electricity(&world, *tile).current.raw(), long));
+        }
+        // The electrodes drive the current of both paths
+        assert!(close(
+            electricity(&world, tiles[0]).current.raw(),
+            short + long
+        ));
+        assert!(close(
+            electricity(&world, tiles[2]).current.raw(),
+            short + long
+        ));
+        // Along the long path, the voltage drops evenly over the six steps between the electrodes
+        let voltage = |index: usize| electricity(&world, tiles[index]).voltage.raw();
+        assert!(close(voltage(3), 5.0 / 6.0));
+        assert!(close(voltage(6), 3.0 / 6.0));
+        assert!(close(voltage(4), 1.0 / 6.0));
+    }
+}
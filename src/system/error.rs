@@ -0,0 +1,137 @@
+use crate::component::{Heat, Mass, OverfullTile};
+use crate::grid_storage::Position;
+use crate::material::BuildError;
+use crate::units::{Joule, Kelvin, ThermalConductivity, UnitError};
+use std::fmt;
+
+/// An error that occurred while running the simulation, e.g. because the solver diverged.
+///
+/// Systems can not return errors, so instead they push them into the [SimulationErrors] resource. The tile that caused the error is not updated.
+#[derive(Debug)]
+pub enum SimulationError {
+    Temperature {
+        position: Position,
+        heat: Heat,
+        mass: Mass,
+        error: UnitError,
+    },
+    HeatTransfer {
+        position: Position,
+        other_position: Position,
+        temperature: Kelvin,
+        other_temperature: Kelvin,
+        conductivity: ThermalConductivity,
+        error: UnitError,
+    },
+    HeatApply {
+        position: Position,
+        joules: Joule,
+        transfer: Joule,
+        error: UnitError,
+    },
+    Reaction {
+        position: Position,
+        material: String,
+        error: BuildError,
+    },
+    Flow {
+        position: Position,
+        material: String,
+        error: BuildError,
+    },
+    /// A gas that spread into a tile, or that was added to fill a tile with air
+    Atmosphere {
+        position: Position,
+        material: String,
+        error: BuildError,
+    },
+    /// The solids and liquids on a tile take up more space than the tile has. They are compressed into it until they can spill over
+    Overfull {
+        position: Position,
+        error: OverfullTile,
+    },
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SimulationError::Temperature {
+                position,
+                heat,
+                mass,
+                error,
+            } => write!(
+                fmt,
+                "Could not calculate the temperature of tile {} ({:?}, {:?}, capacity {:?}): {}",
+                position, heat.joules, mass, heat.capacity, error
+            ),
+            SimulationError::HeatTransfer {
+                position,
+                other_position,
+                temperature,
+                other_temperature,
+                conductivity,
+                error,
+            } => write!(
+                fmt,
+                "Heat transfer between tile {} ({:?}) and tile {} ({:?}) diverged (conductivity {:?}): {}",
+                position, temperature, other_position, other_temperature, conductivity, error
+            ),
+            SimulationError::HeatApply {
+                position,
+                joules,
+                transfer,
+                error,
+            } => write!(
+                fmt,
+                "Could not apply a heat transfer of {:?} to tile {} ({:?}): {}",
+                transfer, position, joules, error
+            ),
+            SimulationError::Reaction {
+                position,
+                material,
+                error,
+            } => write!(
+                fmt,
+                "Could not place the {} produced by a reaction at tile {}: {}",
+                material, position, error
+            ),
+            SimulationError::Flow {
+                position,
+                material,
+                error,
+            } => write!(
+                fmt,
+                "Could not place the {} that flowed into tile {}: {}",
+                material, position, error
+            ),
+            SimulationError::Atmosphere {
+                position,
+                material,
+                error,
+            } => write!(
+                fmt,
+                "Could not place the {} of the atmosphere at tile {}: {}",
+                material, position, error
+            ),
+            SimulationError::Overfull { position, error } => {
+                write!(fmt, "Tile {} is overfull: {}", position, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
+#[derive(Default)]
+pub struct SimulationErrors(Vec<SimulationError>);
+
+impl SimulationErrors {
+    pub fn push(&mut self, error: SimulationError) {
+        self.0.push(error);
+    }
+
+    pub fn drain(&mut self) -> std::vec::Drain<'_, SimulationError> {
+        self.0.drain(..)
+    }
+}
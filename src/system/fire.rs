@@ -0,0 +1,50 @@
+use crate::grid_storage::Position;
+use crate::material::MaterialId;
+use std::fmt;
+
+/// Emitted by the [ReactionSystem](super::ReactionSystem) when a tile catches fire or stops burning.
+#[derive(Debug, Copy, Clone)]
+pub enum FireEvent {
+    Started {
+        position: Position,
+        material: MaterialId,
+    },
+    Stopped {
+        position: Position,
+        material: MaterialId,
+        /// `true` if all the fuel was used up, `false` if the fire went out because it cooled down or ran out of oxidiser
+        burned_out: bool,
+    },
+}
+
+impl fmt::Display for FireEvent {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FireEvent::Started { position, .. } => write!(fmt, "Tile {} caught fire", position),
+            FireEvent::Stopped {
+                position,
+                burned_out: true,
+                ..
+            } => write!(fmt, "Tile {} burned out", position),
+            FireEvent::Stopped {
+                position,
+                burned_out: false,
+                ..
+            } => write!(fmt, "The fire at tile {} went out", position),
+        }
+    }
+}
+
+/// The [FireEvent]s of the last tick. Drain these after every dispatch, otherwise they pile up.
+#[derive(Default)]
+pub struct FireEvents(Vec<FireEvent>);
+
+impl FireEvents {
+    pub fn push(&mut self, event: FireEvent) {
+        self.0.push(event);
+    }
+
+    pub fn drain(&mut self) -> std::vec::Drain<'_, FireEvent> {
+        self.0.drain(..)
+    }
+}
@@ -0,0 +1,545 @@
+use super::{SimulationError, SimulationErrors};
+use crate::component::{
+    Composition, DeltaTime, Heat, Mass, MaterialKind, Phase, Volume, TILE_FACE_AREA,
+};
+use crate::material::{Material, MaterialId, MaterialRegistry};
+use crate::units::{
+    scalar, Density, Joule, Kelvin, Kilogram, KilogramPerSecond, Meter, MeterCubed, Pascal,
+    RawScalar, SpecificHeatCapacity, GRAVITY,
+};
+use crate::{GridStorageExt, Position, NEIGHBOUR_OFFSETS};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// The fraction of the ideal flow through an opening that actually gets through, for a sharp-edged opening
+const DISCHARGE_COEFFICIENT: RawScalar = 0.6;
+/// A tile gives at most this fraction of its mass to a single neighbour per tick, so it never gives away more than it has
+const MAX_FLOW_FRACTION: RawScalar = 0.2;
+/// Liquid tiles with less mass than this flow into a neighbour all at once
+const MIN_FLUID_MASS: Kilogram = Kilogram::new_const(0.01);
+
+type FluidSystemData<'a> = (
+    ReadExpect<'a, MaterialRegistry>,
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, MaterialKind>,
+    ReadStorage<'a, Composition>,
+    ReadStorage<'a, Phase>,
+    ReadStorage<'a, Position>,
+    WriteStorage<'a, Mass>,
+    WriteStorage<'a, Heat>,
+    WriteStorage<'a, Volume>,
+    Entities<'a>,
+    Read<'a, LazyUpdate>,
+    Write<'a, SimulationErrors>,
+);
+
+/// Lets liquids and gases flow from high to low pressure, carrying their heat with them.
+///
+/// The pressure of a gas follows from the ideal gas law, and the pressure at the bottom of a liquid is the pressure of the gas above it plus the weight of the liquid. Fluids flow to neighbouring tiles that hold the same fluid, or that hold gas that they can push aside. Tiles with solids in them block the flow.
+///
+/// Only tiles made of a single material flow, mixtures stay where they are.
+pub struct FluidSystem;
+
+impl<'a> System<'a> for FluidSystem {
+    type SystemData = FluidSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            registry,
+            delta_time,
+            kind_storage,
+            composition_storage,
+            phase_storage,
+            position_storage,
+            mut mass_storage,
+            mut heat_storage,
+            mut volume_storage,
+            entities,
+            lazy,
+            mut errors,
+        ): Self::SystemData,
+    ) {
+        let mut tiles: HashMap<Position, Tile> = HashMap::new();
+        for (entity, position, phase) in
+            (&entities, &position_storage, phase_storage.maybe()).join()
+        {
+            let tile = tiles.entry(*position).or_insert_with(Tile::new);
+            let flows = match phase {
+                Some(phase) => phase.is_fluid() && !composition_storage.contains(entity),
+                None => false,
+            };
+            if !flows {
+                tile.blocked = true;
+            }
+            if phase != Some(&Phase::Gas) {
+                let volume = volume_storage
+                    .get(entity)
+                    .copied()
+                    .unwrap_or_else(Volume::full);
+                tile.occupied += volume.0;
+            }
+        }
+
+        let mut fluids: Vec<Fluid> = Vec::new();
+        for (entity, kind, phase, position, mass, heat, _) in (
+            &entities,
+            &kind_storage,
+            &phase_storage,
+            &position_storage,
+            &mass_storage,
+            &heat_storage,
+            !&composition_storage,
+        )
+            .join()
+        {
+            if !phase.is_fluid() || *mass <= Kilogram::new_const(0.0) {
+                continue;
+            }
+            let temperature = some_or_continue!(heat.try_temperature(*mass).ok());
+            let tile = tiles.entry(*position).or_insert_with(Tile::new);
+            let (volume, density) = match phase {
+                Phase::Gas => {
+                    let volume = Volume::free(tile.occupied);
+                    (volume, *mass / volume)
+                }
+                _ => {
+                    let density = some_or_continue!(registry.material(kind.id(), *phase)).density();
+                    (*mass / density, density)
+                }
+            };
+            let fluid = Fluid {
+                entity,
+                kind: kind.id(),
+                phase: *phase,
+                position: *position,
+                mass: *mass,
+                heat: *heat,
+                temperature,
+                volume,
+                density,
+            };
+            let excess_pressure = some_or_continue!(fluid.excess_pressure(&registry));
+            if fluid.phase == Phase::Gas {
+                tile.gas_pressure += excess_pressure;
+                tile.has_gas = true;
+            }
+            tile.fluids.push(fluids.len());
+            fluids.push(fluid);
+        }
+
+        let mut flows = Vec::new();
+        for (index, fluid) in fluids.iter().enumerate() {
+            let tile = &tiles[&fluid.position];
+            let same_fluid_in = |tile: &Tile| {
+                tile.fluids.iter().copied().find(|other| {
+                    fluids[*other].kind == fluid.kind && fluids[*other].phase == fluid.phase
+                })
+            };
+
+            // Fluids of the same kind that ended up on the same tile, e.g. because they were pushed around by the gravity system, merge into one
+            let first = same_fluid_in(tile);
+            if first != Some(index) {
+                flows.push(Flow {
+                    from: index,
+                    to: fluid.position,
+                    into: first,
+                    mass: fluid.mass,
+                });
+                continue;
+            }
+
+            let excess_pressure = some_or_continue!(fluid.excess_pressure(&registry));
+            let pressure_per_kg = excess_pressure / fluid.mass;
+
+            let mut targets: Vec<Position> = Vec::with_capacity(4);
+            for (_, position) in position_storage.neighbours(fluid.position) {
+                if !targets.contains(&position) {
+                    targets.push(position);
+                }
+            }
+            let mut outflows = Vec::with_capacity(targets.len());
+            for target in targets {
+                let target_tile = some_or_continue!(tiles.get(&target));
+                if target_tile.blocked || !target_tile.accepts(fluid, &fluids) {
+                    continue;
+                }
+                let same_fluid = same_fluid_in(target_tile);
+                let target_excess_pressure = match same_fluid {
+                    Some(other) => some_or_continue!(fluids[other].excess_pressure(&registry)),
+                    None => Pascal::new_const(0.0),
+                };
+
+                // The difference in pressure, plus the weight of the fluid if it flows down
+                let mut potential = match fluid.phase {
+                    Phase::Gas => tile.gas_pressure - target_tile.gas_pressure,
+                    _ => {
+                        let mut potential = excess_pressure - target_excess_pressure;
+                        // A liquid without gas above it is pressed on by the gas around it, so we can only compare gas pressures if both tiles have gas
+                        if tile.has_gas && target_tile.has_gas {
+                            potential += tile.gas_pressure - target_tile.gas_pressure;
+                        }
+                        potential
+                    }
+                };
+                let drop = Meter(scalar(RawScalar::from((target.y - fluid.position.y).raw())));
+                potential += fluid.density * GRAVITY * drop;
+                if potential <= Pascal::new_const(0.0) {
+                    continue;
+                }
+
+                // Never move more than it takes to equalize the pressure, otherwise the fluid sloshes back and forth
+                let mut pressure_per_kg_total = pressure_per_kg;
+                pressure_per_kg_total += match same_fluid {
+                    Some(other) => target_excess_pressure / fluids[other].mass,
+                    None => pressure_per_kg,
+                };
+                // A tile exchanges with all of its neighbours at once, so each one only gets its share
+                let equalizing_mass: Kilogram = potential / pressure_per_kg_total
+                    * scalar(1.0 / NEIGHBOUR_OFFSETS.len() as RawScalar);
+
+                // Liquids flow sideways through the part of the tile that they fill
+                let area = if fluid.phase == Phase::Liquid && drop == Meter::new_const(0.0) {
+                    TILE_FACE_AREA * (fluid.height() / Meter::new_const(1.0)).min(scalar(1.0))
+                } else {
+                    TILE_FACE_AREA
+                };
+                let orifice_flow = some_or_continue!(KilogramPerSecond::try_new(
+                    DISCHARGE_COEFFICIENT
+                        * area.raw()
+                        * (2.0 * fluid.density.raw() * potential.raw()).sqrt()
+                )
+                .ok());
+
+                let mut mass = orifice_flow * delta_time.as_si();
+                if equalizing_mass < mass {
+                    mass = equalizing_mass;
+                }
+                if fluid.mass * scalar(MAX_FLOW_FRACTION) < mass {
+                    mass = fluid.mass * scalar(MAX_FLOW_FRACTION);
+                }
+                if fluid.phase == Phase::Gas {
+                    // Every gas on the tile is pushed by the total pressure, so each one flows in proportion to its share
+                    mass = mass * (excess_pressure / tile.gas_pressure);
+                }
+                if mass <= Kilogram::new_const(0.0) {
+                    continue;
+                }
+                outflows.push(Flow {
+                    from: index,
+                    to: target,
+                    into: same_fluid,
+                    mass,
+                });
+            }
+
+            // A tiny bit of liquid would otherwise keep trickling out forever, so it joins its neighbour all at once. Traces of a gas are fine, they are mixed in by the atmosphere system
+            if fluid.phase == Phase::Liquid && fluid.mass < MIN_FLUID_MASS {
+                if let Some(flow) = outflows.iter().find(|flow| flow.into.is_some()) {
+                    flows.push(Flow {
+                        mass: fluid.mass,
+                        ..*flow
+                    });
+                    continue;
+                }
+            }
+            flows.extend(outflows);
+        }
+
+        let mut changes = vec![(Kilogram::new_const(0.0), Joule::new_const(0.0)); fluids.len()];
+        let mut spawns: Vec<Spawn> = Vec::new();
+        for flow in flows {
+            let fluid = &fluids[flow.from];
+            // The fluid takes its heat with it
+            let joules = fluid.heat.joules * (flow.mass / fluid.mass);
+            changes[flow.from].0 -= flow.mass;
+            changes[flow.from].1 -= joules;
+            match flow.into {
+                Some(other) => {
+                    changes[other].0 += flow.mass;
+                    changes[other].1 += joules;
+                }
+                None => {
+                    let existing = spawns.iter_mut().find(|spawn| {
+                        spawn.position == flow.to
+                            && spawn.kind == fluid.kind
+                            && spawn.phase == fluid.phase
+                    });
+                    match existing {
+                        Some(spawn) => {
+                            spawn.mass += flow.mass;
+                            spawn.joules += joules;
+                        }
+                        None => spawns.push(Spawn {
+                            position: flow.to,
+                            kind: fluid.kind,
+                            phase: fluid.phase,
+                            capacity: fluid.heat.capacity,
+                            mass: flow.mass,
+                            joules,
+                        }),
+                    }
+                }
+            }
+        }
+
+        // The tiles where a fluid changed its volume, and still fits on its own
+        let mut changed = Vec::new();
+        for (fluid, (mass_change, joules_change)) in fluids.iter().zip(changes) {
+            if mass_change == Kilogram::new_const(0.0) {
+                continue;
+            }
+            let mass = some_or_continue!(mass_storage.get_mut(fluid.entity));
+            *mass += mass_change;
+            if *mass <= Kilogram::new_const(0.0) {
+                // Everything flowed away. If deleting fails, the entity was already deleted by another system
+                let _ = entities.delete(fluid.entity);
+                continue;
+            }
+            if let Some(heat) = heat_storage.get_mut(fluid.entity) {
+                heat.joules += joules_change;
+            }
+            if let Some(volume) = volume_storage.get_mut(fluid.entity) {
+                let density =
+                    some_or_continue!(registry.material(fluid.kind, fluid.phase)).density();
+                // Until tiles can spill over, fluid that does not fit in its tile is compressed into it, and flows out again because of its pressure
+                *volume = match Volume::new(*mass, density, fluid.phase) {
+                    Ok(volume) => {
+                        if !changed.contains(&fluid.position) {
+                            changed.push(fluid.position);
+                        }
+                        volume
+                    }
+                    Err(error) => {
+                        errors.push(SimulationError::Overfull {
+                            position: fluid.position,
+                            error,
+                        });
+                        Volume::full()
+                    }
+                };
+            }
+        }
+        for position in changed {
+            if let Err(error) =
+                Volume::check_tile(position, &position_storage, &phase_storage, &volume_storage)
+            {
+                errors.push(SimulationError::Overfull { position, error });
+            }
+        }
+
+        for spawn in spawns {
+            let temperature: Kelvin = spawn.joules / (spawn.capacity * spawn.mass);
+            if let Err(error) = registry.build_entity(
+                spawn.kind,
+                lazy.create_entity(&entities),
+                temperature,
+                spawn.mass,
+                spawn.position,
+            ) {
+                errors.push(SimulationError::Flow {
+                    position: spawn.position,
+                    material: MaterialKind(spawn.kind)
+                        .name(&registry)
+                        .unwrap_or("an unknown material")
+                        .to_owned(),
+                    error,
+                });
+            }
+        }
+    }
+}
+
+/// A liquid or gas entity, as it was at the start of the tick.
+struct Fluid {
+    entity: Entity,
+    kind: MaterialId,
+    phase: Phase,
+    position: Position,
+    mass: Kilogram,
+    heat: Heat,
+    temperature: Kelvin,
+    /// For a gas this is the space that is left in its tile
+    volume: MeterCubed,
+    density: Density,
+}
+
+impl Fluid {
+    /// The height of a liquid in its tile. This is more than a tile if the liquid is compressed into its tile.
+    fn height(&self) -> Meter {
+        self.volume / TILE_FACE_AREA
+    }
+
+    /// The pressure that this fluid adds to its tile. For a gas this is its partial pressure, for a liquid this is the weight of the liquid.
+    fn excess_pressure(&self, registry: &MaterialRegistry) -> Option<Pascal> {
+        match self.phase {
+            Phase::Gas => Some(Pascal::ideal_gas(
+                self.mass,
+                registry.get(self.kind)?.molar_mass?,
+                self.temperature,
+                self.volume,
+            )),
+            _ => Some(self.density * GRAVITY * self.height()),
+        }
+    }
+}
+
+struct Tile {
+    /// The sum of the partial pressures of all gases in this tile
+    gas_pressure: Pascal,
+    /// The space that is taken up by solids and liquids
+    occupied: MeterCubed,
+    has_gas: bool,
+    /// Set if the tile holds something that fluids can not flow through, like a solid
+    blocked: bool,
+    /// Indices into the list of fluids
+    fluids: Vec<usize>,
+}
+
+impl Tile {
+    fn new() -> Self {
+        Tile {
+            gas_pressure: Pascal::new_const(0.0),
+            occupied: MeterCubed::new_const(0.0),
+            has_gas: false,
+            blocked: false,
+            fluids: Vec::new(),
+        }
+    }
+
+    /// Gases only flow into tiles that hold gas, and liquids do not flow into tiles that hold a different liquid.
+    fn accepts(&self, fluid: &Fluid, fluids: &[Fluid]) -> bool {
+        match fluid.phase {
+            Phase::Gas => self.has_gas,
+            _ => !self.fluids.iter().any(|other| {
+                let other = &fluids[*other];
+                other.phase == Phase::Liquid && other.kind != fluid.kind
+            }),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Flow {
+    from: usize,
+    to: Position,
+    /// The fluid of the same kind in the target tile, if there is one
+    into: Option<usize>,
+    mass: Kilogram,
+}
+
+/// Fluid that flowed into a tile that did not have any of it yet.
+struct Spawn {
+    position: Position,
+    kind: MaterialId,
+    phase: Phase,
+    capacity: SpecificHeatCapacity,
+    mass: Kilogram,
+    joules: Joule,
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::component::MaterialColor;
+    use crate::grid_storage::GridStorage;
+    use noisy_float::types::r32;
+
+    /// Build the `tiles` on a row of tiles, and let them flow for one tick
+    fn flow(tiles: &[(&str, RawScalar, RawScalar)]) -> (World, Vec<Entity>) {
+        let registry =
+            MaterialRegistry::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/materials.ron"))
+                .unwrap();
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(tiles.len(), 1));
+        world.register::<MaterialKind>();
+        world.register::<Composition>();
+        world.register::<Phase>();
+        world.register::<Mass>();
+        world.register::<Volume>();
+        world.register::<Heat>();
+        world.register::<MaterialColor>();
+        world.insert(DeltaTime::tick());
+        world.insert(SimulationErrors::default());
+        let entities = tiles
+            .iter()
+            .enumerate()
+            .map(|(x, (name, temperature, mass))| {
+                registry
+                    .build_entity(
+                        registry.require(name).unwrap(),
+                        world.create_entity(),
+                        Kelvin::new_const(*temperature),
+                        Kilogram::new_const(*mass),
+                        Position {
+                            x: r32(x as f32),
+                            y: r32(0.0),
+                        },
+                    )
+                    .unwrap()
+            })
+            .collect();
+        world.insert(registry);
+
+        FluidSystem.run_now(&world);
+        world.maintain();
+        assert_eq!(
+            world.write_resource::<SimulationErrors>().drain().count(),
+            0
+        );
+        (world, entities)
+    }
+
+    fn totals(world: &World) -> (RawScalar, RawScalar) {
+        let mut mass = 0.0;
+        let mut joules = 0.0;
+        for (tile_mass, heat) in
+            (&world.read_storage::<Mass>(), &world.read_storage::<Heat>()).join()
+        {
+            mass += tile_mass.raw();
+            joules += heat.joules.raw();
+        }
+        (mass, joules)
+    }
+
+    #[test]
+    fn gas_flows_from_high_to_low_pressure() {
+        let tiles = [("oxygen", 400.0, 2.0), ("oxygen", 300.0, 0.5)];
+        let (world, entities) = flow(&tiles);
+
+        let masses = world.read_storage::<Mass>();
+        let moved = 2.0 - masses.get(entities[0]).unwrap().raw();
+        assert!(moved > 0.0);
+        assert!((masses.get(entities[1]).unwrap().raw() - (0.5 + moved)).abs() < 1e-5);
+
+        // The gas takes its heat with it, so no mass or energy is lost
+        let capacity = world
+            .read_storage::<Heat>()
+            .get(entities[0])
+            .unwrap()
+            .capacity
+            .raw();
+        let (mass, joules) = totals(&world);
+        let joules_before = capacity * (400.0 * 2.0 + 300.0 * 0.5);
+        assert!((mass - 2.5).abs() < 1e-5);
+        assert!((joules - joules_before).abs() < 1e-5 * joules_before);
+    }
+
+    #[test]
+    fn solids_never_flow() {
+        let tiles = [
+            ("stone", 293.15, 100.0),
+            ("sand", 293.15, 100.0),
+            ("water", 293.15, 500.0),
+        ];
+        let (world, entities) = flow(&tiles);
+
+        assert_eq!(world.entities().join().count(), tiles.len());
+        let masses = world.read_storage::<Mass>();
+        let positions = world.read_storage::<Position>();
+        for (x, (entity, (_, _, mass))) in entities.iter().zip(&tiles).enumerate() {
+            assert_eq!(masses.get(*entity).unwrap().raw(), *mass);
+            assert_eq!(positions.get(*entity).unwrap().x, r32(x as f32));
+        }
+    }
+}
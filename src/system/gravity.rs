@@ -0,0 +1,270 @@
+use crate::component::{DeltaTime, Falling, Mass, MaterialKind, Phase, Volume};
+use crate::material::MaterialRegistry;
+use crate::units::{Density, Meter, GRAVITY};
+use crate::{GridStorageExt, GridStorageMutExt, Position};
+use noisy_float::types::r32;
+use specs::prelude::*;
+
+/// Entities move to the next tile every time they have travelled this far
+const TILE_SIZE: Meter = Meter::new_const(1.0);
+
+type GravitySystemData<'a> = (
+    ReadExpect<'a, MaterialRegistry>,
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, MaterialKind>,
+    ReadStorage<'a, Phase>,
+    ReadStorage<'a, Mass>,
+    ReadStorage<'a, Volume>,
+    WriteStorage<'a, Position>,
+    WriteStorage<'a, Falling>,
+    Entities<'a>,
+);
+
+/// Lets granular solids and liquids fall down, like sand in a falling-sand game.
+///
+/// An entity falls into the tile below, or diagonally below, when that tile only holds fluids that are lighter than the entity. Those fluids are pushed up into the tile it came from. Liquids that can not fall spread sideways instead.
+///
+/// Entities are updated from the bottom row up and from left to right, so the result does not depend on the order in which entities were created.
+#[derive(Default)]
+pub struct GravitySystem {
+    left_first: bool,
+}
+
+impl<'a> System<'a> for GravitySystem {
+    type SystemData = GravitySystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            registry,
+            delta_time,
+            kind_storage,
+            phase_storage,
+            mass_storage,
+            volume_storage,
+            mut position_storage,
+            mut falling_storage,
+            entities,
+        ): Self::SystemData,
+    ) {
+        // Alternate between trying left and right first, so piles and puddles stay symmetrical
+        self.left_first = !self.left_first;
+        let sides = if self.left_first { [-1, 1] } else { [1, -1] };
+
+        let mut movers: Vec<(Entity, Position)> =
+            (&entities, &position_storage, &phase_storage, &kind_storage)
+                .join()
+                .filter(|(_, _, phase, kind)| match phase {
+                    Phase::Solid => registry
+                        .get(kind.id())
+                        .is_some_and(|definition| definition.granular),
+                    Phase::Liquid => true,
+                    Phase::Gas => false,
+                })
+                .map(|(entity, position, _, _)| (entity, *position))
+                .collect();
+        movers.sort_by(|(entity, position), (other_entity, other_position)| {
+            other_position
+                .y
+                .cmp(&position.y)
+                .then(position.x.cmp(&other_position.x))
+                .then(entity.id().cmp(&other_entity.id()))
+        });
+
+        for (entity, _) in movers {
+            // The position might have changed if this entity was pushed up by something that fell into its tile
+            let mut position = *some_or_continue!(position_storage.get(entity));
+            let liquid = phase_storage.get(entity) == Some(&Phase::Liquid);
+            let density = some_or_continue!(density(entity, &mass_storage, &volume_storage));
+
+            let mut offsets = vec![(0, 1), (sides[0], 1), (sides[1], 1)];
+            if liquid {
+                offsets.push((sides[0], 0));
+                offsets.push((sides[1], 0));
+            }
+            let find_target = |position: Position, position_storage: &WriteStorage<Position>| {
+                offsets
+                    .iter()
+                    .map(|(x, y)| Position {
+                        x: position.x + r32(*x as f32),
+                        y: position.y + r32(*y as f32),
+                    })
+                    .find(|target| {
+                        can_enter(
+                            *target,
+                            entity,
+                            density,
+                            position_storage,
+                            &phase_storage,
+                            &mass_storage,
+                            &volume_storage,
+                        )
+                    })
+            };
+
+            if find_target(position, &position_storage).is_none() {
+                // Resting entities start falling from a standstill again
+                falling_storage.remove(entity);
+                continue;
+            }
+
+            let mut falling = falling_storage.get(entity).copied().unwrap_or_default();
+            falling.speed += GRAVITY * delta_time.as_si();
+            falling.distance += falling.speed * delta_time.as_si();
+            while falling.distance >= TILE_SIZE {
+                let target = match find_target(position, &position_storage) {
+                    Some(target) => target,
+                    None => {
+                        falling = Falling::default();
+                        break;
+                    }
+                };
+                let displaced: Vec<Entity> = position_storage
+                    .on_tile(target)
+                    .map(|(other, _)| other)
+                    .collect();
+                for other in displaced {
+                    position_storage.move_to(other, position);
+                }
+                position_storage.move_to(entity, target);
+                position = target;
+                falling.distance -= TILE_SIZE;
+            }
+            // This can only fail if the entity is dead, and dead entities are not joined
+            let _ = falling_storage.insert(entity, falling);
+        }
+    }
+}
+
+fn density(
+    entity: Entity,
+    mass_storage: &ReadStorage<Mass>,
+    volume_storage: &ReadStorage<Volume>,
+) -> Option<Density> {
+    let mass = *mass_storage.get(entity)?;
+    let volume = volume_storage
+        .get(entity)
+        .copied()
+        .unwrap_or_else(Volume::full);
+    Some(mass / volume.0)
+}
+
+/// An entity can move into a tile if the tile only contains fluids that are lighter than the entity.
+fn can_enter(
+    target: Position,
+    entity: Entity,
+    entity_density: Density,
+    position_storage: &WriteStorage<Position>,
+    phase_storage: &ReadStorage<Phase>,
+    mass_storage: &ReadStorage<Mass>,
+    volume_storage: &ReadStorage<Volume>,
+) -> bool {
+    if !position_storage.is_in_bounds(target) {
+        return false;
+    }
+    position_storage
+        .on_tile(target)
+        .filter(|(other, _)| *other != entity)
+        .all(|(other, _)| {
+            let fluid = matches!(phase_storage.get(other), Some(phase) if phase.is_fluid());
+            let lighter = matches!(
+                density(other, mass_storage, volume_storage),
+                Some(density) if density < entity_density
+            );
+            fluid && lighter
+        })
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::component::{Heat, MaterialColor};
+    use crate::grid_storage::GridStorage;
+    use crate::units::{Kelvin, Kilogram};
+
+    fn at(x: f32, y: f32) -> Position {
+        Position {
+            x: r32(x),
+            y: r32(y),
+        }
+    }
+
+    /// Build the `tiles` in order, and let them fall for `ticks` ticks. Returns where every tile ended up.
+    fn fall(
+        width: usize,
+        height: usize,
+        tiles: &[(&str, Position)],
+        ticks: usize,
+    ) -> Vec<Position> {
+        let registry =
+            MaterialRegistry::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/materials.ron"))
+                .unwrap();
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(width, height));
+        world.register::<MaterialKind>();
+        world.register::<Phase>();
+        world.register::<Mass>();
+        world.register::<Volume>();
+        world.register::<Heat>();
+        world.register::<MaterialColor>();
+        world.register::<Falling>();
+        world.insert(DeltaTime::tick());
+        let entities: Vec<Entity> = tiles
+            .iter()
+            .map(|(name, position)| {
+                registry
+                    .build_entity(
+                        registry.require(name).unwrap(),
+                        world.create_entity(),
+                        Kelvin::new_const(293.15),
+                        Kilogram::new_const(100.0),
+                        *position,
+                    )
+                    .unwrap()
+            })
+            .collect();
+        world.insert(registry);
+
+        let mut gravity = GravitySystem::default();
+        for _ in 0..ticks {
+            gravity.run_now(&world);
+            world.maintain();
+        }
+        let positions = world.read_storage::<Position>();
+        entities
+            .iter()
+            .map(|entity| *positions.get(*entity).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn granular_tiles_fall_into_empty_tiles() {
+        let positions = fall(1, 3, &[("sand", at(0.0, 0.0))], 20);
+        assert_eq!(positions, vec![at(0.0, 2.0)]);
+    }
+
+    #[test]
+    fn solid_tiles_stay_put() {
+        let positions = fall(1, 3, &[("stone", at(0.0, 0.0))], 20);
+        assert_eq!(positions, vec![at(0.0, 0.0)]);
+    }
+
+    #[test]
+    fn falling_does_not_depend_on_the_order_of_the_entities() {
+        let tiles = [
+            ("sand", at(1.0, 0.0)),
+            ("sand", at(1.0, 1.0)),
+            ("water", at(0.0, 0.0)),
+            ("water", at(2.0, 1.0)),
+            ("stone", at(1.0, 3.0)),
+        ];
+        let positions = fall(3, 4, &tiles, 30);
+        let reversed: Vec<_> = tiles.iter().rev().copied().collect();
+        let mut reversed_positions = fall(3, 4, &reversed, 30);
+        reversed_positions.reverse();
+        assert_eq!(positions, reversed_positions);
+        assert_eq!(positions, fall(3, 4, &tiles, 30));
+        // The sand piles up next to the stone
+        assert!(positions[..2].iter().all(|position| position.y == r32(3.0)));
+    }
+}
@@ -1,18 +1,21 @@
-use crate::component::{DeltaTime, Heat, Mass, StateChangeRequired};
-use crate::units::{Joule, Meter};
+use super::{SimulationError, SimulationErrors};
+use crate::component::{DeltaTime, Heat, Mass, StateChangeRequired, Volume};
+use crate::units::{scalar, CheckedDiv, CheckedMul, Joule, Meter, RawScalar};
 use crate::{GridStorageExt, Position};
-use noisy_float::types::{r32, R32};
 use specs::prelude::*;
+use std::collections::HashSet;
 
-const HEAT_EQUATION_DISTANCE: Meter = Meter(unsafe { R32::const_unchecked_new(0.000_01) });
+const HEAT_EQUATION_DISTANCE: Meter = Meter::new_const(0.000_01);
 
 type HeatSystemData<'a> = (
     ReadStorage<'a, Mass>,
+    ReadStorage<'a, Volume>,
     WriteStorage<'a, Heat>,
     WriteStorage<'a, StateChangeRequired>,
     ReadStorage<'a, Position>,
     Entities<'a>,
     ReadExpect<'a, DeltaTime>,
+    Write<'a, SimulationErrors>,
 );
 pub struct HeatSystem;
 
@@ -23,14 +26,18 @@ impl<'a> System<'a> for HeatSystem {
         &mut self,
         (
             mass_storage,
+            volume_storage,
             mut heat_storage,
             _state_change_required_storage,
             grid_storage,
             entities,
             delta_time,
+            mut errors,
         ): Self::SystemData,
     ) {
         let mut joules_updates = JoulesList::with_size(heat_storage.count());
+        // Odd tiles are seen by up to four even neighbours, but their errors are only reported once
+        let mut diverged = HashSet::new();
         for (mass, heat, position, entity) in
             (&mass_storage, &heat_storage, &grid_storage, &entities).join()
         {
@@ -38,28 +45,85 @@ impl<'a> System<'a> for HeatSystem {
                 // we only update the even tiles, the odd tiles will automatically be updated because they're neighbours of the even tiles
                 continue;
             }
-            let temp = heat.temperature(*mass);
+            let volume = volume_storage
+                .get(entity)
+                .copied()
+                .unwrap_or_else(Volume::full);
+            let temp = match heat.try_temperature(*mass) {
+                Ok(temp) => temp,
+                Err(error) => {
+                    errors.push(SimulationError::Temperature {
+                        position: *position,
+                        heat: *heat,
+                        mass: *mass,
+                        error,
+                    });
+                    continue;
+                }
+            };
 
-            for (other_entity, _) in grid_storage.neighbours(*position) {
+            for (other_entity, other_position) in grid_storage.neighbours(*position) {
                 if let (Some(other_mass), Some(other_heat)) = (
                     mass_storage.get(other_entity),
                     heat_storage.get(other_entity),
                 ) {
+                    let other_volume = volume_storage
+                        .get(other_entity)
+                        .copied()
+                        .unwrap_or_else(Volume::full);
+                    let contact_area = volume.contact_area(other_volume);
                     let mean_conductivity =
                         heat.conductivity.geometric_mean(other_heat.conductivity);
-                    let other_temp = other_heat.temperature(*other_mass);
-                    let temp_diff = temp - other_temp;
-                    let transfer_per_second =
-                        mean_conductivity * temp_diff / HEAT_EQUATION_DISTANCE;
-                    let transfer_this_tick = transfer_per_second * delta_time.as_si();
-
-                    joules_updates.add(entity, -transfer_this_tick);
-                    joules_updates.add(other_entity, transfer_this_tick);
+                    let other_temp = match other_heat.try_temperature(*other_mass) {
+                        Ok(temp) => temp,
+                        Err(error) => {
+                            // The odd tiles are only seen as neighbours, so this is where their errors are found
+                            if diverged.insert(other_entity) {
+                                errors.push(SimulationError::Temperature {
+                                    position: other_position,
+                                    heat: *other_heat,
+                                    mass: *other_mass,
+                                    error,
+                                });
+                            }
+                            continue;
+                        }
+                    };
+                    let transfer_this_tick = temp
+                        .checked_sub(other_temp)
+                        .and_then(|temp_diff| mean_conductivity.checked_mul(temp_diff))
+                        .and_then(|transfer| transfer.checked_mul(contact_area))
+                        .and_then(|transfer| transfer.checked_div(HEAT_EQUATION_DISTANCE))
+                        .and_then(|transfer_per_second| {
+                            transfer_per_second.checked_mul(delta_time.as_si())
+                        });
+                    let transfer_this_tick = match transfer_this_tick {
+                        Ok(transfer) => transfer,
+                        Err(error) => {
+                            errors.push(SimulationError::HeatTransfer {
+                                position: *position,
+                                other_position,
+                                temperature: temp,
+                                other_temperature: other_temp,
+                                conductivity: mean_conductivity,
+                                error,
+                            });
+                            continue;
+                        }
+                    };
+
+                    joules_updates.add(entity, *position, -transfer_this_tick, &mut errors);
+                    joules_updates.add(
+                        other_entity,
+                        other_position,
+                        transfer_this_tick,
+                        &mut errors,
+                    );
                 }
             }
         }
 
-        joules_updates.apply(&mut heat_storage, &entities);
+        joules_updates.apply(&mut heat_storage, &grid_storage, &entities, &mut errors);
     }
 }
 
@@ -73,25 +137,148 @@ impl JoulesList {
             entities: Vec::with_capacity(n),
         }
     }
-    pub fn add(&mut self, entity: Entity, joules: Joule) {
+    /// Add `joules` to the transfer of `entity`, or report the tile at `position` if the sum is not finite
+    pub fn add(
+        &mut self,
+        entity: Entity,
+        position: Position,
+        joules: Joule,
+        errors: &mut SimulationErrors,
+    ) {
         let index = entity.id() as usize;
-        if joules.0.raw() <= std::f32::EPSILON {
+        if joules.raw().abs() <= RawScalar::EPSILON {
             return;
         }
         if self.entities.len() <= index {
             self.entities.reserve(index - self.entities.len());
             while self.entities.len() <= index {
-                self.entities.push(Joule(r32(0.0)));
+                self.entities.push(Joule(scalar(0.0)));
             }
         }
-        self.entities[index] += joules;
+        match self.entities[index].checked_add(joules) {
+            Ok(sum) => self.entities[index] = sum,
+            Err(error) => errors.push(SimulationError::HeatApply {
+                position,
+                joules: self.entities[index],
+                transfer: joules,
+                error,
+            }),
+        }
     }
 
-    pub fn apply(self, storage: &mut WriteStorage<Heat>, entities: &Entities) {
-        for (entity, heat) in (entities, storage).join() {
+    pub fn apply(
+        self,
+        storage: &mut WriteStorage<Heat>,
+        positions: &ReadStorage<Position>,
+        entities: &Entities,
+        errors: &mut SimulationErrors,
+    ) {
+        for (entity, heat, position) in (entities, storage, positions).join() {
             if let Some(joules) = self.entities.get(entity.id() as usize) {
-                heat.joules += *joules;
+                match heat.joules.checked_add(*joules) {
+                    Ok(new_joules) => heat.joules = new_joules,
+                    Err(error) => errors.push(SimulationError::HeatApply {
+                        position: *position,
+                        joules: heat.joules,
+                        transfer: *joules,
+                        error,
+                    }),
+                }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid_storage::GridStorage;
+    use crate::units::{Kelvin, SpecificHeatCapacity, ThermalConductivity};
+    use noisy_float::types::r32;
+
+    // The transfers are too small to be seen next to the heat of a tile in single precision
+    #[cfg(feature = "f64")]
+    #[test]
+    fn small_transfer_to_large_tile() {
+        use crate::units::SpecificEnergy;
+
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(1, 1));
+        world.register::<Heat>();
+        let entity = world
+            .create_entity()
+            .with(Position {
+                x: r32(0.0),
+                y: r32(0.0),
+            })
+            .with(Heat {
+                capacity: SpecificHeatCapacity::new_const(1000.0),
+                conductivity: ThermalConductivity::new_const(1.0),
+                joules: Joule::new_const(1e9),
+                latent: SpecificEnergy::new_const(0.0),
+            })
+            .build();
+
+        let mut list = JoulesList::with_size(1);
+        let mut errors = SimulationErrors::default();
+        list.add(
+            entity,
+            Position {
+                x: r32(0.0),
+                y: r32(0.0),
+            },
+            Joule::new_const(1e-3),
+            &mut errors,
+        );
+        world.exec(
+            |(mut heat, positions, entities): (
+                WriteStorage<Heat>,
+                ReadStorage<Position>,
+                Entities,
+            )| list.apply(&mut heat, &positions, &entities, &mut errors),
+        );
+
+        let joules = world.read_storage::<Heat>().get(entity).unwrap().joules;
+        assert!(((joules - Joule::new_const(1e9)).raw() - 1e-3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn diverging_tile_is_reported_once() {
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(3, 1));
+        world.register::<Mass>();
+        world.register::<Volume>();
+        world.register::<Heat>();
+        world.register::<StateChangeRequired>();
+        world.insert(DeltaTime::tick());
+        world.insert(SimulationErrors::default());
+        let heat = Heat::from_material_specs(
+            Kelvin::new_const(300.0),
+            Mass::new_const(1.0),
+            SpecificHeatCapacity::new_const(1000.0),
+            ThermalConductivity::new_const(1.0),
+        );
+        for x in 0..3 {
+            // The odd tile in the middle has lost all of its mass, so it has no temperature
+            let mass = if x == 1 { 0.0 } else { 1.0 };
+            world
+                .create_entity()
+                .with(Position {
+                    x: r32(x as f32),
+                    y: r32(0.0),
+                })
+                .with(Mass::new_const(mass))
+                .with(heat)
+                .build();
+        }
+
+        HeatSystem.run_now(&world);
+
+        let errors: Vec<_> = world.write_resource::<SimulationErrors>().drain().collect();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        match &errors[0] {
+            SimulationError::Temperature { position, .. } => assert_eq!(position.x, r32(1.0)),
+            error => panic!("unexpected error {}", error),
+        }
+    }
+}
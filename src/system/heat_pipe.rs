@@ -0,0 +1,269 @@
+use crate::component::{
+    Boiler, Damage, DeltaTime, Heat, HeatExchanger, HeatPipe, Mass, Reactor, SteamEngine,
+};
+use crate::units::{scalar, HeatCapacity, Joule, Kelvin, Seconds, WattPerKelvin};
+use crate::{GridStorageExt, Position};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// How well a heat pipe passes heat to a machine next to it. This is far more than any material conducts over a tile, so the exchange is usually limited by the two reaching the same temperature
+const EXCHANGE_CONDUCTANCE: WattPerKelvin = WattPerKelvin::new_const(1e8);
+
+type HeatPipeSystemData<'a> = (
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, HeatExchanger>,
+    ReadStorage<'a, Boiler>,
+    ReadStorage<'a, SteamEngine>,
+    ReadStorage<'a, Reactor>,
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, Mass>,
+    WriteStorage<'a, HeatPipe>,
+    WriteStorage<'a, Heat>,
+    WriteStorage<'a, Damage>,
+    Entities<'a>,
+);
+
+/// A fast path next to the [HeatSystem](super::HeatSystem) for [HeatPipe]s.
+///
+/// Heat pipes first exchange heat with the machines next to them: [HeatExchanger]s, [Boiler]s, [SteamEngine]s and [Reactor]s. Then every network of connected heat pipes shares its heat, as if it had no thermal resistance at all: the heat is spread over the pipes by their heat capacity, so they all end up at the same temperature. This takes a single pass over the pipes, no matter how long the network is. Heat pipes also conduct heat to and from the other tiles around them through the `HeatSystem`, like any other tile.
+///
+/// A heat pipe that is hotter than its maximum temperature bursts. It is no longer a heat pipe, and its tile is broken.
+pub struct HeatPipeSystem;
+
+impl<'a> System<'a> for HeatPipeSystem {
+    type SystemData = HeatPipeSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            delta_time,
+            exchanger_storage,
+            boiler_storage,
+            engine_storage,
+            reactor_storage,
+            position_storage,
+            mass_storage,
+            mut pipe_storage,
+            mut heat_storage,
+            mut damage_storage,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let delta_time = delta_time.as_si();
+        let pipes: Vec<(Entity, Position, Mass)> = (
+            &entities,
+            &pipe_storage,
+            &position_storage,
+            &mass_storage,
+            &heat_storage,
+        )
+            .join()
+            .map(|(entity, _, position, mass, _)| (entity, *position, *mass))
+            .collect();
+        let indices: HashMap<Entity, usize> = pipes
+            .iter()
+            .enumerate()
+            .map(|(index, (entity, _, _))| (*entity, index))
+            .collect();
+
+        let is_exchanger = |entity: Entity| {
+            exchanger_storage.contains(entity)
+                || boiler_storage.contains(entity)
+                || engine_storage.contains(entity)
+                || reactor_storage.contains(entity)
+        };
+        for (entity, position, mass) in &pipes {
+            for (neighbour, _) in position_storage.neighbours(*position) {
+                if !is_exchanger(neighbour) || indices.contains_key(&neighbour) {
+                    continue;
+                }
+                let pipe = some_or_continue!(thermal_state(&heat_storage, *entity, *mass));
+                let neighbour_mass = *some_or_continue!(mass_storage.get(neighbour));
+                let machine =
+                    some_or_continue!(thermal_state(&heat_storage, neighbour, neighbour_mass));
+                let transfer = exchange(pipe, machine, EXCHANGE_CONDUCTANCE, delta_time);
+                if let Some(heat) = heat_storage.get_mut(*entity) {
+                    heat.joules -= transfer;
+                }
+                if let Some(heat) = heat_storage.get_mut(neighbour) {
+                    heat.joules += transfer;
+                }
+            }
+        }
+
+        let mut network_of = vec![None; pipes.len()];
+        for start in 0..pipes.len() {
+            if network_of[start].is_some() {
+                continue;
+            }
+            network_of[start] = Some(start);
+            let mut network = vec![start];
+            let mut next = 0;
+            while let Some(index) = network.get(next).copied() {
+                next += 1;
+                for (neighbour, _) in position_storage.neighbours(pipes[index].1) {
+                    let other = *some_or_continue!(indices.get(&neighbour));
+                    if network_of[other].is_none() {
+                        network_of[other] = Some(start);
+                        network.push(other);
+                    }
+                }
+            }
+
+            let mut joules = Joule::new_const(0.0);
+            let mut capacity = HeatCapacity::new_const(0.0);
+            for index in &network {
+                let (entity, _, mass) = pipes[*index];
+                let heat = some_or_continue!(heat_storage.get(entity));
+                joules += heat.joules;
+                capacity += heat.capacity * mass;
+            }
+            let temperature: Kelvin = joules / capacity;
+            for index in network {
+                let (entity, _, mass) = pipes[index];
+                if let Some(heat) = heat_storage.get_mut(entity) {
+                    heat.joules = heat.capacity * mass * temperature;
+                }
+                let burst = matches!(pipe_storage.get(entity), Some(pipe) if temperature > pipe.max_temperature);
+                if burst {
+                    pipe_storage.remove(entity);
+                    if let Ok(entry) = damage_storage.entry(entity) {
+                        entry.or_insert_with(Damage::default).add(scalar(1.0));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn thermal_state(
+    heat_storage: &WriteStorage<Heat>,
+    entity: Entity,
+    mass: Mass,
+) -> Option<(Kelvin, HeatCapacity)> {
+    let heat = heat_storage.get(entity)?;
+    Some((heat.try_temperature(mass).ok()?, heat.capacity * mass))
+}
+
+/// The heat that flows from `a` to `b` during a tick, given their temperature and heat capacity.
+///
+/// The flow is limited to the heat that brings both to the same temperature, ΔT×Ca×Cb/(Ca+Cb), so a large conductance can not make them overshoot. Nothing flows when neither side has any heat capacity.
+pub(super) fn exchange(
+    (a_temperature, a_capacity): (Kelvin, HeatCapacity),
+    (b_temperature, b_capacity): (Kelvin, HeatCapacity),
+    conductance: WattPerKelvin,
+    delta_time: Seconds,
+) -> Joule {
+    let difference = a_temperature - b_temperature;
+    let flow = conductance * difference * delta_time;
+    let mut total_capacity = a_capacity;
+    total_capacity += b_capacity;
+    if total_capacity <= HeatCapacity::new_const(0.0) {
+        // Neither side can hold any heat
+        return Joule::new_const(0.0);
+    }
+    let equilibrium = a_capacity * (b_capacity / total_capacity) * difference;
+    if difference > Kelvin::new_const(0.0) {
+        flow.min(equilibrium)
+    } else {
+        flow.max(equilibrium)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid_storage::GridStorage;
+    use crate::units::{Kilogram, RawScalar, SpecificHeatCapacity, ThermalConductivity, Watt};
+    use noisy_float::types::r32;
+
+    const MASS: Kilogram = Kilogram::new_const(1.0);
+
+    #[test]
+    fn pipes_carry_heat_from_a_reactor_to_an_engine() {
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(5, 1));
+        world.register::<HeatExchanger>();
+        world.register::<Boiler>();
+        world.register::<SteamEngine>();
+        world.register::<Reactor>();
+        world.register::<Mass>();
+        world.register::<HeatPipe>();
+        world.register::<Heat>();
+        world.register::<Damage>();
+        world.insert(DeltaTime::tick());
+        fn tile(world: &mut World, x: f32, temperature: RawScalar) -> EntityBuilder {
+            world
+                .create_entity()
+                .with(Position {
+                    x: r32(x),
+                    y: r32(0.0),
+                })
+                .with(MASS)
+                .with(Heat::from_material_specs(
+                    Kelvin::new_const(temperature),
+                    MASS,
+                    SpecificHeatCapacity::new_const(500.0),
+                    ThermalConductivity::new_const(50.0),
+                ))
+        }
+        let reactor = tile(&mut world, 0.0, 900.0)
+            .with(Reactor::new(
+                Watt::new_const(0.0),
+                Joule::new_const(0.0),
+                Kelvin::new_const(2000.0),
+                1,
+            ))
+            .build();
+        let pipe = HeatPipe {
+            max_temperature: Kelvin::new_const(1500.0),
+        };
+        let pipes: Vec<Entity> = (1..4)
+            .map(|x| tile(&mut world, x as f32, 300.0).with(pipe).build())
+            .collect();
+        let engine = tile(&mut world, 4.0, 300.0)
+            .with(SteamEngine::new(Watt::new_const(1e6), 0.5))
+            .build();
+
+        let temperature = |world: &World, entity| {
+            world
+                .read_storage::<Heat>()
+                .get(entity)
+                .unwrap()
+                .temperature(MASS)
+                .raw()
+        };
+        let total = |world: &World| {
+            let mut joules = Joule::new_const(0.0);
+            for heat in world.read_storage::<Heat>().join() {
+                joules += heat.joules;
+            }
+            joules.raw()
+        };
+        let before = total(&world);
+        HeatPipeSystem.run_now(&world);
+        // The reactor heats the first pipe, and the network shares that heat right away
+        let first = temperature(&world, pipes[0]);
+        assert!(first > 300.0);
+        for pipe in &pipes {
+            assert!((temperature(&world, *pipe) - first).abs() < 1e-3);
+        }
+        for _ in 0..10 {
+            HeatPipeSystem.run_now(&world);
+        }
+        assert!(temperature(&world, engine) > 300.0);
+        assert!(temperature(&world, reactor) < 900.0);
+        assert!((total(&world) - before).abs() < before * 1e-5);
+    }
+
+    #[test]
+    fn nothing_is_exchanged_without_heat_capacity() {
+        let transfer = exchange(
+            (Kelvin::new_const(400.0), HeatCapacity::new_const(0.0)),
+            (Kelvin::new_const(300.0), HeatCapacity::new_const(0.0)),
+            WattPerKelvin::new_const(1.0),
+            DeltaTime::tick().as_si(),
+        );
+        assert_eq!(transfer, Joule::new_const(0.0));
+    }
+}
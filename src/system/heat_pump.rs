@@ -0,0 +1,182 @@
+use crate::component::{Consumer, DeltaTime, Heat, HeatPump, Mass};
+use crate::units::{scalar, Joule, RawScalar};
+use crate::{GridStorageExt, Position};
+use specs::prelude::*;
+
+/// A pump takes at most this fraction of the heat of its cold side in a single tick, so a small cold side can not be pumped down to absolute zero at once
+const MAX_COLD_FRACTION: RawScalar = 0.1;
+
+type HeatPumpSystemData<'a> = (
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, HeatPump>,
+    ReadStorage<'a, Consumer>,
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, Mass>,
+    WriteStorage<'a, Heat>,
+);
+
+/// Runs the [HeatPump]s on the power that their [Consumer] received from the [PowerGridSystem](super::PowerGridSystem).
+///
+/// A pump that gets less power moves less heat. The pump acts on the first tile with [Heat] on each of its sides, and does nothing if either side is missing.
+pub struct HeatPumpSystem;
+
+impl<'a> System<'a> for HeatPumpSystem {
+    type SystemData = HeatPumpSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            delta_time,
+            pump_storage,
+            consumer_storage,
+            position_storage,
+            mass_storage,
+            mut heat_storage,
+        ): Self::SystemData,
+    ) {
+        let delta_time = delta_time.as_si();
+        for (pump, consumer, position) in
+            (&pump_storage, &consumer_storage, &position_storage).join()
+        {
+            let tile_at = |side_position: Position| {
+                position_storage
+                    .on_tile(side_position)
+                    .map(|(entity, _)| entity)
+                    .find(|entity| heat_storage.contains(*entity) && mass_storage.contains(*entity))
+            };
+            let cold = some_or_continue!(tile_at(pump.cold_side.of(*position)));
+            let hot = some_or_continue!(tile_at(pump.hot_side.of(*position)));
+            if cold == hot {
+                continue;
+            }
+            let cold_heat = *some_or_continue!(heat_storage.get(cold));
+            let hot_heat = *some_or_continue!(heat_storage.get(hot));
+            let cold_mass = *some_or_continue!(mass_storage.get(cold));
+            let hot_mass = *some_or_continue!(mass_storage.get(hot));
+            let cold_temperature = some_or_continue!(cold_heat.try_temperature(cold_mass).ok());
+            let hot_temperature = some_or_continue!(hot_heat.try_temperature(hot_mass).ok());
+
+            let work: Joule = consumer.received() * delta_time;
+            let coefficient = pump.effective_coefficient(cold_temperature, hot_temperature);
+            let moved = (work * coefficient).min(cold_heat.joules * scalar(MAX_COLD_FRACTION));
+            if let Some(heat) = heat_storage.get_mut(cold) {
+                heat.joules -= moved;
+            }
+            if let Some(heat) = heat_storage.get_mut(hot) {
+                heat.joules += moved;
+                heat.joules += work;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::Side;
+    use crate::grid_storage::GridStorage;
+    use crate::units::{Kelvin, Kilogram, SpecificHeatCapacity, ThermalConductivity, Watt};
+    use noisy_float::types::r32;
+
+    /// Runs a pump that gets `demand` between a cold tile and a hot tile for one tick, and returns the heat that each side gained
+    fn pump(
+        coefficient_of_performance: RawScalar,
+        cold: RawScalar,
+        hot: RawScalar,
+        demand: Watt,
+    ) -> (Joule, Joule) {
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(3, 1));
+        world.register::<HeatPump>();
+        world.register::<Consumer>();
+        world.register::<Mass>();
+        world.register::<Heat>();
+        world.insert(DeltaTime::tick());
+        let mut tile = |x: f32, temperature: RawScalar| {
+            let mass = Kilogram::new_const(1.0);
+            world
+                .create_entity()
+                .with(Position {
+                    x: r32(x),
+                    y: r32(0.0),
+                })
+                .with(mass)
+                .with(Heat::from_material_specs(
+                    Kelvin::new_const(temperature),
+                    mass,
+                    SpecificHeatCapacity::new_const(1000.0),
+                    ThermalConductivity::new_const(1.0),
+                ))
+                .build()
+        };
+        let cold = tile(0.0, cold);
+        let hot = tile(2.0, hot);
+        let mut consumer = Consumer::new(demand);
+        consumer.set_satisfaction(scalar(1.0));
+        world
+            .create_entity()
+            .with(Position {
+                x: r32(1.0),
+                y: r32(0.0),
+            })
+            .with(HeatPump {
+                cold_side: Side::Left,
+                hot_side: Side::Right,
+                coefficient_of_performance,
+            })
+            .with(consumer)
+            .build();
+
+        let joules =
+            |world: &World, entity| world.read_storage::<Heat>().get(entity).unwrap().joules;
+        let cold_before = joules(&world, cold);
+        let hot_before = joules(&world, hot);
+        HeatPumpSystem.run_now(&world);
+        (
+            joules(&world, cold) - cold_before,
+            joules(&world, hot) - hot_before,
+        )
+    }
+
+    fn close(a: Joule, b: Joule) -> bool {
+        (a - b).raw().abs() <= 1e-3 * b.raw().abs()
+    }
+
+    #[test]
+    fn moved_heat_and_work_end_up_on_the_hot_side() {
+        let demand = Watt::new_const(100.0);
+        let work = demand * DeltaTime::tick().as_si();
+        let (cold, hot) = pump(3.0, 280.0, 300.0, demand);
+        let mut moved = Joule::new_const(0.0);
+        moved -= cold;
+        assert!(close(moved, work * scalar(3.0)), "moved {:?}", moved);
+        moved += work;
+        assert!(close(hot, moved), "the hot side gained {:?}", hot);
+    }
+
+    #[test]
+    fn coefficient_of_performance_is_capped_by_carnot() {
+        let pump_component = HeatPump {
+            cold_side: Side::Left,
+            hot_side: Side::Right,
+            coefficient_of_performance: 100.0,
+        };
+        let cold = Kelvin::new_const(250.0);
+        let hot = Kelvin::new_const(500.0);
+        assert_eq!(pump_component.effective_coefficient(cold, hot), scalar(1.0));
+        // Heat flows from hot to cold by itself, so the configured coefficient applies
+        assert_eq!(
+            pump_component.effective_coefficient(hot, cold),
+            scalar(100.0)
+        );
+
+        let demand = Watt::new_const(100.0);
+        let work = demand * DeltaTime::tick().as_si();
+        let (cold, hot) = pump(100.0, 250.0, 500.0, demand);
+        let mut moved = Joule::new_const(0.0);
+        moved -= cold;
+        assert!(close(moved, work), "moved {:?}", moved);
+        moved += work;
+        assert!(close(hot, moved), "the hot side gained {:?}", hot);
+    }
+}
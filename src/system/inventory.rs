@@ -0,0 +1,159 @@
+use super::heat_pipe::exchange;
+use crate::component::{DeltaTime, Heat, Inventory, Mass};
+use crate::material::{ItemRegistry, MaterialRegistry};
+use crate::units::{HeatCapacity, Kelvin, WattPerKelvin};
+use specs::prelude::*;
+
+/// How well a stack of items passes heat to the inventory that holds it, through the walls that it touches
+const CONTACT_CONDUCTANCE: WattPerKelvin = WattPerKelvin::new_const(100.0);
+
+type InventorySystemData<'a> = (
+    ReadExpect<'a, MaterialRegistry>,
+    ReadExpect<'a, ItemRegistry>,
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, Mass>,
+    WriteStorage<'a, Inventory>,
+    WriteStorage<'a, Heat>,
+);
+
+/// Exchanges heat between the items in an [Inventory] and the entity that holds them.
+///
+/// Every stack exchanges heat with the entity on its own, so hot ingots that are put into a cold chest heat up the chest, and the chest passes that heat on to its neighbours through the [HeatSystem](super::HeatSystem).
+pub struct InventorySystem;
+
+impl<'a> System<'a> for InventorySystem {
+    type SystemData = InventorySystemData<'a>;
+
+    fn run(
+        &mut self,
+        (materials, items, delta_time, mass_storage, mut inventory_storage, mut heat_storage): Self::SystemData,
+    ) {
+        let delta_time = delta_time.as_si();
+        for (inventory, mass, heat) in
+            (&mut inventory_storage, &mass_storage, &mut heat_storage).join()
+        {
+            let capacity = heat.capacity * *mass;
+            for stack in inventory.stacks_mut() {
+                let temperature = some_or_continue!(heat.try_temperature(*mass).ok());
+                let stack_capacity = some_or_continue!(stack.heat_capacity(&items, &materials));
+                if stack_capacity <= HeatCapacity::new_const(0.0) {
+                    // A stack without items, or of weightless items, holds no heat
+                    continue;
+                }
+                let transfer = exchange(
+                    (stack.temperature, stack_capacity),
+                    (temperature, capacity),
+                    CONTACT_CONDUCTANCE,
+                    delta_time,
+                );
+                let change: Kelvin = transfer / stack_capacity;
+                stack.temperature -= change;
+                heat.joules += transfer;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::component::Stack;
+    use crate::units::{Joule, Kilogram, SpecificHeatCapacity, ThermalConductivity};
+
+    fn chest_with_ore(count: u32, ore: Kelvin, chest: Kelvin) -> (World, Entity) {
+        let materials =
+            MaterialRegistry::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/materials.ron"))
+                .unwrap();
+        let items = ItemRegistry::load(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/assets/items.ron"),
+            &materials,
+        )
+        .unwrap();
+        let mut inventory = Inventory::new(1);
+        let left = inventory.insert(
+            &items,
+            Stack {
+                item: items.by_name("iron ore").unwrap(),
+                count,
+                temperature: ore,
+            },
+        );
+        assert!(left.is_none());
+
+        let mut world = World::new();
+        world.register::<Mass>();
+        world.register::<Heat>();
+        world.register::<Inventory>();
+        world.insert(DeltaTime::tick());
+        let mass = Kilogram::new_const(10.0);
+        let entity = world
+            .create_entity()
+            .with(mass)
+            .with(Heat::from_material_specs(
+                chest,
+                mass,
+                SpecificHeatCapacity::new_const(500.0),
+                ThermalConductivity::new_const(50.0),
+            ))
+            .with(inventory)
+            .build();
+        world.insert(materials);
+        world.insert(items);
+        (world, entity)
+    }
+
+    /// The temperature of the stack, and the heat of the chest and the stack together
+    fn state(world: &World, chest: Entity) -> (Kelvin, Joule) {
+        let materials = world.read_resource::<MaterialRegistry>();
+        let items = world.read_resource::<ItemRegistry>();
+        let inventory = world.read_storage::<Inventory>();
+        let stack = *inventory.get(chest).unwrap().stacks().next().unwrap();
+        let mut total = world.read_storage::<Heat>().get(chest).unwrap().joules;
+        total += stack.heat_capacity(&items, &materials).unwrap() * stack.temperature;
+        (stack.temperature, total)
+    }
+
+    fn chest_temperature(world: &World, chest: Entity) -> Kelvin {
+        let mass = *world.read_storage::<Mass>().get(chest).unwrap();
+        world
+            .read_storage::<Heat>()
+            .get(chest)
+            .unwrap()
+            .temperature(mass)
+    }
+
+    #[test]
+    fn hot_ore_warms_up_a_cold_chest() {
+        let (world, chest) = chest_with_ore(10, Kelvin::new_const(800.0), Kelvin::new_const(300.0));
+        let (_, before) = state(&world, chest);
+        let mut last = Kelvin::new_const(300.0);
+        for _ in 0..6000 {
+            InventorySystem.run_now(&world);
+            let temperature = chest_temperature(&world, chest);
+            assert!(temperature >= last);
+            last = temperature;
+        }
+
+        let (ore, after) = state(&world, chest);
+        assert!((ore - last).raw().abs() < 1.0, "{:?} and {:?}", ore, last);
+        assert!(last > Kelvin::new_const(300.0) && last < Kelvin::new_const(800.0));
+        assert!((after - before).raw().abs() < before.raw() * 1e-4);
+    }
+
+    #[test]
+    fn empty_stacks_are_skipped() {
+        let (world, chest) = chest_with_ore(10, Kelvin::new_const(800.0), Kelvin::new_const(300.0));
+        for stack in world
+            .write_storage::<Inventory>()
+            .get_mut(chest)
+            .unwrap()
+            .stacks_mut()
+        {
+            stack.count = 0;
+        }
+        let before = world.read_storage::<Heat>().get(chest).unwrap().joules;
+        InventorySystem.run_now(&world);
+        let after = world.read_storage::<Heat>().get(chest).unwrap().joules;
+        assert_eq!(before, after);
+    }
+}
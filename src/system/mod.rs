@@ -1,3 +1,39 @@
+mod atmosphere;
+mod cooling;
+mod electric;
+mod error;
+mod fire;
+mod fluid;
+mod gravity;
 mod heat;
+mod heat_pipe;
+mod heat_pump;
+mod inventory;
+mod motion;
+mod phase;
+mod power;
+mod reaction;
+mod reactor;
+mod steam;
+mod structure;
+mod thermal_stress;
 
+pub use self::atmosphere::*;
+pub use self::cooling::*;
+pub use self::electric::*;
+pub use self::error::*;
+pub use self::fire::*;
+pub use self::fluid::*;
+pub use self::gravity::*;
 pub use self::heat::*;
+pub use self::heat_pipe::*;
+pub use self::heat_pump::*;
+pub use self::inventory::*;
+pub use self::motion::*;
+pub use self::phase::*;
+pub use self::power::*;
+pub use self::reaction::*;
+pub use self::reactor::*;
+pub use self::steam::*;
+pub use self::structure::*;
+pub use self::thermal_stress::*;
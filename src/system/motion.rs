@@ -0,0 +1,474 @@
+use crate::component::{Acceleration, DeltaTime, Force, Heat, Mass, Phase, Velocity};
+use crate::units::{scalar, Joule, Kilogram, Meter, MeterPerSecond, RawScalar, Seconds, GRAVITY};
+use crate::{GridStorageExt, GridStorageMutExt, Position};
+use noisy_float::types::r32;
+use specs::prelude::*;
+
+/// Entities move to the next tile every time they have travelled this far
+const TILE_SIZE: Meter = Meter::new_const(1.0);
+/// The coefficient of kinetic friction between two bodies. This is a typical value for dry surfaces like wood or stone
+const FRICTION_COEFFICIENT: RawScalar = 0.4;
+
+type MotionSystemData<'a> = (
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, Phase>,
+    ReadStorage<'a, Mass>,
+    ReadStorage<'a, Acceleration>,
+    WriteStorage<'a, Force>,
+    WriteStorage<'a, Velocity>,
+    WriteStorage<'a, Heat>,
+    WriteStorage<'a, Position>,
+    Entities<'a>,
+);
+
+/// Moves rigid bodies, i.e. entities with a [Velocity].
+///
+/// The velocity changes by the [Acceleration] and by the [Force] on the entity, divided by its mass. A body that moves into a tile with only fluids pushes those fluids into the tile it came from. A body that moves into a solid, or into the edge of the grid, collides with it. Collisions are perfectly inelastic, so both bodies end up with the same velocity.
+///
+/// A body that rests on a solid, or on the bottom of the grid, slows down because of kinetic friction. The kinetic energy that is lost to friction and collisions is split evenly over the [Heat] of both bodies.
+#[derive(Default)]
+pub struct MotionSystem;
+
+impl<'a> System<'a> for MotionSystem {
+    type SystemData = MotionSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            delta_time,
+            phase_storage,
+            mass_storage,
+            acceleration_storage,
+            mut force_storage,
+            mut velocity_storage,
+            mut heat_storage,
+            mut position_storage,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let delta_time = delta_time.as_si();
+
+        let mut movers: Vec<(Entity, Position)> = (&entities, &position_storage, &velocity_storage)
+            .join()
+            .map(|(entity, position, _)| (entity, *position))
+            .collect();
+        // Update the bodies in a fixed order, so the result does not depend on the order in which entities were created
+        movers.sort_by(|(entity, position), (other_entity, other_position)| {
+            other_position
+                .y
+                .cmp(&position.y)
+                .then(position.x.cmp(&other_position.x))
+                .then(entity.id().cmp(&other_entity.id()))
+        });
+
+        for (entity, _) in movers {
+            let mut position = *some_or_continue!(position_storage.get(entity));
+            let mass = *some_or_continue!(mass_storage.get(entity));
+            if mass <= Kilogram::new_const(0.0) {
+                continue;
+            }
+            let mut velocity = *some_or_continue!(velocity_storage.get(entity));
+
+            let previous_velocity = velocity;
+            // Newton's second law, integrated with the semi-implicit Euler method
+            let force = force_storage.get(entity).copied().unwrap_or_default();
+            let mut acceleration = acceleration_storage
+                .get(entity)
+                .copied()
+                .unwrap_or_default();
+            acceleration.x += force.x / mass;
+            acceleration.y += force.y / mass;
+            velocity.x += acceleration.x * delta_time;
+            velocity.y += acceleration.y * delta_time;
+
+            let below = Position {
+                x: position.x,
+                y: position.y + r32(1.0),
+            };
+            if let Some(support) = obstacle(below, entity, &position_storage, &phase_storage) {
+                let other = support.and_then(|other| {
+                    let other_velocity = velocity_storage.get(other)?;
+                    Some((other_velocity.x, *mass_storage.get(other)?))
+                });
+                let (speed, other_speed, joules) = slide(velocity.x, mass, other, delta_time);
+                velocity.x = speed;
+                if let (Some(other), Some(other_speed)) = (support, other_speed) {
+                    if let Some(other_velocity) = velocity_storage.get_mut(other) {
+                        other_velocity.x = other_speed;
+                    }
+                }
+                dissipate(entity, support, joules, &mut heat_storage);
+            }
+
+            for horizontal in &[true, false] {
+                let horizontal = *horizontal;
+                let speed = if horizontal { velocity.x } else { velocity.y };
+
+                // A body that touches an obstacle can not move into it
+                if speed != MeterPerSecond::new_const(0.0) {
+                    let step = if speed > MeterPerSecond::new_const(0.0) {
+                        1
+                    } else {
+                        -1
+                    };
+                    let neighbour = neighbour(position, horizontal, step);
+                    if let Some(blocker) =
+                        obstacle(neighbour, entity, &position_storage, &phase_storage)
+                    {
+                        // An obstacle that can not move pushes back, which cancels the acceleration towards it
+                        if !matches!(blocker, Some(other) if velocity_storage.contains(other)) {
+                            if horizontal {
+                                velocity.x = previous_velocity.x;
+                            } else {
+                                velocity.y = previous_velocity.y;
+                            }
+                        }
+                        impact(
+                            entity,
+                            mass,
+                            &mut velocity,
+                            blocker,
+                            horizontal,
+                            step,
+                            &mass_storage,
+                            &mut velocity_storage,
+                            &mut heat_storage,
+                        );
+                    }
+                }
+
+                let speed = if horizontal { velocity.x } else { velocity.y };
+                let offset = if horizontal {
+                    &mut velocity.offset_x
+                } else {
+                    &mut velocity.offset_y
+                };
+                *offset += speed * delta_time;
+                loop {
+                    let offset = if horizontal {
+                        &mut velocity.offset_x
+                    } else {
+                        &mut velocity.offset_y
+                    };
+                    let step: i8 = if *offset >= TILE_SIZE {
+                        1
+                    } else if *offset <= -TILE_SIZE {
+                        -1
+                    } else {
+                        break;
+                    };
+                    let target = neighbour(position, horizontal, step);
+                    match obstacle(target, entity, &position_storage, &phase_storage) {
+                        None => {
+                            *offset -= TILE_SIZE * scalar(RawScalar::from(step));
+                            let displaced: Vec<Entity> = position_storage
+                                .on_tile(target)
+                                .map(|(other, _)| other)
+                                .collect();
+                            for other in displaced {
+                                position_storage.move_to(other, position);
+                            }
+                            position_storage.move_to(entity, target);
+                            position = target;
+                        }
+                        Some(blocker) => {
+                            *offset = Meter::new_const(0.0);
+                            impact(
+                                entity,
+                                mass,
+                                &mut velocity,
+                                blocker,
+                                horizontal,
+                                step,
+                                &mass_storage,
+                                &mut velocity_storage,
+                                &mut heat_storage,
+                            );
+                            break;
+                        }
+                    }
+                }
+            }
+
+            // This can only fail if the entity is dead, and dead entities are not joined
+            let _ = velocity_storage.insert(entity, velocity);
+        }
+
+        // Forces only last for a single tick
+        for force in (&mut force_storage).join() {
+            *force = Force::default();
+        }
+    }
+}
+
+/// The next tile from `position` in the given direction.
+fn neighbour(position: Position, horizontal: bool, step: i8) -> Position {
+    let step = r32(f32::from(step));
+    if horizontal {
+        Position {
+            x: position.x + step,
+            y: position.y,
+        }
+    } else {
+        Position {
+            x: position.x,
+            y: position.y + step,
+        }
+    }
+}
+
+/// Let a body that moves in the direction of `step` run into `blocker`, which is `None` for the edge of the grid.
+///
+/// Nothing happens if the body does not move towards the blocker, e.g. because the blocker moves away even faster.
+#[allow(clippy::too_many_arguments)]
+fn impact(
+    entity: Entity,
+    mass: Kilogram,
+    velocity: &mut Velocity,
+    blocker: Option<Entity>,
+    horizontal: bool,
+    step: i8,
+    mass_storage: &ReadStorage<Mass>,
+    velocity_storage: &mut WriteStorage<Velocity>,
+    heat_storage: &mut WriteStorage<Heat>,
+) {
+    let speed = if horizontal {
+        &mut velocity.x
+    } else {
+        &mut velocity.y
+    };
+    let other = blocker.and_then(|other| {
+        let other_velocity = velocity_storage.get(other)?;
+        let other_speed = if horizontal {
+            other_velocity.x
+        } else {
+            other_velocity.y
+        };
+        Some((other_speed, *mass_storage.get(other)?))
+    });
+    let relative_speed = match other {
+        Some((other_speed, _)) => *speed - other_speed,
+        None => *speed,
+    };
+    if relative_speed * scalar(RawScalar::from(step)) <= MeterPerSecond::new_const(0.0) {
+        return;
+    }
+
+    let (common_speed, joules) = collide(*speed, mass, other);
+    *speed = common_speed;
+    if let Some(other_velocity) = blocker.and_then(|other| velocity_storage.get_mut(other)) {
+        if horizontal {
+            other_velocity.x = common_speed;
+        } else {
+            other_velocity.y = common_speed;
+        }
+    }
+    dissipate(entity, blocker, joules, heat_storage);
+}
+
+/// Find what blocks a body from entering `position`.
+///
+/// Returns `None` if the tile only holds fluids, `Some(None)` if it lies outside of the grid, and `Some(Some(entity))` if it holds a solid.
+fn obstacle(
+    position: Position,
+    entity: Entity,
+    position_storage: &WriteStorage<Position>,
+    phase_storage: &ReadStorage<Phase>,
+) -> Option<Option<Entity>> {
+    if !position_storage.is_in_bounds(position) {
+        return Some(None);
+    }
+    position_storage
+        .on_tile(position)
+        .map(|(other, _)| other)
+        .find(|other| {
+            *other != entity
+                && !matches!(phase_storage.get(*other), Some(phase) if phase.is_fluid())
+        })
+        .map(Some)
+}
+
+/// Kinetic friction between a body with horizontal speed `speed` and the body it rests on. The other body is `None` if it can not move, e.g. because it is the bottom of the grid.
+///
+/// Returns the new speed of both bodies, and the kinetic energy that was turned into heat.
+fn slide(
+    speed: MeterPerSecond,
+    mass: Kilogram,
+    other: Option<(MeterPerSecond, Kilogram)>,
+    delta_time: Seconds,
+) -> (MeterPerSecond, Option<MeterPerSecond>, Joule) {
+    let other_speed = other.map(|(other_speed, _)| other_speed);
+    let relative_speed = speed - other_speed.unwrap_or_else(|| MeterPerSecond::new_const(0.0));
+    if relative_speed == MeterPerSecond::new_const(0.0) {
+        return (speed, other_speed, Joule::new_const(0.0));
+    }
+    let direction = scalar(if relative_speed > MeterPerSecond::new_const(0.0) {
+        1.0
+    } else {
+        -1.0
+    });
+    let relative_speed = relative_speed * direction;
+
+    let reduced_mass = self::reduced_mass(mass, other.map(|(_, other_mass)| other_mass));
+    // The friction force is the weight of the body times the friction coefficient. It slows down the relative motion of the two bodies, but never reverses it
+    let mut slowdown = GRAVITY * delta_time * scalar(FRICTION_COEFFICIENT) * (mass / reduced_mass);
+    if slowdown > relative_speed {
+        slowdown = relative_speed;
+    }
+    let remaining_speed = relative_speed - slowdown;
+    let joules = (relative_speed * relative_speed - remaining_speed * remaining_speed)
+        * reduced_mass
+        * scalar(0.5);
+
+    let speed = speed - slowdown * (reduced_mass / mass) * direction;
+    let other_speed = other.map(|(other_speed, other_mass)| {
+        let mut other_speed = other_speed;
+        other_speed += slowdown * (reduced_mass / other_mass) * direction;
+        other_speed
+    });
+    (speed, other_speed, joules)
+}
+
+/// A perfectly inelastic collision between a body and another body, which is `None` if it can not move.
+///
+/// Returns the speed that both bodies have after the collision, and the kinetic energy that was turned into heat.
+fn collide(
+    speed: MeterPerSecond,
+    mass: Kilogram,
+    other: Option<(MeterPerSecond, Kilogram)>,
+) -> (MeterPerSecond, Joule) {
+    let other_speed = other
+        .map(|(other_speed, _)| other_speed)
+        .unwrap_or_else(|| MeterPerSecond::new_const(0.0));
+    let relative_speed = speed - other_speed;
+    let reduced_mass = reduced_mass(mass, other.map(|(_, other_mass)| other_mass));
+    let joules = relative_speed * relative_speed * reduced_mass * scalar(0.5);
+
+    // Momentum is conserved
+    let common_speed = match other {
+        Some((other_speed, other_mass)) => {
+            let mut total_mass = mass;
+            total_mass += other_mass;
+            let mut common_speed = speed * (mass / total_mass);
+            common_speed += other_speed * (other_mass / total_mass);
+            common_speed
+        }
+        None => MeterPerSecond::new_const(0.0),
+    };
+    (common_speed, joules)
+}
+
+/// The reduced mass of two bodies, which is the mass that matters for their relative motion. A body that can not move has an infinite mass.
+fn reduced_mass(mass: Kilogram, other_mass: Option<Kilogram>) -> Kilogram {
+    match other_mass {
+        Some(other_mass) => {
+            let mut total_mass = mass;
+            total_mass += other_mass;
+            mass * (other_mass / total_mass)
+        }
+        None => mass,
+    }
+}
+
+/// Split `joules` of heat evenly over a body and the body it rubbed against or collided with. The edges of the grid are not simulated, so if there is no other body that can hold heat, all of the heat goes to the first one.
+fn dissipate(
+    entity: Entity,
+    other: Option<Entity>,
+    joules: Joule,
+    heat_storage: &mut WriteStorage<Heat>,
+) {
+    let other_heat = other.filter(|other| heat_storage.contains(*other));
+    let share = match other_heat {
+        Some(other) => {
+            let half = joules * scalar(0.5);
+            if let Some(heat) = heat_storage.get_mut(other) {
+                heat.joules += half;
+            }
+            half
+        }
+        None => joules,
+    };
+    if let Some(heat) = heat_storage.get_mut(entity) {
+        heat.joules += share;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid_storage::GridStorage;
+    use crate::units::{Kelvin, SpecificHeatCapacity, ThermalConductivity};
+
+    #[test]
+    fn collisions_conserve_momentum_and_turn_kinetic_energy_into_heat() {
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(4, 2));
+        world.register::<Phase>();
+        world.register::<Mass>();
+        world.register::<Acceleration>();
+        world.register::<Force>();
+        world.register::<Velocity>();
+        world.register::<Heat>();
+        world.insert(DeltaTime::tick());
+        // The bodies float in the top row, so they do not rub against anything
+        let mut body = |x: f32, mass: RawScalar, speed: RawScalar| {
+            let mass = Mass::new_const(mass);
+            world
+                .create_entity()
+                .with(Position {
+                    x: r32(x),
+                    y: r32(0.0),
+                })
+                .with(Phase::Solid)
+                .with(mass)
+                .with(Heat::from_material_specs(
+                    Kelvin::new_const(1.0),
+                    mass,
+                    SpecificHeatCapacity::new_const(1.0),
+                    ThermalConductivity::new_const(1.0),
+                ))
+                .with(Velocity::new(
+                    MeterPerSecond::new_const(speed),
+                    MeterPerSecond::new_const(0.0),
+                ))
+                .build()
+        };
+        let bodies = [body(0.0, 1.0, 2.0), body(1.0, 3.0, 0.0)];
+
+        let state = |world: &World| {
+            let masses = world.read_storage::<Mass>();
+            let velocities = world.read_storage::<Velocity>();
+            let heats = world.read_storage::<Heat>();
+            let mut momentum = 0.0;
+            let mut kinetic_energy = 0.0;
+            let mut heat = 0.0;
+            for body in &bodies {
+                let mass = masses.get(*body).unwrap().raw();
+                let speed = velocities.get(*body).unwrap().x.raw();
+                momentum += mass * speed;
+                kinetic_energy += 0.5 * mass * speed * speed;
+                heat += heats.get(*body).unwrap().joules.raw();
+            }
+            (momentum, kinetic_energy, heat)
+        };
+        let (momentum, kinetic_energy, heat) = state(&world);
+
+        MotionSystem.run_now(&world);
+
+        let (momentum_after, kinetic_energy_after, heat_after) = state(&world);
+        let velocities = world.read_storage::<Velocity>();
+        // The collision is perfectly inelastic, so both bodies move on together
+        assert_eq!(
+            velocities.get(bodies[0]).unwrap().x,
+            velocities.get(bodies[1]).unwrap().x
+        );
+        assert!((momentum_after - momentum).abs() < 1e-5);
+        assert!(kinetic_energy_after < kinetic_energy);
+        assert!(
+            ((heat_after - heat) - (kinetic_energy - kinetic_energy_after)).abs() < 1e-5,
+            "{} J of kinetic energy was lost, but the heat went up by {} J",
+            kinetic_energy - kinetic_energy_after,
+            heat_after - heat
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use super::{SimulationError, SimulationErrors};
+use crate::component::{Composition, Heat, Mass, MaterialColor, MaterialKind, Phase, Volume};
+use crate::material::{Material, MaterialDefinition, MaterialRegistry, Mixture};
+use crate::units::{HeatCapacity, Joule, Kelvin, Kilogram, SpecificEnergy};
+use crate::Position;
+use specs::prelude::*;
+
+type PhaseSystemData<'a> = (
+    ReadExpect<'a, MaterialRegistry>,
+    ReadStorage<'a, MaterialKind>,
+    ReadStorage<'a, Composition>,
+    ReadStorage<'a, Mass>,
+    ReadStorage<'a, Position>,
+    WriteStorage<'a, Phase>,
+    WriteStorage<'a, Heat>,
+    WriteStorage<'a, Volume>,
+    WriteStorage<'a, MaterialColor>,
+    Entities<'a>,
+    Write<'a, SimulationErrors>,
+);
+
+/// Melts, freezes, boils and condenses tiles when their temperature crosses the melting or boiling point of their material.
+///
+/// A tile that reaches its melting or boiling point stays at that temperature, and keeps its phase, until it has taken up the heat of fusion or vaporization of its material. That heat is kept in [Heat::latent]. Freezing and condensing give the heat off again in the same way. When the phase changes, the tile gets the heat capacity, conductivity, density and color of the new phase, and its total energy stays the same.
+pub struct PhaseSystem;
+
+impl<'a> System<'a> for PhaseSystem {
+    type SystemData = PhaseSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            registry,
+            kind_storage,
+            composition_storage,
+            mass_storage,
+            position_storage,
+            mut phase_storage,
+            mut heat_storage,
+            mut volume_storage,
+            mut color_storage,
+            entities,
+            mut errors,
+        ): Self::SystemData,
+    ) {
+        // The tiles where an entity changed its volume, and still fits on its own
+        let mut changed = Vec::new();
+        for (kind, mass, position, phase, heat, volume, color, entity) in (
+            &kind_storage,
+            &mass_storage,
+            &position_storage,
+            &mut phase_storage,
+            &mut heat_storage,
+            &mut volume_storage,
+            &mut color_storage,
+            &entities,
+        )
+            .join()
+        {
+            let temperature = match heat.try_temperature(*mass) {
+                Ok(temperature) => temperature,
+                Err(error) => {
+                    errors.push(SimulationError::Temperature {
+                        position: *position,
+                        heat: *heat,
+                        mass: *mass,
+                        error,
+                    });
+                    continue;
+                }
+            };
+            let definition = some_or_continue!(registry.get(kind.id()));
+            if heat.latent == SpecificEnergy::new_const(0.0)
+                && definition.phase_at(temperature) == *phase
+            {
+                continue;
+            }
+
+            let composition = composition_storage.get(entity);
+            let curve = some_or_continue!(match composition {
+                Some(composition) => {
+                    EnthalpyCurve::mixture(composition, &registry, definition, *mass)
+                }
+                None => Some(EnthalpyCurve::material(definition, *mass)),
+            });
+            let mut enthalpy = curve.enthalpy(*phase, temperature);
+            enthalpy += heat.latent * *mass;
+            let (new_phase, new_temperature) = curve.settle(enthalpy, *phase);
+            let latent: SpecificEnergy =
+                (enthalpy - curve.enthalpy(new_phase, new_temperature)) / *mass;
+            if new_phase == *phase {
+                heat.joules = heat.capacity * *mass * new_temperature;
+                heat.latent = latent;
+                continue;
+            }
+
+            let (density, new_color) = match composition {
+                Some(composition) => {
+                    let mixture =
+                        some_or_continue!(Mixture::new(composition, &registry, new_phase));
+                    *heat = Heat::from_material(mixture, new_temperature, *mass);
+                    (mixture.density(), mixture.material_color())
+                }
+                None => {
+                    let material = some_or_continue!(registry.material(kind.id(), new_phase));
+                    *heat = Heat::from_material(material, new_temperature, *mass);
+                    (material.density(), material.material_color())
+                }
+            };
+            heat.latent = latent;
+            *phase = new_phase;
+            *color = new_color;
+            // Until tiles can spill over, material that expands beyond its tile is compressed into it
+            *volume = match Volume::new(*mass, density, new_phase) {
+                Ok(volume) => {
+                    if !changed.contains(position) {
+                        changed.push(*position);
+                    }
+                    volume
+                }
+                Err(error) => {
+                    errors.push(SimulationError::Overfull {
+                        position: *position,
+                        error,
+                    });
+                    Volume::full()
+                }
+            };
+        }
+
+        for position in changed {
+            if let Err(error) =
+                Volume::check_tile(position, &position_storage, &phase_storage, &volume_storage)
+            {
+                errors.push(SimulationError::Overfull { position, error });
+            }
+        }
+    }
+}
+
+/// The enthalpy of a tile, from 0 for a solid at 0 K, through all of the phases of its material.
+///
+/// In each phase, the enthalpy grows with the temperature times the heat capacity of that phase. At the melting and boiling point, it grows by the latent heat while the temperature stays the same.
+struct EnthalpyCurve {
+    solid: HeatCapacity,
+    liquid: HeatCapacity,
+    gas: HeatCapacity,
+    /// The melting point and heat of fusion, if the material melts
+    melting: Option<(Kelvin, Joule)>,
+    /// The boiling point and heat of vaporization, if the material boils
+    boiling: Option<(Kelvin, Joule)>,
+}
+
+impl EnthalpyCurve {
+    fn material(definition: &MaterialDefinition, mass: Kilogram) -> Self {
+        let no_latent_heat = SpecificEnergy::new_const(0.0);
+        let heat_of_fusion = definition.heat_of_fusion.unwrap_or(no_latent_heat);
+        let heat_of_vaporization = definition.heat_of_vaporization.unwrap_or(no_latent_heat);
+        EnthalpyCurve {
+            solid: definition.properties(Phase::Solid).specific_heat_capacity * mass,
+            liquid: definition.properties(Phase::Liquid).specific_heat_capacity * mass,
+            gas: definition.properties(Phase::Gas).specific_heat_capacity * mass,
+            melting: definition
+                .melting_point
+                .map(|temperature| (temperature, heat_of_fusion * mass)),
+            boiling: definition
+                .boiling_point
+                .map(|temperature| (temperature, heat_of_vaporization * mass)),
+        }
+    }
+
+    /// The curve of a mixture, which changes phase at the temperatures of its `dominant` material. Returns `None` if a part is not in `registry`.
+    fn mixture(
+        composition: &Composition,
+        registry: &MaterialRegistry,
+        dominant: &MaterialDefinition,
+        mass: Kilogram,
+    ) -> Option<Self> {
+        let heat_of_fusion = composition.heat_of_fusion(registry)? * mass;
+        let heat_of_vaporization = composition.heat_of_vaporization(registry)? * mass;
+        Some(EnthalpyCurve {
+            solid: composition.specific_heat_capacity(registry, Phase::Solid)? * mass,
+            liquid: composition.specific_heat_capacity(registry, Phase::Liquid)? * mass,
+            gas: composition.specific_heat_capacity(registry, Phase::Gas)? * mass,
+            melting: dominant
+                .melting_point
+                .map(|temperature| (temperature, heat_of_fusion)),
+            boiling: dominant
+                .boiling_point
+                .map(|temperature| (temperature, heat_of_vaporization)),
+        })
+    }
+
+    fn capacity(&self, phase: Phase) -> HeatCapacity {
+        match phase {
+            Phase::Solid => self.solid,
+            Phase::Liquid => self.liquid,
+            Phase::Gas => self.gas,
+        }
+    }
+
+    /// The phases above solid that the material can change into, each with the transition into it
+    fn transitions(&self) -> [(Option<(Kelvin, Joule)>, Phase); 2] {
+        [(self.melting, Phase::Liquid), (self.boiling, Phase::Gas)]
+    }
+
+    /// The enthalpy of `phase` at `temperature`, without the latent heat of a phase change in progress
+    fn enthalpy(&self, phase: Phase, temperature: Kelvin) -> Joule {
+        let mut enthalpy: Joule = self.capacity(phase) * temperature;
+        enthalpy += self.offset(phase);
+        enthalpy
+    }
+
+    /// The enthalpy of `phase` at 0 K, if it did not change phase on the way down
+    fn offset(&self, phase: Phase) -> Joule {
+        let mut below = Phase::Solid;
+        let mut offset = Joule::new_const(0.0);
+        for (transition, above) in self.transitions().iter() {
+            if below == phase {
+                break;
+            }
+            let (temperature, latent) = some_or_continue!(*transition);
+            // Apart from the latent heat, both phases have the same enthalpy at the transition
+            offset += (self.capacity(below) - self.capacity(*above)) * temperature;
+            offset += latent;
+            below = *above;
+        }
+        offset
+    }
+
+    /// The phase and temperature of a tile in `phase` that has `enthalpy`.
+    ///
+    /// During a phase change the tile keeps its phase, until all of the latent heat has been taken up or given off.
+    fn settle(&self, enthalpy: Joule, phase: Phase) -> (Phase, Kelvin) {
+        let mut below = Phase::Solid;
+        for (transition, above) in self.transitions().iter() {
+            let (temperature, latent) = some_or_continue!(*transition);
+            let start = self.enthalpy(below, temperature);
+            if enthalpy < start {
+                break;
+            }
+            let mut end = start;
+            end += latent;
+            if enthalpy <= end {
+                let phase = if phase >= *above { *above } else { below };
+                return (phase, temperature);
+            }
+            below = *above;
+        }
+        let temperature: Kelvin = (enthalpy - self.offset(below)) / self.capacity(below);
+        (below, temperature)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::grid_storage::GridStorage;
+    use crate::units::{scalar, RawScalar};
+    use noisy_float::types::r32;
+
+    #[test]
+    fn melting_takes_up_the_heat_of_fusion() {
+        let registry =
+            MaterialRegistry::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/materials.ron"))
+                .unwrap();
+        let water = registry.require("water").unwrap();
+        let definition = registry.get(water).unwrap();
+        let solid = definition.properties(Phase::Solid).specific_heat_capacity;
+        let liquid = definition.properties(Phase::Liquid).specific_heat_capacity;
+        let heat_of_fusion = definition.heat_of_fusion.unwrap();
+
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(1, 1));
+        world.register::<MaterialKind>();
+        world.register::<Composition>();
+        world.register::<Mass>();
+        world.register::<Phase>();
+        world.register::<Heat>();
+        world.register::<Volume>();
+        world.register::<MaterialColor>();
+        world.insert(SimulationErrors::default());
+        let mass = Mass::new_const(1.0);
+        let position = Position {
+            x: r32(0.0),
+            y: r32(0.0),
+        };
+        let entity = registry
+            .build_entity(
+                water,
+                world.create_entity(),
+                Kelvin::new_const(263.15),
+                mass,
+                position,
+            )
+            .unwrap();
+        world.insert(registry);
+
+        // Enough heat to warm the ice to its melting point, melt it, and warm the water by as much again
+        let mut total: Joule = solid * mass * Kelvin::new_const(10.0);
+        total += heat_of_fusion * mass;
+        total += liquid * mass * Kelvin::new_const(10.0);
+        let steps = 10;
+        let step = total * scalar(1.0 / steps as RawScalar);
+        let state = |world: &World| {
+            let heat = *world.read_storage::<Heat>().get(entity).unwrap();
+            let phase = *world.read_storage::<Phase>().get(entity).unwrap();
+            (phase, heat.temperature(mass).raw())
+        };
+        let add = |world: &mut World, heat: Joule| {
+            world
+                .write_storage::<Heat>()
+                .get_mut(entity)
+                .unwrap()
+                .joules += heat;
+            PhaseSystem.run_now(world);
+        };
+
+        // The ice stops at its melting point until it has taken up the heat of fusion
+        add(&mut world, step);
+        let (phase, temperature) = state(&world);
+        assert_eq!(phase, Phase::Solid);
+        assert!((temperature - 273.15).abs() < 1e-3, "{}", temperature);
+        for _ in 1..steps {
+            add(&mut world, step);
+        }
+        let (phase, temperature) = state(&world);
+        assert_eq!(phase, Phase::Liquid);
+        assert!((temperature - 283.15).abs() < 0.1, "{}", temperature);
+
+        // Freezing gives the same heat off again
+        for _ in 0..steps {
+            add(&mut world, -step);
+        }
+        let (phase, temperature) = state(&world);
+        assert_eq!(phase, Phase::Solid);
+        assert!((temperature - 263.15).abs() < 0.1, "{}", temperature);
+        assert_eq!(
+            world.write_resource::<SimulationErrors>().drain().count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,392 @@
+use crate::component::{Accumulator, Consumer, DeltaTime, Generator, Heat, PowerPole};
+use crate::units::{scalar, Joule, Scalar, Watt};
+use crate::Position;
+use specs::prelude::*;
+
+type PowerGridSystemData<'a> = (
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, PowerPole>,
+    WriteStorage<'a, Generator>,
+    WriteStorage<'a, Consumer>,
+    WriteStorage<'a, Accumulator>,
+    WriteStorage<'a, Heat>,
+    Entities<'a>,
+);
+
+/// Balances the supply and demand of electrical power in every network of [PowerPole]s.
+///
+/// Generators only produce what their network uses. Power to spare charges the accumulators, and when the generators can not keep up, the accumulators make up the difference. If that is still not enough, every [Consumer] on the network gets the same fraction of its demand.
+///
+/// The losses of a [Generator] are added to its [Heat], so power plants need to get rid of their waste heat.
+pub struct PowerGridSystem;
+
+impl<'a> System<'a> for PowerGridSystem {
+    type SystemData = PowerGridSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            delta_time,
+            position_storage,
+            pole_storage,
+            mut generator_storage,
+            mut consumer_storage,
+            mut accumulator_storage,
+            mut heat_storage,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let delta_time = delta_time.as_si();
+        let poles = Poles::connect(
+            (&position_storage, &pole_storage)
+                .join()
+                .map(|(position, pole)| (*position, *pole))
+                .collect(),
+        );
+        let mut networks = vec![Network::default(); poles.network_count];
+
+        for (position, generator) in (&position_storage, &generator_storage).join() {
+            let network = some_or_continue!(poles.network_at(*position));
+            networks[network].capacity += generator.capacity;
+        }
+        for (position, consumer) in (&position_storage, &consumer_storage).join() {
+            let network = some_or_continue!(poles.network_at(*position));
+            networks[network].demand += consumer.demand;
+        }
+        for (position, accumulator) in (&position_storage, &accumulator_storage).join() {
+            let network = some_or_continue!(poles.network_at(*position));
+            let (charge, discharge) = accumulator.limits(delta_time);
+            networks[network].charge_limit += charge;
+            networks[network].discharge_limit += discharge;
+        }
+        let balances: Vec<Balance> = networks.iter().map(Network::balance).collect();
+
+        for (entity, position, generator) in
+            (&entities, &position_storage, &mut generator_storage).join()
+        {
+            let load = poles
+                .network_at(*position)
+                .map_or(scalar(0.0), |network| balances[network].load);
+            let output = generator.capacity * load;
+            generator.set_output(output);
+            if generator.efficiency > 0.0 {
+                let losses = output * scalar((1.0 - generator.efficiency) / generator.efficiency);
+                if let Some(heat) = heat_storage.get_mut(entity) {
+                    heat.joules += losses * delta_time;
+                }
+            }
+        }
+        for (position, consumer) in (&position_storage, &mut consumer_storage).join() {
+            let satisfaction = poles
+                .network_at(*position)
+                .map_or(scalar(0.0), |network| balances[network].satisfaction);
+            consumer.set_satisfaction(satisfaction);
+        }
+        for (position, accumulator) in (&position_storage, &mut accumulator_storage).join() {
+            let network = some_or_continue!(poles.network_at(*position));
+            let balance = &balances[network];
+            let (charge, discharge) = accumulator.limits(delta_time);
+            accumulator.charge += charge * balance.charging * delta_time;
+            accumulator.charge -= discharge * balance.discharging * delta_time;
+            accumulator.charge = accumulator
+                .charge
+                .clamp(Joule::new_const(0.0), accumulator.capacity);
+        }
+    }
+}
+
+/// The poles on the map, and which network each of them belongs to.
+struct Poles {
+    poles: Vec<(Position, PowerPole)>,
+    networks: Vec<usize>,
+    network_count: usize,
+}
+
+impl Poles {
+    fn connect(poles: Vec<(Position, PowerPole)>) -> Self {
+        let mut networks: Vec<Option<usize>> = vec![None; poles.len()];
+        let mut network_count = 0;
+        for start in 0..poles.len() {
+            if networks[start].is_some() {
+                continue;
+            }
+            networks[start] = Some(network_count);
+            let mut queue = vec![start];
+            while let Some(index) = queue.pop() {
+                for other in 0..poles.len() {
+                    if networks[other].is_none() && connected(poles[index], poles[other]) {
+                        networks[other] = Some(network_count);
+                        queue.push(other);
+                    }
+                }
+            }
+            network_count += 1;
+        }
+        Poles {
+            poles,
+            // Every pole was given a network above
+            networks: networks.into_iter().flatten().collect(),
+            network_count,
+        }
+    }
+
+    /// The network that powers the building at `position`. If several networks reach it, the building is connected to the first one
+    fn network_at(&self, position: Position) -> Option<usize> {
+        self.poles
+            .iter()
+            .zip(&self.networks)
+            .find(|((pole_position, pole), _)| {
+                let range = f32::from(pole.supply_range);
+                (position.x - pole_position.x).raw().abs() <= range
+                    && (position.y - pole_position.y).raw().abs() <= range
+            })
+            .map(|(_, network)| *network)
+    }
+}
+
+fn connected((a, a_pole): (Position, PowerPole), (b, b_pole): (Position, PowerPole)) -> bool {
+    let reach = f32::from(a_pole.wire_reach.min(b_pole.wire_reach));
+    let (dx, dy) = ((a.x - b.x).raw(), (a.y - b.y).raw());
+    dx * dx + dy * dy <= reach * reach
+}
+
+/// The total supply and demand of a network.
+#[derive(Clone)]
+struct Network {
+    capacity: Watt,
+    demand: Watt,
+    charge_limit: Watt,
+    discharge_limit: Watt,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Network {
+            capacity: Watt::new_const(0.0),
+            demand: Watt::new_const(0.0),
+            charge_limit: Watt::new_const(0.0),
+            discharge_limit: Watt::new_const(0.0),
+        }
+    }
+}
+
+/// How hard every building on a network runs during this tick.
+struct Balance {
+    /// The fraction of their capacity that the generators produce
+    load: Scalar,
+    /// The fraction of their demand that the consumers get
+    satisfaction: Scalar,
+    /// The fraction of their charge limit that the accumulators are charged with
+    charging: Scalar,
+    /// The fraction of their discharge limit that the accumulators give back
+    discharging: Scalar,
+}
+
+impl Network {
+    fn balance(&self) -> Balance {
+        let zero = Watt::new_const(0.0);
+        let fraction = |part: Watt, whole: Watt| {
+            if whole > zero {
+                (part / whole).clamp(scalar(0.0), scalar(1.0))
+            } else {
+                scalar(0.0)
+            }
+        };
+        if self.capacity >= self.demand {
+            let charge = (self.capacity - self.demand).min(self.charge_limit);
+            let mut production = self.demand;
+            production += charge;
+            Balance {
+                load: fraction(production, self.capacity),
+                satisfaction: scalar(1.0),
+                charging: fraction(charge, self.charge_limit),
+                discharging: scalar(0.0),
+            }
+        } else {
+            let discharge = (self.demand - self.capacity).min(self.discharge_limit);
+            let mut supply = self.capacity;
+            supply += discharge;
+            Balance {
+                load: scalar(1.0),
+                satisfaction: fraction(supply, self.demand),
+                charging: scalar(0.0),
+                discharging: fraction(discharge, self.discharge_limit),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid_storage::GridStorage;
+    use crate::units::{Kelvin, Kilogram, RawScalar, SpecificHeatCapacity, ThermalConductivity};
+    use noisy_float::types::r32;
+
+    fn assert_close(value: RawScalar, expected: RawScalar) {
+        assert!(
+            (value - expected).abs() < 1e-4,
+            "expected {}, got {}",
+            expected,
+            value
+        );
+    }
+
+    fn network(
+        capacity: RawScalar,
+        demand: RawScalar,
+        charge_limit: RawScalar,
+        discharge_limit: RawScalar,
+    ) -> Network {
+        Network {
+            capacity: Watt::new_const(capacity),
+            demand: Watt::new_const(demand),
+            charge_limit: Watt::new_const(charge_limit),
+            discharge_limit: Watt::new_const(discharge_limit),
+        }
+    }
+
+    #[test]
+    fn spare_power_charges_the_accumulators() {
+        let balance = network(100.0, 60.0, 20.0, 20.0).balance();
+        assert_close(balance.load.raw(), 0.8);
+        assert_close(balance.satisfaction.raw(), 1.0);
+        assert_close(balance.charging.raw(), 1.0);
+        assert_close(balance.discharging.raw(), 0.0);
+
+        let balance = network(100.0, 60.0, 80.0, 20.0).balance();
+        assert_close(balance.load.raw(), 1.0);
+        assert_close(balance.charging.raw(), 0.5);
+    }
+
+    #[test]
+    fn accumulators_make_up_a_shortage() {
+        let balance = network(100.0, 110.0, 20.0, 20.0).balance();
+        assert_close(balance.load.raw(), 1.0);
+        assert_close(balance.satisfaction.raw(), 1.0);
+        assert_close(balance.charging.raw(), 0.0);
+        assert_close(balance.discharging.raw(), 0.5);
+    }
+
+    #[test]
+    fn consumers_share_what_is_left() {
+        let balance = network(100.0, 200.0, 20.0, 20.0).balance();
+        assert_close(balance.load.raw(), 1.0);
+        assert_close(balance.satisfaction.raw(), 0.6);
+        assert_close(balance.discharging.raw(), 1.0);
+
+        let balance = network(0.0, 0.0, 0.0, 0.0).balance();
+        assert_close(balance.load.raw(), 0.0);
+        assert_close(balance.satisfaction.raw(), 1.0);
+    }
+
+    #[test]
+    fn power_grid() {
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(4, 1));
+        world.register::<PowerPole>();
+        world.register::<Generator>();
+        world.register::<Consumer>();
+        world.register::<Accumulator>();
+        world.register::<Heat>();
+        world.insert(DeltaTime::tick());
+        let at = |x: f32| Position {
+            x: r32(x),
+            y: r32(0.0),
+        };
+        world
+            .create_entity()
+            .with(at(1.0))
+            .with(PowerPole {
+                wire_reach: 5,
+                supply_range: 1,
+            })
+            .build();
+        let mass = Kilogram::new_const(1.0);
+        let generator = world
+            .create_entity()
+            .with(at(0.0))
+            .with(Generator::new(Watt::new_const(100.0), 0.5))
+            .with(Heat::from_material_specs(
+                Kelvin::new_const(300.0),
+                mass,
+                SpecificHeatCapacity::new_const(1000.0),
+                ThermalConductivity::new_const(1.0),
+            ))
+            .build();
+        let consumer = world
+            .create_entity()
+            .with(at(1.0))
+            .with(Consumer::new(Watt::new_const(60.0)))
+            .build();
+        let accumulator = world
+            .create_entity()
+            .with(at(2.0))
+            .with(Accumulator::new(
+                Joule::new_const(1000.0),
+                Watt::new_const(20.0),
+            ))
+            .build();
+        // Out of the supply range of the pole
+        let unconnected = world
+            .create_entity()
+            .with(at(3.0))
+            .with(Consumer::new(Watt::new_const(10.0)))
+            .build();
+        let dt = DeltaTime::tick().as_si().raw();
+        let charge = |world: &World| {
+            world
+                .read_storage::<Accumulator>()
+                .get(accumulator)
+                .unwrap()
+                .charge
+                .raw()
+        };
+        let satisfaction = |world: &World, entity| {
+            world
+                .read_storage::<Consumer>()
+                .get(entity)
+                .unwrap()
+                .satisfaction()
+                .raw()
+        };
+        let set_demand = |world: &World, demand: RawScalar| {
+            world
+                .write_storage::<Consumer>()
+                .get_mut(consumer)
+                .unwrap()
+                .demand = Watt::new_const(demand);
+        };
+
+        // The generator makes enough for the consumer, and charges the accumulator as fast as it can
+        let joules = world.read_storage::<Heat>().get(generator).unwrap().joules;
+        PowerGridSystem.run_now(&world);
+        let output = world
+            .read_storage::<Generator>()
+            .get(generator)
+            .unwrap()
+            .output();
+        assert_close(output.raw(), 80.0);
+        assert_close(satisfaction(&world, consumer), 1.0);
+        assert_close(satisfaction(&world, unconnected), 0.0);
+        assert_close(charge(&world), 20.0 * dt);
+        // At an efficiency of 0.5, the generator loses as much heat as it produces power
+        let heat = world.read_storage::<Heat>().get(generator).unwrap().joules - joules;
+        assert_close(heat.raw(), 80.0 * dt);
+
+        // The accumulator makes up the difference
+        set_demand(&world, 110.0);
+        PowerGridSystem.run_now(&world);
+        assert_close(satisfaction(&world, consumer), 1.0);
+        assert_close(charge(&world), 10.0 * dt);
+
+        // Once it is empty, the consumer is throttled
+        set_demand(&world, 200.0);
+        PowerGridSystem.run_now(&world);
+        assert_close(satisfaction(&world, consumer), 110.0 / 200.0);
+        assert_close(charge(&world), 0.0);
+        PowerGridSystem.run_now(&world);
+        assert_close(satisfaction(&world, consumer), 0.5);
+    }
+}
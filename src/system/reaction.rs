@@ -0,0 +1,698 @@
+use super::{FireEvent, FireEvents, SimulationError, SimulationErrors};
+use crate::component::{
+    Burning, Composition, DeltaTime, Heat, Mass, MaterialKind, OverfullTile, Phase, Volume,
+};
+use crate::material::{Material, MaterialId, MaterialRegistry, Reaction, ReactionRegistry};
+use crate::units::{scalar, Joule, Kelvin, Kilogram, RawScalar, Scalar};
+use crate::{GridStorageExt, Position};
+use specs::prelude::*;
+
+/// Tiles with less mass than this are burned up completely, so we don't keep specks of fuel around forever.
+const DEPLETED_MASS: Kilogram = Kilogram::new_const(0.001);
+/// A reaction runs at its `rate` at the ignition temperature, and faster when it is hotter, up to this many times its `rate`
+const MAX_RATE_FACTOR: RawScalar = 4.0;
+
+type ReactionSystemData<'a> = (
+    ReadExpect<'a, MaterialRegistry>,
+    ReadExpect<'a, ReactionRegistry>,
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, MaterialKind>,
+    ReadStorage<'a, Composition>,
+    ReadStorage<'a, Phase>,
+    ReadStorage<'a, Position>,
+    WriteStorage<'a, Mass>,
+    WriteStorage<'a, Heat>,
+    WriteStorage<'a, Volume>,
+    WriteStorage<'a, Burning>,
+    Entities<'a>,
+    Read<'a, LazyUpdate>,
+    Write<'a, SimulationErrors>,
+    Write<'a, FireEvents>,
+);
+
+/// Runs the reactions in the [ReactionRegistry], e.g. burning coal with oxygen.
+///
+/// A tile of fuel that is hotter than the ignition temperature of a reaction consumes the other reactants from its own and neighbouring tiles. The released heat goes into the fuel, and the products are added to the tile of the fuel.
+///
+/// Fuel in an exothermic reaction is marked as [Burning], and a [FireEvent] is emitted when a fire starts or stops.
+///
+/// Only tiles made of a single material take part in reactions, mixtures are ignored.
+pub struct ReactionSystem;
+
+impl<'a> System<'a> for ReactionSystem {
+    type SystemData = ReactionSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            materials,
+            reactions,
+            delta_time,
+            kind_storage,
+            composition_storage,
+            phase_storage,
+            position_storage,
+            mut mass_storage,
+            mut heat_storage,
+            mut volume_storage,
+            mut burning_storage,
+            entities,
+            lazy,
+            mut errors,
+            mut fire_events,
+        ): Self::SystemData,
+    ) {
+        let mut changes = ReactionChanges::default();
+
+        for (kind, mass, heat, position, entity, _) in (
+            &kind_storage,
+            &mass_storage,
+            &heat_storage,
+            &position_storage,
+            &entities,
+            !&composition_storage,
+        )
+            .join()
+        {
+            let temperature = match heat.try_temperature(*mass) {
+                Ok(temperature) => temperature,
+                Err(error) => {
+                    errors.push(SimulationError::Temperature {
+                        position: *position,
+                        heat: *heat,
+                        mass: *mass,
+                        error,
+                    });
+                    continue;
+                }
+            };
+            let reaction = some_or_continue!(reactions
+                .with_fuel(kind.id())
+                .find(|reaction| temperature >= reaction.ignition_temperature));
+
+            // Find the other reactants around this tile
+            let mut partners = Vec::with_capacity(reaction.reactants.len());
+            let mut exposure = scalar(1.0);
+            for (material, ratio) in &reaction.reactants {
+                let candidates: Vec<(Entity, Position, Kilogram)> = position_storage
+                    .on_tile(*position)
+                    .chain(position_storage.neighbours(*position))
+                    .filter(|(other, _)| *other != entity)
+                    .filter(|(other, _)| {
+                        kind_storage.get(*other).map(|kind| kind.id()) == Some(*material)
+                            && !composition_storage.contains(*other)
+                    })
+                    .filter_map(|(other, other_position)| {
+                        let left = *mass_storage.get(other)? - changes.consumed(other);
+                        if left > DEPLETED_MASS {
+                            Some((other, other_position, left))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                exposure = exposure.min(exposure_to(*position, &candidates));
+                partners.push((candidates, *ratio));
+            }
+
+            // Hotter fires burn faster, and a fire that is surrounded by the other reactants burns faster than one that only touches them on one side
+            let rate_factor =
+                (temperature / reaction.ignition_temperature).min(scalar(MAX_RATE_FACTOR));
+            let mut extent = reaction.rate * delta_time.as_si() * (rate_factor * exposure);
+            let fuel_left = *mass - changes.consumed(entity);
+            if fuel_left < extent {
+                extent = fuel_left;
+            }
+            for (candidates, ratio) in &partners {
+                let mut available = Kilogram::new_const(0.0);
+                for (_, _, left) in candidates {
+                    available += *left;
+                }
+                let limit = available * scalar(1.0 / ratio);
+                if limit < extent {
+                    extent = limit;
+                }
+            }
+            if extent <= Kilogram::new_const(0.0) {
+                continue;
+            }
+
+            changes.consume(entity, extent);
+            for (candidates, ratio) in partners {
+                let mut needed = extent * scalar(ratio);
+                for (other, _, left) in candidates {
+                    if needed <= Kilogram::new_const(0.0) {
+                        break;
+                    }
+                    let taken = if left < needed { left } else { needed };
+                    changes.consume(other, taken);
+                    needed -= taken;
+                }
+            }
+
+            let energy = reaction.energy * extent;
+            if energy > Joule::new_const(0.0) {
+                changes.fires.push((entity, *position, kind.id()));
+            }
+            let fuel_depleted = *mass - changes.consumed(entity) <= DEPLETED_MASS;
+            if fuel_depleted {
+                // There is no fuel left to heat, so the products carry the heat away
+                changes.add_products(reaction, extent, *position, temperature, energy, &materials);
+            } else {
+                changes.released.push((entity, energy));
+                changes.add_products(
+                    reaction,
+                    extent,
+                    *position,
+                    temperature,
+                    Joule::new_const(0.0),
+                    &materials,
+                );
+            }
+        }
+
+        let mut deleted = Vec::new();
+        for (entity, consumed) in changes.consumed {
+            let mass = some_or_continue!(mass_storage.get_mut(entity));
+            let new_mass = *mass - consumed;
+            if new_mass <= DEPLETED_MASS {
+                // If deleting fails, the entity was already deleted by another system
+                let _ = entities.delete(entity);
+                deleted.push(entity);
+                continue;
+            }
+            if let Some(heat) = heat_storage.get_mut(entity) {
+                if let Err(error) = heat.try_set_mass(*mass, new_mass) {
+                    if let Some(position) = position_storage.get(entity) {
+                        errors.push(SimulationError::Temperature {
+                            position: *position,
+                            heat: *heat,
+                            mass: *mass,
+                            error,
+                        });
+                    }
+                }
+            }
+            *mass = new_mass;
+            // The reactants only lose mass, so they still fit in their tile
+            let _ = update_volume(
+                entity,
+                &materials,
+                &kind_storage,
+                &phase_storage,
+                &mass_storage,
+                &mut volume_storage,
+            );
+        }
+
+        for (entity, energy) in changes.released {
+            if deleted.contains(&entity) {
+                continue;
+            }
+            if let Some(heat) = heat_storage.get_mut(entity) {
+                heat.joules += energy;
+            }
+        }
+
+        for product in changes.products {
+            // Merge the product into a tile of the same material, so we don't pile up a new entity every tick
+            let existing = position_storage
+                .on_tile(product.position)
+                .map(|(entity, _)| entity)
+                .find(|entity| {
+                    !deleted.contains(entity)
+                        && kind_storage.get(*entity).map(|kind| kind.id()) == Some(product.material)
+                        && !composition_storage.contains(*entity)
+                        && mass_storage.contains(*entity)
+                        && heat_storage.contains(*entity)
+                });
+            if let Some(entity) = existing {
+                // The existing tile may be in another phase than the product, so its heat is mixed in by temperature
+                if let Some(heat) = heat_storage.get_mut(entity) {
+                    let merged = product
+                        .heat
+                        .try_temperature(product.mass)
+                        .and_then(|temperature| heat.try_add_mass(product.mass, temperature));
+                    if let Err(error) = merged {
+                        errors.push(SimulationError::Temperature {
+                            position: product.position,
+                            heat: product.heat,
+                            mass: product.mass,
+                            error,
+                        });
+                        continue;
+                    }
+                }
+                if let Some(mass) = mass_storage.get_mut(entity) {
+                    *mass += product.mass;
+                }
+                let fits = update_volume(
+                    entity,
+                    &materials,
+                    &kind_storage,
+                    &phase_storage,
+                    &mass_storage,
+                    &mut volume_storage,
+                )
+                .and_then(|_| {
+                    Volume::check_tile(
+                        product.position,
+                        &position_storage,
+                        &phase_storage,
+                        &volume_storage,
+                    )
+                });
+                if let Err(error) = fits {
+                    errors.push(SimulationError::Overfull {
+                        position: product.position,
+                        error,
+                    });
+                }
+                continue;
+            }
+
+            let temperature = match product.heat.try_temperature(product.mass) {
+                Ok(temperature) => temperature,
+                Err(error) => {
+                    errors.push(SimulationError::Temperature {
+                        position: product.position,
+                        heat: product.heat,
+                        mass: product.mass,
+                        error,
+                    });
+                    continue;
+                }
+            };
+            if let Err(error) = materials.build_entity(
+                product.material,
+                lazy.create_entity(&entities),
+                temperature,
+                product.mass,
+                product.position,
+            ) {
+                errors.push(SimulationError::Reaction {
+                    position: product.position,
+                    material: MaterialKind(product.material)
+                        .name(&materials)
+                        .unwrap_or("an unknown material")
+                        .to_owned(),
+                    error,
+                });
+            }
+        }
+
+        let fires = changes.fires;
+        for (entity, position, material) in &fires {
+            let burned_out = deleted.contains(entity);
+            if !burning_storage.contains(*entity) {
+                fire_events.push(FireEvent::Started {
+                    position: *position,
+                    material: *material,
+                });
+                if !burned_out {
+                    // This can only fail if the entity is dead, which we just checked
+                    let _ = burning_storage.insert(*entity, Burning);
+                }
+            }
+            if burned_out {
+                fire_events.push(FireEvent::Stopped {
+                    position: *position,
+                    material: *material,
+                    burned_out,
+                });
+            }
+        }
+        let extinguished: Vec<_> = (
+            &entities,
+            &burning_storage,
+            &position_storage,
+            &kind_storage,
+        )
+            .join()
+            .filter(|(entity, ..)| !fires.iter().any(|(burning, ..)| burning == entity))
+            .map(|(entity, _, position, kind)| (entity, *position, kind.id()))
+            .collect();
+        for (entity, position, material) in extinguished {
+            burning_storage.remove(entity);
+            fire_events.push(FireEvent::Stopped {
+                position,
+                material,
+                burned_out: false,
+            });
+        }
+    }
+}
+
+/// How much of a tile is exposed to a reactant, between 0 and 1. This is the fraction of the four sides of the tile that touch the reactant, where a reactant on the tile itself touches every side.
+fn exposure_to(position: Position, candidates: &[(Entity, Position, Kilogram)]) -> Scalar {
+    if candidates.iter().any(|(_, other, _)| *other == position) {
+        return scalar(1.0);
+    }
+    let mut tiles: Vec<Position> = Vec::with_capacity(candidates.len());
+    for (_, other, _) in candidates {
+        if !tiles.contains(other) {
+            tiles.push(*other);
+        }
+    }
+    scalar((tiles.len() as RawScalar / 4.0).min(1.0))
+}
+
+/// Recalculate the [Volume] of an entity after its [Mass] changed.
+///
+/// Until tiles can spill over, material that grows beyond its tile is compressed into it, and the error is returned so it can be reported.
+fn update_volume(
+    entity: Entity,
+    materials: &MaterialRegistry,
+    kind_storage: &ReadStorage<MaterialKind>,
+    phase_storage: &ReadStorage<Phase>,
+    mass_storage: &WriteStorage<Mass>,
+    volume_storage: &mut WriteStorage<Volume>,
+) -> Result<(), OverfullTile> {
+    let (kind, mass, volume) = match (
+        kind_storage.get(entity),
+        mass_storage.get(entity),
+        volume_storage.get_mut(entity),
+    ) {
+        (Some(kind), Some(mass), Some(volume)) => (kind, mass, volume),
+        _ => return Ok(()),
+    };
+    let phase = phase_storage.get(entity).copied().unwrap_or(Phase::Solid);
+    let density = match materials.material(kind.id(), phase) {
+        Some(material) => material.density(),
+        None => return Ok(()),
+    };
+    match Volume::new(*mass, density, phase) {
+        Ok(new_volume) => {
+            *volume = new_volume;
+            Ok(())
+        }
+        Err(error) => {
+            *volume = Volume::full();
+            Err(error)
+        }
+    }
+}
+
+struct Product {
+    position: Position,
+    material: MaterialId,
+    mass: Kilogram,
+    /// The heat of the product in its own phase, which includes its share of the released energy
+    heat: Heat,
+}
+
+/// Everything the reactions of this tick change. This is collected first and applied afterwards, so every reaction sees the world as it was at the start of the tick.
+#[derive(Default)]
+struct ReactionChanges {
+    consumed: Vec<(Entity, Kilogram)>,
+    released: Vec<(Entity, Joule)>,
+    products: Vec<Product>,
+    /// The fuel of every exothermic reaction that ran this tick
+    fires: Vec<(Entity, Position, MaterialId)>,
+}
+
+impl ReactionChanges {
+    fn consumed(&self, entity: Entity) -> Kilogram {
+        self.consumed
+            .iter()
+            .find(|(other, _)| *other == entity)
+            .map(|(_, mass)| *mass)
+            .unwrap_or_else(|| Kilogram::new_const(0.0))
+    }
+
+    fn consume(&mut self, entity: Entity, mass: Kilogram) {
+        match self.consumed.iter_mut().find(|(other, _)| *other == entity) {
+            Some((_, consumed)) => *consumed += mass,
+            None => self.consumed.push((entity, mass)),
+        }
+    }
+
+    /// Add the products of `extent` kg of fuel reacting at `temperature`, and divide `energy` over them by mass.
+    fn add_products(
+        &mut self,
+        reaction: &Reaction,
+        extent: Kilogram,
+        position: Position,
+        temperature: Kelvin,
+        energy: Joule,
+        materials: &MaterialRegistry,
+    ) {
+        let total_ratio: RawScalar = reaction.products.iter().map(|(_, ratio)| ratio).sum();
+        for (material, ratio) in &reaction.products {
+            let mass = extent * scalar(*ratio);
+            let phase = some_or_continue!(materials.get(*material)).phase_at(temperature);
+            let material_in_phase = some_or_continue!(materials.material(*material, phase));
+            let share = energy * scalar(ratio / total_ratio);
+
+            let existing = self
+                .products
+                .iter_mut()
+                .find(|product| product.material == *material && product.position == position);
+            match existing {
+                // Another fuel on this tile may have reacted at a temperature where the product has another phase, so it is mixed in by temperature
+                Some(product) => {
+                    product.heat.joules += product.heat.capacity * mass * temperature;
+                    product.heat.joules += share;
+                    product.mass += mass;
+                }
+                None => {
+                    let mut heat = Heat::from_material(material_in_phase, temperature, mass);
+                    heat.joules += share;
+                    self.products.push(Product {
+                        position,
+                        material: *material,
+                        mass,
+                        heat,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::component::{MaterialColor, StateChangeRequired};
+    use crate::grid_storage::GridStorage;
+    use crate::system::HeatSystem;
+    use crate::units::SpecificEnergy;
+    use noisy_float::types::r32;
+
+    fn materials() -> MaterialRegistry {
+        MaterialRegistry::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/materials.ron"))
+            .unwrap()
+    }
+
+    /// A world with every storage the [ReactionSystem] needs. Insert the registries once the tiles are built.
+    fn world(width: usize, height: usize) -> World {
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(width, height));
+        world.register::<MaterialKind>();
+        world.register::<Composition>();
+        world.register::<Phase>();
+        world.register::<Mass>();
+        world.register::<Heat>();
+        world.register::<Volume>();
+        world.register::<MaterialColor>();
+        world.register::<Burning>();
+        world.register::<StateChangeRequired>();
+        world.insert(DeltaTime::tick());
+        world.insert(SimulationErrors::default());
+        world.insert(FireEvents::default());
+        world
+    }
+
+    fn tile(
+        world: &mut World,
+        materials: &MaterialRegistry,
+        name: &str,
+        temperature: RawScalar,
+        mass: RawScalar,
+        (x, y): (f32, f32),
+    ) -> Entity {
+        materials
+            .build_entity(
+                materials.require(name).unwrap(),
+                world.create_entity(),
+                Kelvin::new_const(temperature),
+                Mass::new_const(mass),
+                Position {
+                    x: r32(x),
+                    y: r32(y),
+                },
+            )
+            .unwrap()
+    }
+
+    fn insert_registries(world: &mut World, materials: MaterialRegistry) {
+        let reactions = ReactionRegistry::load(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/assets/reactions.ron"),
+            &materials,
+        )
+        .unwrap();
+        world.insert(reactions);
+        world.insert(materials);
+    }
+
+    fn mass(world: &World, entity: Entity) -> Kilogram {
+        *world.read_storage::<Mass>().get(entity).unwrap()
+    }
+
+    fn temperature(world: &World, entity: Entity) -> RawScalar {
+        let heat = *world.read_storage::<Heat>().get(entity).unwrap();
+        heat.temperature(mass(world, entity)).raw()
+    }
+
+    fn total_mass(world: &World) -> RawScalar {
+        world
+            .read_storage::<Mass>()
+            .join()
+            .map(|mass| mass.raw())
+            .sum()
+    }
+
+    #[test]
+    fn burning_coal_consumes_its_reactants_and_heats_up() {
+        let materials = materials();
+        let capacity = materials
+            .material(materials.require("coal").unwrap(), Phase::Solid)
+            .unwrap()
+            .specific_heat_capacity();
+        let mut world = world(2, 1);
+        let coal = tile(&mut world, &materials, "coal", 1000.0, 1.0, (0.0, 0.0));
+        let oxygen = tile(&mut world, &materials, "oxygen", 300.0, 1.0, (1.0, 0.0));
+        insert_registries(&mut world, materials);
+        let mass_before = total_mass(&world);
+
+        ReactionSystem.run_now(&world);
+        world.maintain();
+
+        let burned = Mass::new_const(1.0) - mass(&world, coal);
+        assert!(burned > Mass::new_const(0.0));
+        let oxygen_used = (Mass::new_const(1.0) - mass(&world, oxygen)).raw();
+        assert!((oxygen_used - burned.raw() * 32.0 / 13.2).abs() < 1e-6);
+        // The products are added to the tile of the fuel, so no mass is lost
+        assert!((total_mass(&world) - mass_before).abs() < 1e-4);
+        // The fuel keeps its temperature while it loses mass, and takes up all of the released heat
+        let released: Joule = SpecificEnergy::new_const(29.8e6) * burned;
+        let rise = (released / (capacity * mass(&world, coal))).raw();
+        assert!((temperature(&world, coal) - (1000.0 + rise)).abs() < 1e-2);
+        assert!(world.read_storage::<Burning>().contains(coal));
+        assert_eq!(
+            world.write_resource::<SimulationErrors>().drain().count(),
+            0
+        );
+    }
+
+    #[test]
+    fn products_are_mixed_into_a_tile_in_another_phase_by_temperature() {
+        let materials = materials();
+        let mut world = world(2, 1);
+        tile(&mut world, &materials, "wood", 600.0, 1.0, (0.0, 0.0));
+        let water = tile(&mut world, &materials, "water", 300.0, 1.0, (0.0, 0.0));
+        tile(&mut world, &materials, "oxygen", 300.0, 1.0, (1.0, 0.0));
+        insert_registries(&mut world, materials);
+        assert_eq!(
+            world.read_storage::<Phase>().get(water),
+            Some(&Phase::Liquid)
+        );
+
+        ReactionSystem.run_now(&world);
+        world.maintain();
+
+        // The water is made as steam at the temperature of the wood, and mixed into the liquid water
+        let added = (mass(&world, water) - Mass::new_const(1.0)).raw();
+        assert!(added > 0.0);
+        let expected = (300.0 + added * 600.0) / (1.0 + added);
+        assert!((temperature(&world, water) - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn fire_spreads_to_neighbouring_fuel() {
+        let materials = materials();
+        let mut world = world(2, 2);
+        tile(&mut world, &materials, "coal", 1000.0, 1.0, (0.0, 0.0));
+        let wood = tile(&mut world, &materials, "wood", 450.0, 1.0, (1.0, 0.0));
+        tile(&mut world, &materials, "oxygen", 300.0, 10.0, (0.0, 1.0));
+        tile(&mut world, &materials, "oxygen", 300.0, 10.0, (1.0, 1.0));
+        insert_registries(&mut world, materials);
+        let wood_position = Position {
+            x: r32(1.0),
+            y: r32(0.0),
+        };
+
+        // The wood is heated by the burning coal until it catches fire itself
+        let mut events = Vec::new();
+        for _ in 0..100 {
+            if world.read_storage::<Burning>().contains(wood) {
+                break;
+            }
+            HeatSystem.run_now(&world);
+            ReactionSystem.run_now(&world);
+            world.maintain();
+            events.extend(world.write_resource::<FireEvents>().drain());
+        }
+
+        assert!(events.len() >= 2, "{:?}", events);
+        assert!(matches!(
+            events[0],
+            FireEvent::Started { position, .. } if position != wood_position
+        ));
+        assert!(matches!(
+            events[events.len() - 1],
+            FireEvent::Started { position, .. } if position == wood_position
+        ));
+        assert_eq!(
+            world.write_resource::<SimulationErrors>().drain().count(),
+            0
+        );
+    }
+
+    #[test]
+    fn fuel_burns_out_into_ash() {
+        let materials = materials();
+        let ash = materials.require("ash").unwrap();
+        let mut world = world(2, 1);
+        let coal = tile(&mut world, &materials, "coal", 1000.0, 0.02, (0.0, 0.0));
+        tile(&mut world, &materials, "oxygen", 300.0, 1.0, (1.0, 0.0));
+        insert_registries(&mut world, materials);
+
+        let mut events = Vec::new();
+        for _ in 0..100 {
+            if !world.is_alive(coal) {
+                break;
+            }
+            ReactionSystem.run_now(&world);
+            world.maintain();
+            events.extend(world.write_resource::<FireEvents>().drain());
+        }
+        assert!(!world.is_alive(coal));
+
+        assert_eq!(events.len(), 2, "{:?}", events);
+        assert!(matches!(events[0], FireEvent::Started { .. }));
+        assert!(matches!(
+            events[1],
+            FireEvent::Stopped {
+                burned_out: true,
+                ..
+            }
+        ));
+        let on_tile: Vec<_> = world
+            .read_storage::<Position>()
+            .on_tile(Position {
+                x: r32(0.0),
+                y: r32(0.0),
+            })
+            .map(|(entity, _)| entity)
+            .collect();
+        let kinds = world.read_storage::<MaterialKind>();
+        let masses = world.read_storage::<Mass>();
+        let ash_mass: RawScalar = on_tile
+            .iter()
+            .filter(|entity| kinds.get(**entity).map(|kind| kind.id()) == Some(ash))
+            .map(|entity| masses.get(*entity).unwrap().raw())
+            .sum();
+        assert!((ash_mass - 0.02 * 1.2 / 13.2).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,283 @@
+use crate::component::{DeltaTime, Heat, Mass, Reactor};
+use crate::units::{scalar, HeatCapacity, Joule, RawScalar};
+use crate::{Position, NEIGHBOUR_OFFSETS};
+use noisy_float::types::r32;
+use specs::prelude::*;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Every active reactor next to a reactor adds this fraction of its power to its output
+const NEIGHBOUR_BONUS: RawScalar = 1.0;
+
+/// Emitted by the [ReactorSystem] when a reactor melts down.
+#[derive(Debug, Copy, Clone)]
+pub struct MeltdownEvent {
+    pub position: Position,
+    /// The heat of the reactor and its remaining fuel, which was spread over its surroundings
+    pub released: Joule,
+}
+
+impl fmt::Display for MeltdownEvent {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "The reactor at tile {} melted down, releasing {:.3e} J",
+            self.position,
+            self.released.raw()
+        )
+    }
+}
+
+/// The [MeltdownEvent]s of the last tick. Drain these after every dispatch, otherwise they pile up.
+#[derive(Default)]
+pub struct MeltdownEvents(Vec<MeltdownEvent>);
+
+impl MeltdownEvents {
+    pub fn push(&mut self, event: MeltdownEvent) {
+        self.0.push(event);
+    }
+
+    pub fn drain(&mut self) -> std::vec::Drain<'_, MeltdownEvent> {
+        self.0.drain(..)
+    }
+}
+
+type ReactorSystemData<'a> = (
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, Mass>,
+    WriteStorage<'a, Reactor>,
+    WriteStorage<'a, Heat>,
+    Entities<'a>,
+    Write<'a, MeltdownEvents>,
+);
+
+/// Burns the fuel cells of [Reactor]s, and melts down the reactors that got too hot.
+///
+/// A reactor gets the bonus for the reactors next to it that were active at the start of the tick. The heat goes into the reactor itself, so it has to be carried off by [HeatPipe](crate::component::HeatPipe)s or conduction.
+///
+/// When a reactor is hotter than its meltdown temperature, it is deleted. Its heat and the energy of its remaining fuel are spread over every tile within its meltdown radius by their heat capacity, so they all heat up by the same amount.
+pub struct ReactorSystem;
+
+impl<'a> System<'a> for ReactorSystem {
+    type SystemData = ReactorSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            delta_time,
+            position_storage,
+            mass_storage,
+            mut reactor_storage,
+            mut heat_storage,
+            entities,
+            mut meltdown_events,
+        ): Self::SystemData,
+    ) {
+        let delta_time = delta_time.as_si();
+        let active: HashSet<Position> = (&position_storage, &reactor_storage)
+            .join()
+            .filter(|(_, reactor)| reactor.is_active())
+            .map(|(position, _)| *position)
+            .collect();
+
+        let mut meltdowns = Vec::new();
+        for (entity, position, reactor) in
+            (&entities, &position_storage, &mut reactor_storage).join()
+        {
+            let neighbours = NEIGHBOUR_OFFSETS
+                .iter()
+                .filter(|(dx, dy)| {
+                    active.contains(&Position {
+                        x: position.x + r32(*dx as f32),
+                        y: position.y + r32(*dy as f32),
+                    })
+                })
+                .count();
+            let burned = reactor.burn(reactor.power * delta_time);
+            let produced = burned * scalar(1.0 + NEIGHBOUR_BONUS * neighbours as RawScalar);
+            reactor.set_output(produced / delta_time);
+
+            let heat = some_or_continue!(heat_storage.get_mut(entity));
+            heat.joules += produced;
+            let mass = *some_or_continue!(mass_storage.get(entity));
+            let temperature = some_or_continue!(heat.try_temperature(mass).ok());
+            if temperature > reactor.meltdown_temperature {
+                let mut released = heat.joules;
+                released += reactor.remaining_fuel();
+                meltdowns.push((entity, *position, reactor.meltdown_radius, released));
+            }
+        }
+
+        // Reactors that melt down in the same tick don't take up each other's heat
+        let melted: HashSet<Entity> = meltdowns.iter().map(|(entity, ..)| *entity).collect();
+        for (reactor, position, radius, released) in meltdowns {
+            // If deleting fails, the reactor was already deleted by another system
+            let _ = entities.delete(reactor);
+            let radius = f32::from(radius);
+            let within = |other: Position| {
+                let (dx, dy) = ((other.x - position.x).raw(), (other.y - position.y).raw());
+                dx * dx + dy * dy <= radius * radius
+            };
+
+            let mut total_capacity = HeatCapacity::new_const(0.0);
+            for (entity, other, heat, mass) in
+                (&entities, &position_storage, &heat_storage, &mass_storage).join()
+            {
+                if !melted.contains(&entity) && within(*other) {
+                    total_capacity += heat.capacity * *mass;
+                }
+            }
+            if total_capacity > HeatCapacity::new_const(0.0) {
+                for (entity, other, heat, mass) in (
+                    &entities,
+                    &position_storage,
+                    &mut heat_storage,
+                    &mass_storage,
+                )
+                    .join()
+                {
+                    if !melted.contains(&entity) && within(*other) {
+                        heat.joules += released * ((heat.capacity * *mass) / total_capacity);
+                    }
+                }
+            }
+            meltdown_events.push(MeltdownEvent { position, released });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid_storage::GridStorage;
+    use crate::units::{Kelvin, Kilogram, SpecificHeatCapacity, ThermalConductivity, Watt};
+
+    fn world(width: usize, height: usize) -> World {
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(width, height));
+        world.register::<Mass>();
+        world.register::<Reactor>();
+        world.register::<Heat>();
+        world.insert(DeltaTime::tick());
+        world.insert(MeltdownEvents::default());
+        world
+    }
+
+    /// Place a tile of 1 kg with the given heat capacity at (`x`, `y`)
+    fn tile(
+        world: &mut World,
+        x: f32,
+        y: f32,
+        capacity: RawScalar,
+        temperature: RawScalar,
+    ) -> Entity {
+        let mass = Kilogram::new_const(1.0);
+        world
+            .create_entity()
+            .with(Position {
+                x: r32(x),
+                y: r32(y),
+            })
+            .with(mass)
+            .with(Heat::from_material_specs(
+                Kelvin::new_const(temperature),
+                mass,
+                SpecificHeatCapacity::new_const(capacity),
+                ThermalConductivity::new_const(50.0),
+            ))
+            .build()
+    }
+
+    fn reactor(fuel_cells: u32, meltdown_radius: u8) -> Reactor {
+        let mut reactor = Reactor::new(
+            Watt::new_const(1000.0),
+            Joule::new_const(1e6),
+            Kelvin::new_const(1500.0),
+            meltdown_radius,
+        );
+        reactor.fuel_cells = fuel_cells;
+        reactor
+    }
+
+    #[test]
+    fn neighbours_double_the_output() {
+        let mut world = world(4, 1);
+        let pair = [
+            tile(&mut world, 0.0, 0.0, 500.0, 300.0),
+            tile(&mut world, 1.0, 0.0, 500.0, 300.0),
+        ];
+        let lone = tile(&mut world, 3.0, 0.0, 500.0, 300.0);
+        for entity in pair.iter().chain(&[lone]) {
+            world
+                .write_storage::<Reactor>()
+                .insert(*entity, reactor(1, 1))
+                .unwrap();
+        }
+        let joules =
+            |world: &World, entity| world.read_storage::<Heat>().get(entity).unwrap().joules;
+        let before = joules(&world, lone);
+
+        ReactorSystem.run_now(&world);
+        let reactors = world.read_storage::<Reactor>();
+        for entity in &pair {
+            assert_eq!(
+                reactors.get(*entity).unwrap().output(),
+                Watt::new_const(2000.0)
+            );
+        }
+        assert_eq!(
+            reactors.get(lone).unwrap().output(),
+            Watt::new_const(1000.0)
+        );
+        // The bonus is extra heat, the fuel burns as fast as without it
+        for entity in pair.iter().chain(&[lone]) {
+            let remaining = reactors.get(*entity).unwrap().remaining_fuel();
+            assert_eq!(remaining, Joule::new_const(1e6 - 100.0));
+        }
+        let gained = joules(&world, lone) - before;
+        assert!((gained.raw() - 100.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn meltdown_heats_up_the_surroundings() {
+        let mut world = world(3, 3);
+        let melting = tile(&mut world, 1.0, 1.0, 500.0, 2000.0);
+        world
+            .write_storage::<Reactor>()
+            .insert(melting, reactor(2, 1))
+            .unwrap();
+        // The four tiles next to the reactor, with different heat capacities
+        let within: Vec<Entity> = [
+            (0.0, 1.0, 200.0),
+            (2.0, 1.0, 400.0),
+            (1.0, 0.0, 800.0),
+            (1.0, 2.0, 1600.0),
+        ]
+        .iter()
+        .map(|(x, y, capacity)| tile(&mut world, *x, *y, *capacity, 300.0))
+        .collect();
+        // A diagonal is further away than the meltdown radius
+        let corner = tile(&mut world, 0.0, 0.0, 500.0, 300.0);
+
+        ReactorSystem.run_now(&world);
+        world.maintain();
+        assert!(!world.is_alive(melting));
+        let events: Vec<MeltdownEvent> = world.write_resource::<MeltdownEvents>().drain().collect();
+        assert_eq!(events.len(), 1);
+        let released = events[0].released.raw();
+        // The heat of the reactor after this tick, and the fuel that was left
+        let expected = 500.0 * 2000.0 + 100.0 + (2e6 - 100.0);
+        assert!((released - expected).abs() < 1e-3 * expected);
+
+        let mass = Kilogram::new_const(1.0);
+        let heats = world.read_storage::<Heat>();
+        let temperature = |entity| heats.get(entity).unwrap().temperature(mass).raw();
+        // Spread by heat capacity, so every tile heats up by the same amount
+        let rise = released / (200.0 + 400.0 + 800.0 + 1600.0);
+        for entity in &within {
+            assert!((temperature(*entity) - (300.0 + rise)).abs() < 1e-2);
+        }
+        assert!((temperature(corner) - 300.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,370 @@
+use crate::component::{
+    Boiler, Composition, DeltaTime, Generator, Heat, Mass, MaterialKind, Phase, SteamEngine,
+};
+use crate::material::MaterialRegistry;
+use crate::units::{scalar, HeatCapacity, Joule, Kelvin, Watt};
+use crate::{GridStorageExt, Position};
+use specs::prelude::*;
+
+type BoilerSystemData<'a> = (
+    ReadExpect<'a, MaterialRegistry>,
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, Boiler>,
+    ReadStorage<'a, MaterialKind>,
+    ReadStorage<'a, Composition>,
+    ReadStorage<'a, Phase>,
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, Mass>,
+    WriteStorage<'a, Heat>,
+);
+
+/// Moves heat from hot tiles into the liquid next to a [Boiler], so that the [PhaseSystem](super::PhaseSystem) boils it into steam.
+///
+/// The boiler takes heat from every neighbour that is hotter than the boiling point of its fluid, but never cools them below it. The heat is shared between the neighbouring tiles of the fluid by their heat capacity, so they warm up evenly.
+pub struct BoilerSystem;
+
+impl<'a> System<'a> for BoilerSystem {
+    type SystemData = BoilerSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            registry,
+            delta_time,
+            boiler_storage,
+            kind_storage,
+            composition_storage,
+            phase_storage,
+            position_storage,
+            mass_storage,
+            mut heat_storage,
+        ): Self::SystemData,
+    ) {
+        let delta_time = delta_time.as_si();
+        for (boiler, position) in (&boiler_storage, &position_storage).join() {
+            let boiling_point = some_or_continue!(registry
+                .get(boiler.fluid)
+                .and_then(|fluid| fluid.boiling_point));
+
+            let mut sources = Vec::new();
+            let mut available = Joule::new_const(0.0);
+            let mut fluid = Vec::new();
+            let mut fluid_capacity = HeatCapacity::new_const(0.0);
+            for (neighbour, _) in position_storage.neighbours(*position) {
+                let heat = *some_or_continue!(heat_storage.get(neighbour));
+                let mass = *some_or_continue!(mass_storage.get(neighbour));
+                let capacity = heat.capacity * mass;
+                let is_fluid = matches!(kind_storage.get(neighbour), Some(kind) if kind.id() == boiler.fluid)
+                    && phase_storage.get(neighbour) == Some(&Phase::Liquid)
+                    && !composition_storage.contains(neighbour);
+                if is_fluid {
+                    fluid.push((neighbour, capacity));
+                    fluid_capacity += capacity;
+                    continue;
+                }
+                let temperature = some_or_continue!(heat.try_temperature(mass).ok());
+                if temperature > boiling_point {
+                    let heat_above = capacity * (temperature - boiling_point);
+                    sources.push((neighbour, heat_above));
+                    available += heat_above;
+                }
+            }
+            if fluid.is_empty() || available <= Joule::new_const(0.0) {
+                continue;
+            }
+
+            let moved = available.min(boiler.max_power * delta_time);
+            for (source, heat_above) in sources {
+                if let Some(heat) = heat_storage.get_mut(source) {
+                    heat.joules -= moved * (heat_above / available);
+                }
+            }
+            for (tile, capacity) in fluid {
+                if let Some(heat) = heat_storage.get_mut(tile) {
+                    heat.joules += moved * (capacity / fluid_capacity);
+                }
+            }
+        }
+    }
+}
+
+type SteamEngineSystemData<'a> = (
+    ReadExpect<'a, DeltaTime>,
+    WriteStorage<'a, SteamEngine>,
+    ReadStorage<'a, Phase>,
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, Mass>,
+    WriteStorage<'a, Generator>,
+    WriteStorage<'a, Heat>,
+    Entities<'a>,
+);
+
+/// Offers the power that the [SteamEngine]s can make from the hot gas around them to the power grid.
+///
+/// The efficiency of an engine is a fraction of the Carnot efficiency between the steam and the engine itself. This system runs before the [PowerGridSystem](super::PowerGridSystem), and sets the capacity and efficiency of the [Generator] of each engine from the steam around it this tick. The heat for the full capacity is taken out of the steam right away, so the engine never produces more power than the steam can pay for. The [SteamEngineSystem] gives back what the power grid did not use.
+pub struct SteamIntakeSystem;
+
+impl<'a> System<'a> for SteamIntakeSystem {
+    type SystemData = SteamEngineSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            delta_time,
+            mut engine_storage,
+            phase_storage,
+            position_storage,
+            mass_storage,
+            mut generator_storage,
+            mut heat_storage,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let delta_time = delta_time.as_si();
+        for (entity, engine, position, generator) in (
+            &entities,
+            &mut engine_storage,
+            &position_storage,
+            &mut generator_storage,
+        )
+            .join()
+        {
+            engine.set_reserved(Joule::new_const(0.0));
+            generator.capacity = Watt::new_const(0.0);
+            let ambient = heat_storage
+                .get(entity)
+                .zip(mass_storage.get(entity))
+                .and_then(|(heat, mass)| heat.try_temperature(*mass).ok());
+            let ambient = some_or_continue!(ambient);
+
+            let mut steam = Vec::new();
+            let mut available = Joule::new_const(0.0);
+            let mut steam_capacity = HeatCapacity::new_const(0.0);
+            let mut steam_heat = Joule::new_const(0.0);
+            for (neighbour, _) in position_storage.neighbours(*position) {
+                if phase_storage.get(neighbour) != Some(&Phase::Gas) {
+                    continue;
+                }
+                let heat = *some_or_continue!(heat_storage.get(neighbour));
+                let mass = *some_or_continue!(mass_storage.get(neighbour));
+                let temperature = some_or_continue!(heat.try_temperature(mass).ok());
+                if temperature <= ambient {
+                    continue;
+                }
+                let capacity = heat.capacity * mass;
+                let heat_above = capacity * (temperature - ambient);
+                steam.push((neighbour, heat_above));
+                available += heat_above;
+                steam_capacity += capacity;
+                steam_heat += capacity * temperature;
+            }
+            if steam.is_empty() {
+                continue;
+            }
+            let steam_temperature: Kelvin = steam_heat / steam_capacity;
+            let efficiency = engine.efficiency(steam_temperature, ambient);
+            if efficiency <= scalar(0.0) {
+                continue;
+            }
+
+            let reserved =
+                available.min(engine.max_power * delta_time * scalar(1.0 / efficiency.raw()));
+            for (tile, heat_above) in &steam {
+                if let Some(heat) = heat_storage.get_mut(*tile) {
+                    heat.joules -= reserved * (*heat_above / available);
+                }
+            }
+            engine.set_reserved(reserved);
+            generator.efficiency = efficiency.raw();
+            generator.capacity = reserved * efficiency / delta_time;
+        }
+    }
+}
+
+/// Settles the heat of the [SteamEngine]s after the [PowerGridSystem](super::PowerGridSystem) decided how much power they produce.
+///
+/// The heat for the power that was produced is used up: the power leaves through the grid, and the power grid adds the losses of the generator to the engine. The heat that the [SteamIntakeSystem] took for power that was not used goes back into the steam around the engine, shared by heat capacity. If there is no steam left, the engine takes it up.
+pub struct SteamEngineSystem;
+
+impl<'a> System<'a> for SteamEngineSystem {
+    type SystemData = SteamEngineSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            delta_time,
+            mut engine_storage,
+            phase_storage,
+            position_storage,
+            mass_storage,
+            generator_storage,
+            mut heat_storage,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let delta_time = delta_time.as_si();
+        for (entity, engine, position) in (&entities, &mut engine_storage, &position_storage).join()
+        {
+            let reserved = engine.reserved();
+            engine.set_reserved(Joule::new_const(0.0));
+            let mut used = Joule::new_const(0.0);
+            if let Some(generator) = generator_storage.get(entity) {
+                if generator.efficiency > 0.0 {
+                    used = generator.output() * delta_time * scalar(1.0 / generator.efficiency);
+                }
+            }
+            if used >= reserved {
+                continue;
+            }
+            let unused = reserved - used;
+
+            let mut steam = Vec::new();
+            let mut steam_capacity = HeatCapacity::new_const(0.0);
+            for (neighbour, _) in position_storage.neighbours(*position) {
+                if phase_storage.get(neighbour) != Some(&Phase::Gas) {
+                    continue;
+                }
+                let heat = some_or_continue!(heat_storage.get(neighbour));
+                let mass = *some_or_continue!(mass_storage.get(neighbour));
+                let capacity = heat.capacity * mass;
+                steam.push((neighbour, capacity));
+                steam_capacity += capacity;
+            }
+            if steam_capacity <= HeatCapacity::new_const(0.0) {
+                if let Some(heat) = heat_storage.get_mut(entity) {
+                    heat.joules += unused;
+                }
+                continue;
+            }
+            for (tile, capacity) in steam {
+                if let Some(heat) = heat_storage.get_mut(tile) {
+                    heat.joules += unused * (capacity / steam_capacity);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::{Accumulator, Consumer, PowerPole};
+    use crate::grid_storage::GridStorage;
+    use crate::system::PowerGridSystem;
+    use crate::units::{Kilogram, RawScalar, SpecificHeatCapacity, ThermalConductivity};
+    use noisy_float::types::r32;
+
+    #[test]
+    fn steam_engine_conserves_energy() {
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(3, 1));
+        world.register::<SteamEngine>();
+        world.register::<PowerPole>();
+        world.register::<Generator>();
+        world.register::<Consumer>();
+        world.register::<Accumulator>();
+        world.register::<Phase>();
+        world.register::<Mass>();
+        world.register::<Heat>();
+        world.insert(DeltaTime::tick());
+        let at = |x: f32| Position {
+            x: r32(x),
+            y: r32(0.0),
+        };
+        let heat = |temperature: RawScalar, mass: Kilogram, capacity: RawScalar| {
+            Heat::from_material_specs(
+                Kelvin::new_const(temperature),
+                mass,
+                SpecificHeatCapacity::new_const(capacity),
+                ThermalConductivity::new_const(0.1),
+            )
+        };
+
+        let steam_mass = Kilogram::new_const(0.01);
+        let steam = world
+            .create_entity()
+            .with(at(0.0))
+            .with(Phase::Gas)
+            .with(steam_mass)
+            .with(heat(500.0, steam_mass, 2080.0))
+            .build();
+        let engine_mass = Kilogram::new_const(1.0);
+        let engine = world
+            .create_entity()
+            .with(at(1.0))
+            .with(Phase::Solid)
+            .with(engine_mass)
+            .with(heat(300.0, engine_mass, 500.0))
+            .with(SteamEngine::new(Watt::new_const(1000.0), 0.5))
+            .with(Generator::new(Watt::new_const(0.0), 0.0))
+            .with(PowerPole {
+                wire_reach: 1,
+                supply_range: 1,
+            })
+            .build();
+        world
+            .create_entity()
+            .with(at(2.0))
+            .with(Consumer::new(Watt::new_const(100.0)))
+            .build();
+
+        let joules =
+            |world: &World, entity| world.read_storage::<Heat>().get(entity).unwrap().joules;
+        let set_steam_temperature = |world: &World, temperature: RawScalar| {
+            let before = joules(world, steam);
+            world
+                .write_storage::<Heat>()
+                .insert(steam, heat(temperature, steam_mass, 2080.0))
+                .unwrap();
+            joules(world, steam) - before
+        };
+        let reserved = |world: &World| {
+            world
+                .read_storage::<SteamEngine>()
+                .get(engine)
+                .unwrap()
+                .reserved()
+        };
+        let tick = |world: &World| {
+            SteamIntakeSystem.run_now(world);
+            PowerGridSystem.run_now(world);
+            let output = world
+                .read_storage::<Generator>()
+                .get(engine)
+                .unwrap()
+                .output();
+            SteamEngineSystem.run_now(world);
+            // Whatever the grid did not use went back to the steam within the tick
+            assert_eq!(reserved(world), Joule::new_const(0.0));
+            output * DeltaTime::tick().as_si()
+        };
+
+        let steam_before = joules(&world, steam);
+        let engine_before = joules(&world, engine);
+        let mut added = Joule::new_const(0.0);
+        let mut electricity = tick(&world);
+        electricity += tick(&world);
+        // Steam that is barely hotter than the engine can only pay for part of the power that the consumer wants
+        added += set_steam_temperature(&world, 301.0);
+        let barely = tick(&world);
+        assert!(barely < Watt::new_const(100.0) * DeltaTime::tick().as_si());
+        electricity += barely;
+        added += set_steam_temperature(&world, 500.0);
+        for _ in 0..3 {
+            electricity += tick(&world);
+        }
+        assert!(electricity > Joule::new_const(0.0));
+
+        let mut steam_lost = steam_before;
+        steam_lost += added;
+        steam_lost -= joules(&world, steam);
+        let mut produced = electricity;
+        produced += joules(&world, engine) - engine_before;
+        assert!(
+            (steam_lost - produced).raw().abs() < 0.01 * electricity.raw(),
+            "the steam lost {:?}, but the engine made {:?} of power and heat",
+            steam_lost,
+            produced
+        );
+    }
+}
@@ -0,0 +1,446 @@
+use crate::component::{
+    Acceleration, Composition, Damage, DeltaTime, Heat, Mass, MaterialKind, Phase, Velocity,
+    TILE_FACE_AREA,
+};
+use crate::material::{MaterialDefinition, MaterialRegistry};
+use crate::units::{
+    scalar, Kelvin, Meter, MeterCubed, MeterPerSecondSquared, Newton, NewtonMeter, Pascal,
+    RawScalar, Scalar, Seconds, GRAVITY,
+};
+use crate::{GridStorageExt, Position};
+use noisy_float::types::r32;
+use specs::prelude::*;
+use std::collections::{HashMap, VecDeque};
+
+/// The width and height of a tile
+const TILE_SIZE: Meter = Meter::new_const(1.0);
+/// The section modulus of a tile, which relates the bending moment in a beam to the stress in it. For a square beam it is a sixth of the size cubed
+const SECTION_MODULUS: MeterCubed = MeterCubed::new_const(1.0 / 6.0);
+/// A solid starts to lose strength at this fraction of its melting point, and has no strength left when it melts. Steel at 600 °C has roughly half of its strength left
+const SOFTENING_START: RawScalar = 0.3;
+/// A solid that is loaded far beyond its strength, or that is not supported at all, breaks in this time. The same goes for thermal stress
+pub(super) const FAILURE_TIME: Seconds = Seconds::new_const(1.0);
+
+type StructuralSystemData<'a> = (
+    ReadExpect<'a, MaterialRegistry>,
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, MaterialKind>,
+    ReadStorage<'a, Composition>,
+    ReadStorage<'a, Phase>,
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, Mass>,
+    ReadStorage<'a, Heat>,
+    WriteStorage<'a, Damage>,
+    WriteStorage<'a, Velocity>,
+    WriteStorage<'a, Acceleration>,
+    Entities<'a>,
+);
+
+/// Carries the weight of structures down to their foundations, and breaks solids that can not bear their load.
+///
+/// Solids with a strength that are not granular are structural. Everything else, like sand or water, is a load that rests on the structural tile below it. A structural tile passes its load to a neighbour that is closer to a foundation: down through compression, sideways by bending like a beam, or up by hanging from it. A foundation is a structural tile that stands on the bottom of the grid, or on a solid that is not structural, like a pile of sand.
+///
+/// Solids lose strength as they get closer to their melting point. A tile that is loaded beyond its strength, or that is not connected to a foundation at all, takes [Damage] until it breaks. Broken tiles fall down, and are moved by the [MotionSystem](super::MotionSystem) from then on.
+pub struct StructuralSystem;
+
+impl<'a> System<'a> for StructuralSystem {
+    type SystemData = StructuralSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            registry,
+            delta_time,
+            kind_storage,
+            composition_storage,
+            phase_storage,
+            position_storage,
+            mass_storage,
+            heat_storage,
+            mut damage_storage,
+            mut velocity_storage,
+            mut acceleration_storage,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let mut tiles: HashMap<Position, Tile> = HashMap::new();
+        for (entity, position, mass, phase) in (
+            &entities,
+            &position_storage,
+            &mass_storage,
+            phase_storage.maybe(),
+        )
+            .join()
+        {
+            let phase = phase.copied().unwrap_or(Phase::Solid);
+            if phase == Phase::Gas {
+                // The weight of a gas is carried by the pressure of the gas below it
+                continue;
+            }
+            let tile = tiles.entry(*position).or_insert_with(Tile::new);
+            let weight = GRAVITY * *mass;
+
+            let moving = velocity_storage.contains(entity);
+            let definition = kind_storage
+                .get(entity)
+                .filter(|_| !composition_storage.contains(entity))
+                .and_then(|kind| registry.get(kind.id()));
+            let strength = match definition {
+                Some(definition) if phase == Phase::Solid && !moving && tile.member.is_none() => {
+                    heat_storage
+                        .get(entity)
+                        .and_then(|heat| heat.try_temperature(*mass).ok())
+                        .and_then(|temperature| Strength::of(definition, temperature))
+                }
+                _ => None,
+            };
+            match strength {
+                Some(strength) => {
+                    let remaining = damage_storage
+                        .get(entity)
+                        .map_or(scalar(1.0), |damage| damage.remaining_strength());
+                    tile.member = Some(Member {
+                        entity,
+                        weight,
+                        strength: strength.weakened(remaining),
+                    });
+                }
+                None => {
+                    tile.load += weight;
+                    if phase.bears_load() && !moving {
+                        tile.bears_load = true;
+                    }
+                }
+            }
+        }
+
+        let mut members: Vec<Position> = tiles
+            .iter()
+            .filter(|(_, tile)| tile.member.is_some())
+            .map(|(position, _)| *position)
+            .collect();
+        members.sort_by(|a, b| a.y.cmp(&b.y).then(a.x.cmp(&b.x)));
+
+        // Find the shortest way from every structural tile to a foundation
+        let mut distances: HashMap<Position, u32> = HashMap::new();
+        let mut queue = VecDeque::new();
+        for position in &members {
+            let below = offset(*position, Direction::Down);
+            let on_foundation = !position_storage.is_in_bounds(below)
+                || matches!(tiles.get(&below), Some(tile) if tile.member.is_none() && tile.bears_load);
+            if on_foundation {
+                distances.insert(*position, 0);
+                queue.push_back(*position);
+            }
+        }
+        while let Some(position) = queue.pop_front() {
+            let distance = distances[&position];
+            for direction in &Direction::ALL {
+                let neighbour = offset(position, *direction);
+                let is_member =
+                    matches!(tiles.get(&neighbour), Some(tile) if tile.member.is_some());
+                if is_member && !distances.contains_key(&neighbour) {
+                    distances.insert(neighbour, distance + 1);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        // Pass the loads on, starting with the tiles that are furthest from a foundation
+        let mut supported: Vec<(Position, u32)> = members
+            .iter()
+            .filter_map(|position| Some((*position, *distances.get(position)?)))
+            .collect();
+        supported.sort_by(|(_, a), (_, b)| b.cmp(a));
+        let mut loads: HashMap<Position, Load> = HashMap::new();
+        let mut stresses: HashMap<Position, (Pascal, Pascal)> = HashMap::new();
+        for (position, distance) in supported {
+            let tile = &tiles[&position];
+            let member = some_or_continue!(tile.member.as_ref());
+            let mut load = loads.remove(&position).unwrap_or_default();
+
+            // Everything that is not structural rests on the structural tile below it
+            let mut above = offset(position, Direction::Up);
+            load.central += member.weight;
+            load.central += tile.load;
+            while let Some(tile) = tiles.get(&above).filter(|tile| tile.member.is_none()) {
+                if tile.load == Newton::new_const(0.0) {
+                    break;
+                }
+                load.central += tile.load;
+                above = offset(above, Direction::Up);
+            }
+            let mut force = load.central;
+            force += load.lateral;
+
+            // Foundations carry their load straight down into the ground
+            let parent = if distance == 0 {
+                None
+            } else {
+                Direction::ALL.iter().copied().find(|direction| {
+                    distances.get(&offset(position, *direction)) == Some(&(distance - 1))
+                })
+            };
+            // The bending moment where this tile connects to a neighbour beside it. The loads in the middle of the tile act half a tile away from that edge
+            let mut moment = load.moment;
+            moment += load.central * TILE_SIZE * scalar(0.5);
+            let (compression, tension) = match parent {
+                None | Some(Direction::Down) => (force / TILE_FACE_AREA, Pascal::new_const(0.0)),
+                Some(Direction::Up) => (Pascal::new_const(0.0), force / TILE_FACE_AREA),
+                Some(Direction::Left) | Some(Direction::Right) => {
+                    (Pascal::new_const(0.0), moment / SECTION_MODULUS)
+                }
+            };
+            stresses.insert(position, (compression, tension));
+
+            if let Some(direction) = parent {
+                let parent_load = loads.entry(offset(position, direction)).or_default();
+                match direction {
+                    Direction::Left | Direction::Right => {
+                        // At the far edge of the neighbour, the load of this tile acts a whole tile away
+                        moment += force * TILE_SIZE;
+                        parent_load.moment += moment;
+                        parent_load.lateral += force;
+                    }
+                    Direction::Up | Direction::Down => parent_load.central += force,
+                }
+            }
+        }
+
+        let delta_time = delta_time.as_si();
+        for position in members {
+            let member = some_or_continue!(tiles[&position].member.as_ref());
+            // A tile that is not connected to a foundation carries an infinite load
+            let utilization = match stresses.get(&position) {
+                Some((compression, tension)) => member
+                    .strength
+                    .utilization(*compression, *tension)
+                    .unwrap_or_else(|| scalar(RawScalar::MAX)),
+                None => scalar(RawScalar::MAX),
+            };
+            if utilization <= scalar(1.0) {
+                continue;
+            }
+
+            let overload = (utilization - scalar(1.0)).min(scalar(1.0));
+            let damage = damage_storage.entry(member.entity).map(|entry| {
+                let damage = entry.or_insert_with(Damage::default);
+                damage.add(overload * (delta_time / FAILURE_TIME));
+                *damage
+            });
+            if matches!(damage, Ok(damage) if damage.is_broken()) {
+                // This can only fail if the entity is dead, and dead entities are not joined
+                let _ = velocity_storage.insert(member.entity, Velocity::default());
+                let _ = acceleration_storage.insert(
+                    member.entity,
+                    Acceleration {
+                        x: MeterPerSecondSquared::new_const(0.0),
+                        y: GRAVITY,
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// A structural tile.
+struct Member {
+    entity: Entity,
+    weight: Newton,
+    strength: Strength,
+}
+
+struct Tile {
+    member: Option<Member>,
+    /// The weight of everything on the tile that is not structural
+    load: Newton,
+    /// There is a solid that is not structural on the tile, which can carry a structure on top of it
+    bears_load: bool,
+}
+
+impl Tile {
+    fn new() -> Self {
+        Tile {
+            member: None,
+            load: Newton::new_const(0.0),
+            bears_load: false,
+        }
+    }
+}
+
+/// The loads that are passed to a structural tile by its neighbours.
+struct Load {
+    /// Loads that act on the middle of the tile, from the tiles above and below it
+    central: Newton,
+    /// Loads from the tiles beside it
+    lateral: Newton,
+    /// The bending moment of the tiles beside it, at the edge where they connect
+    moment: NewtonMeter,
+}
+
+impl Default for Load {
+    fn default() -> Self {
+        Load {
+            central: Newton::new_const(0.0),
+            lateral: Newton::new_const(0.0),
+            moment: NewtonMeter::new_const(0.0),
+        }
+    }
+}
+
+/// The strength of a structural tile at its current temperature.
+#[derive(Copy, Clone)]
+pub(super) struct Strength {
+    pub(super) compressive: Pascal,
+    pub(super) tensile: Pascal,
+}
+
+impl Strength {
+    pub(super) fn of(definition: &MaterialDefinition, temperature: Kelvin) -> Option<Self> {
+        if definition.granular {
+            return None;
+        }
+        let strength = Strength {
+            compressive: definition.compressive_strength?,
+            tensile: definition.tensile_strength?,
+        };
+        let remaining = match definition.melting_point {
+            Some(melting_point) => {
+                let start = melting_point * scalar(SOFTENING_START);
+                ((melting_point - temperature) / (melting_point - start))
+                    .min(scalar(1.0))
+                    .max(scalar(0.0))
+            }
+            None => scalar(1.0),
+        };
+        Some(strength.weakened(remaining))
+    }
+
+    pub(super) fn weakened(self, remaining: Scalar) -> Self {
+        Strength {
+            compressive: self.compressive * remaining,
+            tensile: self.tensile * remaining,
+        }
+    }
+
+    /// How close the stresses are to breaking the tile, where 1 means that it is just about to break. Returns `None` if the tile has no strength left.
+    fn utilization(self, compression: Pascal, tension: Pascal) -> Option<Scalar> {
+        let zero = Pascal::new_const(0.0);
+        if self.compressive <= zero || self.tensile <= zero {
+            return None;
+        }
+        Some((compression / self.compressive).max(tension / self.tensile))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Direction {
+    Down,
+    Left,
+    Right,
+    Up,
+}
+
+impl Direction {
+    /// In order of preference: a load is carried down if possible, and only hangs from the tile above if there is no other way
+    const ALL: [Direction; 4] = [
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+        Direction::Up,
+    ];
+}
+
+fn offset(position: Position, direction: Direction) -> Position {
+    let (x, y) = match direction {
+        Direction::Down => (0.0, 1.0),
+        Direction::Left => (-1.0, 0.0),
+        Direction::Right => (1.0, 0.0),
+        Direction::Up => (0.0, -1.0),
+    };
+    Position {
+        x: position.x + r32(x),
+        y: position.y + r32(y),
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::component::{MaterialColor, Volume};
+    use crate::grid_storage::GridStorage;
+    use crate::units::Kilogram;
+
+    /// Build brick tiles at `positions`, and load them for `ticks` ticks. Returns which of them broke.
+    fn load(width: usize, height: usize, positions: &[(f32, f32)], ticks: usize) -> Vec<bool> {
+        let registry =
+            MaterialRegistry::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/materials.ron"))
+                .unwrap();
+        let brick = registry.require("brick").unwrap();
+        let mass = Kilogram::new_const(1900.0);
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(width, height));
+        world.register::<MaterialKind>();
+        world.register::<Composition>();
+        world.register::<Phase>();
+        world.register::<Mass>();
+        world.register::<Volume>();
+        world.register::<Heat>();
+        world.register::<MaterialColor>();
+        world.register::<Damage>();
+        world.register::<Velocity>();
+        world.register::<Acceleration>();
+        world.insert(DeltaTime::tick());
+        let entities: Vec<Entity> = positions
+            .iter()
+            .map(|(x, y)| {
+                registry
+                    .build_entity(
+                        brick,
+                        world.create_entity(),
+                        Kelvin::new_const(293.15),
+                        mass,
+                        Position {
+                            x: r32(*x),
+                            y: r32(*y),
+                        },
+                    )
+                    .unwrap()
+            })
+            .collect();
+        world.insert(registry);
+
+        for _ in 0..ticks {
+            StructuralSystem.run_now(&world);
+        }
+        let velocities = world.read_storage::<Velocity>();
+        entities
+            .iter()
+            .map(|entity| velocities.contains(*entity))
+            .collect()
+    }
+
+    #[test]
+    fn supported_columns_hold() {
+        let broken = load(1, 4, &[(0.0, 0.0), (0.0, 1.0), (0.0, 2.0), (0.0, 3.0)], 30);
+        assert_eq!(broken, vec![false; 4]);
+    }
+
+    #[test]
+    fn unsupported_tiles_break() {
+        // A beam that hangs in the air, without anything to rest on or hang from
+        let broken = load(3, 3, &[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)], 30);
+        assert_eq!(broken, vec![true; 3]);
+    }
+
+    #[test]
+    fn long_overhangs_break() {
+        // A column of two tiles, with a beam of eight tiles sticking out from the top
+        let mut positions = vec![(0.0, 1.0), (0.0, 0.0)];
+        positions.extend((1..9).map(|x| (x as f32, 0.0)));
+        let broken = load(9, 2, &positions, 30);
+        assert!(!broken[0]);
+        // The bending moment is largest where the beam meets the column
+        assert!(broken[2], "{:?}", broken);
+    }
+}
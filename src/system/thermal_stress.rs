@@ -0,0 +1,254 @@
+use super::structure::{Strength, FAILURE_TIME};
+use crate::component::{Composition, Damage, DeltaTime, Heat, Mass, MaterialKind, Phase, Velocity};
+use crate::material::MaterialRegistry;
+use crate::units::{scalar, Kelvin, Pascal, PerKelvin, RawScalar};
+use crate::{GridStorageExt, Position};
+use noisy_float::prelude::Float;
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// Solids are assumed to have been put together at room temperature, when there is no stress between them
+const ASSEMBLY_TEMPERATURE: Kelvin = Kelvin::new_const(293.15);
+/// A solid that touches a liquid only sees the temperature of the liquid at its surface. Its core holds the surface back, and takes half of the difference in expansion
+const SURFACE_FRACTION: RawScalar = 0.5;
+
+type ThermalStressSystemData<'a> = (
+    ReadExpect<'a, MaterialRegistry>,
+    ReadExpect<'a, DeltaTime>,
+    ReadStorage<'a, MaterialKind>,
+    ReadStorage<'a, Composition>,
+    ReadStorage<'a, Phase>,
+    ReadStorage<'a, Position>,
+    ReadStorage<'a, Mass>,
+    ReadStorage<'a, Heat>,
+    ReadStorage<'a, Velocity>,
+    WriteStorage<'a, Damage>,
+    Entities<'a>,
+);
+
+/// Damages solids that are heated unevenly.
+///
+/// A solid that is hotter than its neighbours wants to expand, but is held back by them. Solids that are stuck together push and pull on each other when they expand by different amounts, because they have a different temperature or a different [thermal_expansion](crate::material::MaterialDefinition::thermal_expansion). A solid that is quenched in a liquid cools down at its surface first, which pulls on its hot core.
+///
+/// When the stress is larger than the tensile strength of the solid, it takes [Damage]: it is weaker at first, then it cracks, and a broken solid collapses under the [StructuralSystem](super::StructuralSystem). Gases carry too little heat to cause any stress.
+pub struct ThermalStressSystem;
+
+impl<'a> System<'a> for ThermalStressSystem {
+    type SystemData = ThermalStressSystemData<'a>;
+
+    fn run(
+        &mut self,
+        (
+            registry,
+            delta_time,
+            kind_storage,
+            composition_storage,
+            phase_storage,
+            position_storage,
+            mass_storage,
+            heat_storage,
+            velocity_storage,
+            mut damage_storage,
+            entities,
+        ): Self::SystemData,
+    ) {
+        let mut bodies: HashMap<Entity, Body> = HashMap::new();
+        for (entity, mass, heat, phase) in (
+            &entities,
+            &mass_storage,
+            &heat_storage,
+            phase_storage.maybe(),
+        )
+            .join()
+        {
+            let phase = phase.copied().unwrap_or(Phase::Solid);
+            if phase == Phase::Gas {
+                continue;
+            }
+            let temperature = some_or_continue!(heat.try_temperature(*mass).ok());
+            let elasticity = kind_storage
+                .get(entity)
+                .filter(|_| phase == Phase::Solid && !composition_storage.contains(entity))
+                .and_then(|kind| {
+                    let definition = registry.get(kind.id())?;
+                    let remaining = damage_storage
+                        .get(entity)
+                        .map_or(scalar(1.0), |damage| damage.remaining_strength());
+                    Some(Elasticity {
+                        expansion: definition.thermal_expansion?,
+                        modulus: definition.elastic_modulus?,
+                        strength: Strength::of(definition, temperature)?
+                            .weakened(remaining)
+                            .tensile,
+                    })
+                });
+            bodies.insert(
+                entity,
+                Body {
+                    temperature,
+                    phase,
+                    elasticity,
+                    // Solids that are moving are not stuck to anything
+                    bonded: phase == Phase::Solid && !velocity_storage.contains(entity),
+                },
+            );
+        }
+
+        let delta_time = delta_time.as_si();
+        for (entity, position) in (&entities, &position_storage).join() {
+            let body = some_or_continue!(bodies.get(&entity));
+            let elasticity = some_or_continue!(body.elasticity);
+
+            let mut stress = Pascal::new_const(0.0);
+            for (other_entity, _) in position_storage.neighbours(*position) {
+                let other = some_or_continue!(bodies.get(&other_entity));
+                let other_stress = match (other.phase, other.elasticity) {
+                    (Phase::Liquid, _) => {
+                        let strain =
+                            (elasticity.expansion * (body.temperature - other.temperature)).abs();
+                        elasticity.modulus * scalar(SURFACE_FRACTION) * strain
+                    }
+                    (Phase::Solid, Some(other_elasticity)) if body.bonded && other.bonded => {
+                        let strain = (elasticity.expansion
+                            * (body.temperature - ASSEMBLY_TEMPERATURE)
+                            - other_elasticity.expansion
+                                * (other.temperature - ASSEMBLY_TEMPERATURE))
+                            .abs();
+                        elasticity.in_series(other_elasticity) * strain
+                    }
+                    _ => continue,
+                };
+                if other_stress > stress {
+                    stress = other_stress;
+                }
+            }
+
+            let zero = Pascal::new_const(0.0);
+            if stress <= zero {
+                continue;
+            }
+            // A solid without any strength left is broken by the smallest stress
+            let overload = if elasticity.strength > zero {
+                (stress / elasticity.strength - scalar(1.0)).min(scalar(1.0))
+            } else {
+                scalar(1.0)
+            };
+            if overload <= scalar(0.0) {
+                continue;
+            }
+            if let Ok(entry) = damage_storage.entry(entity) {
+                entry
+                    .or_insert_with(Damage::default)
+                    .add(overload * (delta_time / FAILURE_TIME));
+            }
+        }
+    }
+}
+
+struct Body {
+    temperature: Kelvin,
+    phase: Phase,
+    /// Only solids with a [thermal_expansion](crate::material::MaterialDefinition::thermal_expansion) and a strength feel thermal stress
+    elasticity: Option<Elasticity>,
+    bonded: bool,
+}
+
+#[derive(Copy, Clone)]
+struct Elasticity {
+    expansion: PerKelvin,
+    modulus: Pascal,
+    /// The tensile strength at the current temperature, after damage
+    strength: Pascal,
+}
+
+impl Elasticity {
+    /// The stiffness of two solids that are stuck together, which share the difference in expansion between them
+    fn in_series(self, other: Elasticity) -> Pascal {
+        let mut sum = self.modulus;
+        sum += other.modulus;
+        self.modulus * (other.modulus / sum)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::component::{MaterialColor, Volume};
+    use crate::grid_storage::GridStorage;
+    use crate::units::Kilogram;
+    use noisy_float::types::r32;
+
+    #[test]
+    fn heating_different_solids_together_damages_the_weaker_one() {
+        let registry =
+            MaterialRegistry::load(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/materials.ron"))
+                .unwrap();
+        let mut world = World::new();
+        world.register_with_storage::<_, Position>(|| GridStorage::with_size(2, 1));
+        world.register::<MaterialKind>();
+        world.register::<Composition>();
+        world.register::<Phase>();
+        world.register::<Mass>();
+        world.register::<Volume>();
+        world.register::<Heat>();
+        world.register::<MaterialColor>();
+        world.register::<Velocity>();
+        world.register::<Damage>();
+        world.insert(DeltaTime::tick());
+        let mass = Kilogram::new_const(100.0);
+        // Steel expands twice as much as brick when it is heated
+        let tiles: Vec<Entity> = ["steel", "brick"]
+            .iter()
+            .enumerate()
+            .map(|(x, name)| {
+                registry
+                    .build_entity(
+                        registry.require(name).unwrap(),
+                        world.create_entity(),
+                        ASSEMBLY_TEMPERATURE,
+                        mass,
+                        Position {
+                            x: r32(x as f32),
+                            y: r32(0.0),
+                        },
+                    )
+                    .unwrap()
+            })
+            .collect();
+        let (steel, brick) = (tiles[0], tiles[1]);
+        let damage = |world: &World, entity| {
+            world
+                .read_storage::<Damage>()
+                .get(entity)
+                .copied()
+                .unwrap_or_default()
+        };
+
+        world.insert(registry);
+        ThermalStressSystem.run_now(&world);
+        assert_eq!(damage(&world, brick).amount(), scalar(0.0));
+
+        // Heat both of them at once, much faster than the heat could even out between them
+        for tile in &tiles {
+            let mut heats = world.write_storage::<Heat>();
+            let heat = heats.get_mut(*tile).unwrap();
+            heat.joules = heat.capacity * mass * Kelvin::new_const(600.0);
+        }
+        let mut previous = damage(&world, brick);
+        for _ in 0..5 {
+            ThermalStressSystem.run_now(&world);
+            let damage = damage(&world, brick);
+            assert!(damage.amount() > previous.amount());
+            previous = damage;
+        }
+        assert!(previous.is_cracked());
+        assert!(previous.remaining_strength() <= scalar(0.5));
+        // The steel is much stronger, so it can take the stress
+        assert_eq!(damage(&world, steel).amount(), scalar(0.0));
+
+        for _ in 0..10 {
+            ThermalStressSystem.run_now(&world);
+        }
+        assert!(damage(&world, brick).is_broken());
+    }
+}
@@ -0,0 +1,35 @@
+use super::RawScalar;
+use std::fmt;
+
+/// Error returned when a unit would end up with a value that is NaN or infinite.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnitError {
+    pub unit: &'static str,
+    pub value: RawScalar,
+}
+
+impl UnitError {
+    pub fn new(unit: &'static str, value: RawScalar) -> Self {
+        Self { unit, value }
+    }
+}
+
+impl fmt::Display for UnitError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{} {} is not a finite value", self.value, self.unit)
+    }
+}
+
+impl std::error::Error for UnitError {}
+
+/// Multiplication that returns a [UnitError] instead of panicking when the result is not finite.
+pub trait CheckedMul<Rhs> {
+    type Output;
+    fn checked_mul(self, rhs: Rhs) -> Result<Self::Output, UnitError>;
+}
+
+/// Division that returns a [UnitError] instead of panicking when the result is not finite.
+pub trait CheckedDiv<Rhs> {
+    type Output;
+    fn checked_div(self, rhs: Rhs) -> Result<Self::Output, UnitError>;
+}
@@ -1,54 +1,159 @@
 use super::si::*;
+use super::{scalar, CheckedDiv, CheckedMul, RawScalar, Scalar, UnitError};
 use noisy_float::prelude::Float;
-use noisy_float::types::R32;
 
-pub struct MeterSquared(pub R32);
+pub struct MeterSquared(pub Scalar);
 macros::unit!(MeterSquared: "m²");
 macros::conversion!(Meter * Meter => MeterSquared);
 
-pub struct KgMeter(pub R32);
+pub struct MeterCubed(pub Scalar);
+macros::unit!(MeterCubed: "m³");
+macros::conversion!(MeterSquared * Meter => MeterCubed);
+macros::conversion!(MeterCubed / MeterSquared => Meter);
+
+impl MeterCubed {
+    pub fn random() -> Self {
+        use rand::{thread_rng, Rng};
+        Self(Scalar::new(thread_rng().gen_range(0.0, 1.0)))
+    }
+}
+
+pub struct KgMeter(pub Scalar);
 macros::unit!(KgMeter: "kg×m");
 macros::conversion!(Kilogram * Meter => KgMeter);
 
-pub struct KgMeterSquared(pub R32);
+pub struct KgMeterSquared(pub Scalar);
 macros::unit!(KgMeterSquared: "kg×m²");
 macros::conversion!(Kilogram * MeterSquared => KgMeterSquared);
 macros::conversion!(MeterSquared * Kilogram => KgMeterSquared);
 
-pub struct SecondsSquared(pub R32);
+pub struct SecondsSquared(pub Scalar);
 macros::unit!(SecondsSquared: "s²");
 macros::conversion!(Seconds * Seconds => SecondsSquared);
 
-pub struct Joule(pub R32);
+pub struct Joule(pub Scalar);
 macros::unit!(Joule: "J");
 macros::conversion!(KgMeterSquared / SecondsSquared => Joule);
 
-pub struct Watt(pub R32);
+/// Specific energy, at J/kg
+pub struct SpecificEnergy(pub Scalar);
+macros::unit!(SpecificEnergy: "J/kg");
+macros::conversion!(Joule / Kilogram => SpecificEnergy);
+macros::conversion!(SpecificEnergy * Kilogram => Joule);
+macros::conversion!(Joule / SpecificEnergy => Kilogram);
+
+/// Mass flow, in kg/s
+pub struct KilogramPerSecond(pub Scalar);
+macros::unit!(KilogramPerSecond: "kg/s");
+macros::conversion!(Kilogram / Seconds => KilogramPerSecond);
+macros::conversion!(KilogramPerSecond * Seconds => Kilogram);
+
+pub struct Watt(pub Scalar);
 macros::unit!(Watt: "W");
 macros::conversion!(Joule / Seconds => Watt);
-
-pub struct Newton(pub R32);
+macros::conversion!(Watt * Seconds => Joule);
+macros::conversion!(Volt * Ampere => Watt);
+macros::conversion!(KilogramPerSecond * SpecificEnergy => Watt);
+
+/// Electric potential, in V
+pub struct Volt(pub Scalar);
+macros::unit!(Volt: "V");
+macros::conversion!(Ampere * Ohm => Volt);
+
+/// Electrical resistance, in Ω
+pub struct Ohm(pub Scalar);
+macros::unit!(Ohm: "Ω");
+macros::conversion!(Volt / Ampere => Ohm);
+macros::conversion!(OhmMeter / Meter => Ohm);
+macros::conversion!(Volt / Ohm => Ampere);
+
+/// Electrical resistivity, in Ω×m
+pub struct OhmMeter(pub Scalar);
+macros::unit!(OhmMeter: "Ω×m");
+
+pub struct Newton(pub Scalar);
 macros::unit!(Newton: "N");
-macros::conversion!(KgMeter / Seconds => Newton);
+macros::conversion!(KgMeter / SecondsSquared => Newton);
+macros::conversion!(Newton / Kilogram => MeterPerSecondSquared);
+
+pub struct MeterPerSecond(pub Scalar);
+macros::unit!(MeterPerSecond: "m/s");
+macros::conversion!(Meter / Seconds => MeterPerSecond);
+macros::conversion!(MeterPerSecond * Seconds => Meter);
+macros::conversion!(MeterPerSecond * MeterPerSecond => SpecificEnergy);
 
-pub struct MeterPerSecondSquared(pub R32);
+pub struct MeterPerSecondSquared(pub Scalar);
 macros::unit!(MeterPerSecondSquared: "m/s²");
 macros::conversion!(Meter / SecondsSquared => MeterPerSecondSquared);
+macros::conversion!(MeterPerSecondSquared * Seconds => MeterPerSecond);
+macros::conversion!(MeterPerSecondSquared * Kilogram => Newton);
+
+/// Torque or bending moment, in N×m
+pub struct NewtonMeter(pub Scalar);
+macros::unit!(NewtonMeter: "N×m");
+macros::conversion!(Newton * Meter => NewtonMeter);
+
+pub const GRAVITY: MeterPerSecondSquared = MeterPerSecondSquared::new_const(9.82);
+
+pub struct Pascal(pub Scalar);
+macros::unit!(Pascal: "Pa");
+macros::conversion!(Newton / MeterSquared => Pascal);
+macros::conversion!(Joule / MeterCubed => Pascal);
+macros::conversion!(NewtonMeter / MeterCubed => Pascal);
+
+pub const ATMOSPHERIC_PRESSURE: Pascal = Pascal::new_const(101_325.0);
+
+impl Pascal {
+    /// The pressure of an ideal gas
+    pub fn ideal_gas(
+        mass: Kilogram,
+        molar_mass: KilogramPerMole,
+        temperature: Kelvin,
+        volume: MeterCubed,
+    ) -> Self {
+        (GAS_CONSTANT * (mass / molar_mass)) * temperature / volume
+    }
+}
+
+/// How much the pressure of a tile rises per kg of fluid that is added to it, in Pa/kg
+pub struct PascalPerKilogram(pub Scalar);
+macros::unit!(PascalPerKilogram: "Pa/kg");
+macros::conversion!(Pascal / Kilogram => PascalPerKilogram);
+macros::conversion!(Pascal / PascalPerKilogram => Kilogram);
+
+/// The pressure gradient of a column of fluid, in Pa/m
+pub struct PascalPerMeter(pub Scalar);
+macros::unit!(PascalPerMeter: "Pa/m");
+macros::conversion!(Density * MeterPerSecondSquared => PascalPerMeter);
+macros::conversion!(PascalPerMeter * Meter => Pascal);
 
-// const_unchecked_new is only unsafe because it requires the caller to pass in a non-null and non-inf float value
-// Because we pass a constant value of 9.82, this is actually safe
-pub const GRAVITY: MeterPerSecondSquared =
-    MeterPerSecondSquared(unsafe { R32::const_unchecked_new(9.82) });
+pub struct KilogramPerMole(pub Scalar);
+macros::unit!(KilogramPerMole: "kg/mol");
+macros::conversion!(Kilogram / Mole => KilogramPerMole);
+macros::conversion!(Kilogram / KilogramPerMole => Mole);
+
+pub struct JoulePerMoleKelvin(pub Scalar);
+macros::unit!(JoulePerMoleKelvin: "J/(mol K)");
+macros::conversion!(HeatCapacity / Mole => JoulePerMoleKelvin);
+macros::conversion!(JoulePerMoleKelvin * Mole => HeatCapacity);
+
+/// The ideal gas constant
+pub const GAS_CONSTANT: JoulePerMoleKelvin = JoulePerMoleKelvin::new_const(8.314_462);
 
 /// Heat capacity, at J/K
-pub struct HeatCapacity(pub R32);
+pub struct HeatCapacity(pub Scalar);
 macros::unit!(HeatCapacity: "J/K");
 macros::conversion!(Joule / Kelvin => HeatCapacity);
 macros::conversion!(HeatCapacity * Kelvin => Joule);
 macros::conversion!(Joule / HeatCapacity => Kelvin);
 
+/// Thermal conductance, how much heat flows per kelvin of temperature difference, in W/K
+pub struct WattPerKelvin(pub Scalar);
+macros::unit!(WattPerKelvin: "W/K");
+macros::conversion!(WattPerKelvin * Kelvin => Watt);
+
 /// Specific heat capacity, at J/(K KG)
-pub struct SpecificHeatCapacity(pub R32);
+pub struct SpecificHeatCapacity(pub Scalar);
 macros::unit!(SpecificHeatCapacity: "J/(K Kg)");
 macros::conversion!(HeatCapacity * Kilogram => SpecificHeatCapacity);
 macros::conversion!(SpecificHeatCapacity * Kilogram => HeatCapacity);
@@ -60,7 +165,7 @@ impl SpecificHeatCapacity {
     }
 }
 
-pub struct ThermalConductivity(pub R32);
+pub struct ThermalConductivity(pub Scalar);
 macros::unit!(ThermalConductivity: "(J/(m*s))/K");
 macros::conversion!(ThermalConductivity * Kelvin => JoulesPerMeterSecond);
 
@@ -70,10 +175,67 @@ impl ThermalConductivity {
     }
 }
 
-pub struct JoulesPerMeterSecond(pub R32);
+/// How well heat passes from a surface into the fluid around it, in W/(m² K)
+pub struct HeatTransferCoefficient(pub Scalar);
+macros::unit!(HeatTransferCoefficient: "W/(m² K)");
+macros::conversion!(ThermalConductivity / Meter => HeatTransferCoefficient);
+macros::conversion!(HeatTransferCoefficient * MeterSquared => WattPerKelvin);
+
+/// The Stefan–Boltzmann constant, in W/(m² K⁴)
+pub const STEFAN_BOLTZMANN: RawScalar = 5.670_374e-8;
+
+impl HeatTransferCoefficient {
+    /// Thermal radiation between a surface at `surface` and its surroundings at `surroundings`, as a heat transfer coefficient.
+    ///
+    /// The radiated power εσ(Ts⁴−Ta⁴) is written as h×(Ts−Ta) with h = εσ(Ts²+Ta²)(Ts+Ta), so it adds up with convection.
+    pub fn radiation(emissivity: RawScalar, surface: Kelvin, surroundings: Kelvin) -> Self {
+        let (surface, surroundings) = (surface.0, surroundings.0);
+        HeatTransferCoefficient(
+            scalar(STEFAN_BOLTZMANN * emissivity)
+                * (surface * surface + surroundings * surroundings)
+                * (surface + surroundings),
+        )
+    }
+}
+
+/// How much a material expands per kelvin that it is heated, as a fraction of its size, in 1/K
+pub struct PerKelvin(pub Scalar);
+macros::unit!(PerKelvin: "1/K");
+
+impl std::ops::Mul<Kelvin> for PerKelvin {
+    type Output = Scalar;
+    fn mul(self, rhs: Kelvin) -> Scalar {
+        self.0 * rhs.0
+    }
+}
+
+/// Density, at kg/m³
+pub struct Density(pub Scalar);
+macros::unit!(Density: "kg/m³");
+macros::conversion!(Kilogram / MeterCubed => Density);
+macros::conversion!(Kilogram / Density => MeterCubed);
+macros::conversion!(Density * MeterCubed => Kilogram);
+macros::conversion!(Density * MeterCubedPerSecond => KilogramPerSecond);
+
+/// Diffusivity, in m²/s
+pub struct MeterSquaredPerSecond(pub Scalar);
+macros::unit!(MeterSquaredPerSecond: "m²/s");
+macros::conversion!(MeterSquaredPerSecond / Meter => MeterPerSecond);
+
+/// Volume flow, in m³/s
+pub struct MeterCubedPerSecond(pub Scalar);
+macros::unit!(MeterCubedPerSecond: "m³/s");
+macros::conversion!(MeterPerSecond * MeterSquared => MeterCubedPerSecond);
+
+pub struct JoulesPerMeterSecond(pub Scalar);
 macros::unit!(JoulesPerMeterSecond: "J/(m*s)");
 macros::conversion!(JoulesPerMeterSecond / Meter => JoulesPerSecond);
 
-pub struct JoulesPerSecond(pub R32);
+pub struct JoulesMeterPerSecond(pub Scalar);
+macros::unit!(JoulesMeterPerSecond: "(J*m)/s");
+macros::conversion!(JoulesPerMeterSecond * MeterSquared => JoulesMeterPerSecond);
+macros::conversion!(JoulesMeterPerSecond / Meter => JoulesPerSecond);
+
+pub struct JoulesPerSecond(pub Scalar);
 macros::unit!(JoulesPerSecond: "J/s");
 macros::conversion!(JoulesPerSecond * Seconds => Joule);
@@ -1,7 +1,28 @@
 #![allow(dead_code)]
 
+mod checked;
 mod combinations;
+#[cfg(feature = "serde")]
+pub mod serialize;
 pub mod si;
 
+pub use self::checked::*;
 pub use self::combinations::*;
 pub use self::si::*;
+
+/// The scalar type that all units are stored in.
+///
+/// Tile energies are in the order of 1e9 J while a single tick only transfers a few joules, so with `f32` these transfers get lost entirely. Enable the `f64` feature to run the simulation in double precision.
+#[cfg(not(feature = "f64"))]
+pub type Scalar = noisy_float::types::R32;
+#[cfg(not(feature = "f64"))]
+pub type RawScalar = f32;
+#[cfg(not(feature = "f64"))]
+pub use noisy_float::types::r32 as scalar;
+
+#[cfg(feature = "f64")]
+pub type Scalar = noisy_float::types::R64;
+#[cfg(feature = "f64")]
+pub type RawScalar = f64;
+#[cfg(feature = "f64")]
+pub use noisy_float::types::r64 as scalar;
@@ -0,0 +1,70 @@
+//! Serialization helpers used by the `unit!` macro.
+//!
+//! Human-readable formats (RON, JSON, TOML) store a unit as a string with its symbol, e.g. `"300 K"`. Plain numbers are accepted as well. Compact formats store the raw number.
+
+use super::{RawScalar, Scalar, UnitError};
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+pub fn serialize<S: Serializer>(
+    value: Scalar,
+    unit: &'static str,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.collect_str(&format_args!("{} {}", value, unit))
+    } else {
+        value.raw().serialize(serializer)
+    }
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    unit: &'static str,
+) -> Result<Scalar, D::Error> {
+    let value = if deserializer.is_human_readable() {
+        deserializer.deserialize_any(UnitVisitor { unit })?
+    } else {
+        RawScalar::deserialize(deserializer)?
+    };
+    Scalar::try_new(value).ok_or_else(|| de::Error::custom(UnitError::new(unit, value)))
+}
+
+struct UnitVisitor {
+    unit: &'static str,
+}
+
+impl<'de> Visitor<'de> for UnitVisitor {
+    type Value = RawScalar;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "a number or a string like \"1 {}\"", self.unit)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<RawScalar, E> {
+        Ok(value as RawScalar)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<RawScalar, E> {
+        Ok(value as RawScalar)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<RawScalar, E> {
+        Ok(value as RawScalar)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<RawScalar, E> {
+        let value = value.trim();
+        let number = match value.strip_suffix(self.unit) {
+            Some(number) => number.trim_end(),
+            None => value,
+        };
+        number.parse().map_err(|_| {
+            E::custom(format!(
+                "invalid value {:?}, expected a number in {}",
+                value, self.unit
+            ))
+        })
+    }
+}
@@ -1,45 +1,45 @@
-use noisy_float::types::R32;
+use super::{RawScalar, Scalar, UnitError};
 
-pub struct Seconds(pub R32);
+pub struct Seconds(pub Scalar);
 macros::unit!(Seconds: "s");
 
-pub struct Meter(pub R32);
+pub struct Meter(pub Scalar);
 macros::unit!(Meter: "m");
 
-pub struct Kilogram(pub R32);
+pub struct Kilogram(pub Scalar);
 macros::unit!(Kilogram: "kg");
 
 impl Kilogram {
     pub fn random() -> Self {
         use rand::{thread_rng, Rng};
-        Self(R32::new(thread_rng().gen_range(0.0, 1000.0)))
+        Self(Scalar::new(thread_rng().gen_range(0.0, 1000.0)))
     }
 }
 
-pub struct Ampere(pub R32);
+pub struct Ampere(pub Scalar);
 macros::unit!(Ampere: "A");
 
-pub struct Kelvin(pub R32);
+pub struct Kelvin(pub Scalar);
 macros::unit!(Kelvin: "K");
 
 impl Kelvin {
     pub fn random() -> Self {
         use rand::{thread_rng, Rng};
-        Self(R32::new(thread_rng().gen_range(0.0, 1000.0)))
+        Self(Scalar::new(thread_rng().gen_range(0.0, 1000.0)))
     }
     pub fn one() -> Self {
-        Self(R32::new(1.0))
+        Self(Scalar::new(1.0))
     }
     pub fn min_value() -> Self {
-        Self(R32::new(0.0))
+        Self(Scalar::new(0.0))
     }
     pub fn max_value() -> Self {
-        Self(R32::new(std::f32::MAX))
+        Self(Scalar::new(RawScalar::MAX))
     }
 }
 
-pub struct Mole(pub R32);
+pub struct Mole(pub Scalar);
 macros::unit!(Mole: "mol");
 
-pub struct Candela(pub R32);
+pub struct Candela(pub Scalar);
 macros::unit!(Candela: "cd");